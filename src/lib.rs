@@ -28,11 +28,19 @@ pub mod console;
 /// Safe routines for dealing with files and handles on Windows.
 #[cfg(windows)]
 pub mod file;
+/// Safe routines for querying the NUMA nodes and processor groups of the
+/// system.
+#[cfg(windows)]
+pub mod numa;
 #[cfg(all(windows, feature = "open_process"))]
 /// Safe wrappers around [`OpenProcess`] function and the resulting handle.
 ///
 /// [`OpenProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-openprocess
 pub mod open_process;
+/// Re-exports of the most commonly used process handle types, traits and
+/// functions.
+#[cfg(all(windows, feature = "open_process"))]
+pub mod prelude;
 #[cfg(windows)]
 /// Safe routines for querying various Windows specific properties.
 pub mod sysinfo;
@@ -0,0 +1,167 @@
+use std::io;
+
+use winapi::{
+    shared::ntdef::ULONG,
+    um::{
+        systemtopologyapi::{
+            GetNumaHighestNodeNumber, GetNumaNodeProcessorMaskEx,
+        },
+        winbase::{
+            GetActiveProcessorCount, GetActiveProcessorGroupCount,
+            GetNumaAvailableMemoryNodeEx, GetNumaProcessorNodeEx,
+        },
+        winnt::{GROUP_AFFINITY, PROCESSOR_NUMBER},
+    },
+};
+
+/// A set of logical processors within a single processor group.
+///
+/// Windows splits machines with more than 64 logical processors into
+/// groups of at most 64, and affinity masks only ever address one group.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct GroupAffinity {
+    group: u16,
+    mask: usize,
+}
+
+impl GroupAffinity {
+    /// Creates a set of processors in the given group, with bit `i` of
+    /// `mask` selecting the processor with number `i`.
+    pub fn new(group: u16, mask: usize) -> GroupAffinity {
+        GroupAffinity { group, mask }
+    }
+
+    /// Returns the processor group.
+    pub fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Returns the mask of processors within the group.
+    pub fn mask(&self) -> usize {
+        self.mask
+    }
+
+    pub(crate) fn from_raw(raw: &GROUP_AFFINITY) -> GroupAffinity {
+        GroupAffinity { group: raw.Group, mask: raw.Mask }
+    }
+
+    pub(crate) fn to_raw(self) -> GROUP_AFFINITY {
+        GROUP_AFFINITY { Mask: self.mask, Group: self.group, Reserved: [0; 3] }
+    }
+}
+
+/// Returns the number of active processor groups in the system. This is 1
+/// on machines with at most 64 logical processors.
+///
+/// This corresponds to calling [`GetActiveProcessorGroupCount`].
+///
+/// [`GetActiveProcessorGroupCount`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getactiveprocessorgroupcount
+pub fn processor_group_count() -> u16 {
+    unsafe { GetActiveProcessorGroupCount() }
+}
+
+/// Returns the active logical processors of every processor group, which
+/// together cover all processors of the system.
+///
+/// This corresponds to calling [`GetActiveProcessorCount`] for every
+/// group.
+///
+/// [`GetActiveProcessorCount`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getactiveprocessorcount
+pub fn processor_groups() -> Vec<GroupAffinity> {
+    (0..processor_group_count())
+        .map(|group| {
+            let count = unsafe { GetActiveProcessorCount(group) };
+            let mask = if count as usize >= usize::BITS as usize {
+                usize::MAX
+            } else {
+                (1usize << count) - 1
+            };
+            GroupAffinity::new(group, mask)
+        })
+        .collect()
+}
+
+/// Returns the number of NUMA nodes in the system. This is 1 on machines
+/// that are not NUMA machines.
+///
+/// Node numbers range from zero to one less than the returned value, but
+/// some nodes in that range may not have any processors.
+///
+/// This corresponds to calling [`GetNumaHighestNodeNumber`].
+///
+/// [`GetNumaHighestNodeNumber`]: https://learn.microsoft.com/en-us/windows/win32/api/systemtopologyapi/nf-systemtopologyapi-getnumahighestnodenumber
+pub fn node_count() -> io::Result<u32> {
+    let mut highest: ULONG = 0;
+    if unsafe { GetNumaHighestNodeNumber(&mut highest) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(highest + 1)
+}
+
+/// Returns the processors of the given NUMA node.
+///
+/// This corresponds to calling [`GetNumaNodeProcessorMaskEx`].
+///
+/// [`GetNumaNodeProcessorMaskEx`]: https://learn.microsoft.com/en-us/windows/win32/api/systemtopologyapi/nf-systemtopologyapi-getnumanodeprocessormaskex
+pub fn node_processors(node: u16) -> io::Result<GroupAffinity> {
+    let mut affinity: GROUP_AFFINITY = unsafe { core::mem::zeroed() };
+    if unsafe { GetNumaNodeProcessorMaskEx(node, &mut affinity) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(GroupAffinity::from_raw(&affinity))
+}
+
+/// Returns the amount of memory available on the given NUMA node, in
+/// bytes.
+///
+/// This corresponds to calling [`GetNumaAvailableMemoryNodeEx`].
+///
+/// [`GetNumaAvailableMemoryNodeEx`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getnumaavailablememorynodeex
+pub fn available_memory(node: u16) -> io::Result<u64> {
+    let mut bytes = 0;
+    if unsafe { GetNumaAvailableMemoryNodeEx(node, &mut bytes) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(bytes)
+}
+
+/// Returns the NUMA node of the logical processor with the given number
+/// within the given processor group.
+///
+/// This corresponds to calling [`GetNumaProcessorNodeEx`].
+///
+/// [`GetNumaProcessorNodeEx`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getnumaprocessornodeex
+pub fn processor_node(group: u16, number: u8) -> io::Result<u16> {
+    let mut processor =
+        PROCESSOR_NUMBER { Group: group, Number: number, Reserved: 0 };
+    let mut node = 0;
+    if unsafe { GetNumaProcessorNodeEx(&mut processor, &mut node) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_processor_belongs_to_a_node() {
+        let count = node_count().unwrap();
+        let node = processor_node(0, 0).unwrap();
+        assert!(u32::from(node) < count);
+        let processors = node_processors(node).unwrap();
+        assert_eq!(processors.group(), 0);
+        assert_eq!(processors.mask() & 1, 1);
+        assert!(available_memory(node).unwrap() > 0);
+    }
+
+    #[test]
+    fn processor_groups_cover_all_processors() {
+        let groups = processor_groups();
+        assert_eq!(groups.len(), usize::from(processor_group_count()));
+        let total: u32 = groups.iter().map(|g| g.mask().count_ones()).sum();
+        let expected = std::thread::available_parallelism().unwrap().get();
+        assert!(total as usize >= expected);
+    }
+}
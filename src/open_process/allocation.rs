@@ -0,0 +1,364 @@
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem, ptr,
+};
+
+use winapi::{
+    shared::{
+        minwindef::DWORD,
+        winerror::{ERROR_INVALID_PARAMETER, ERROR_PARTIAL_COPY},
+    },
+    um::{
+        errhandlingapi::SetLastError,
+        memoryapi::{
+            VirtualAllocEx, VirtualAllocExNuma, VirtualFreeEx,
+            VirtualProtectEx,
+        },
+        winnt::{MEM_COMMIT, MEM_RELEASE, MEM_RESERVE},
+    },
+};
+
+use super::{
+    Error, HasProcessVmOperation, HasProcessVmRead, HasProcessVmWrite,
+    ProcessHandle,
+};
+
+/// Memory allocated in another process, which is freed when this is
+/// dropped.
+///
+/// See `ProcessHandle::alloc`.
+pub struct RemoteAllocation<'a, M: HasProcessVmOperation> {
+    process: &'a ProcessHandle<M>,
+    address: usize,
+    size: usize,
+}
+
+impl<M: HasProcessVmOperation> RemoteAllocation<'_, M> {
+    /// Returns the address of the memory in the process.
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the size of the memory that was requested, in bytes. The
+    /// allocation itself is rounded up to whole pages.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Gives up the ownership of the memory without freeing it, and returns
+    /// its address, e.g. because code in the process keeps using it.
+    pub fn leak(self) -> usize {
+        let address = self.address;
+        mem::forget(self);
+        address
+    }
+
+    /// Frees the memory, like dropping it does, but reports whether that
+    /// succeeded.
+    ///
+    /// This corresponds to calling [`VirtualFreeEx`] with `MEM_RELEASE`.
+    ///
+    /// [`VirtualFreeEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualfreeex
+    pub fn free(self) -> Result<(), Error> {
+        let ok = self.release();
+        mem::forget(self);
+        if !ok {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+
+    fn release(&self) -> bool {
+        let ok = unsafe {
+            VirtualFreeEx(
+                self.process.inner.as_ptr(),
+                self.address as *mut _,
+                0,
+                MEM_RELEASE,
+            )
+        };
+        ok != 0
+    }
+
+    /// Returns the address `offset` bytes into the memory, or sets the last
+    /// error if `len` bytes from there do not fit into it.
+    fn range(&self, offset: usize, len: usize) -> Result<usize, Error> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.size => Ok(self.address + offset),
+            _ => {
+                unsafe { SetLastError(ERROR_INVALID_PARAMETER) };
+                Err(Error(PhantomData))
+            }
+        }
+    }
+}
+
+impl<M> RemoteAllocation<'_, M>
+where
+    M: HasProcessVmOperation + HasProcessVmWrite,
+{
+    /// Writes bytes to the memory, starting `offset` bytes into it.
+    ///
+    /// This fails with `ERROR_INVALID_PARAMETER` if the bytes do not fit
+    /// into the memory.
+    pub fn write(&self, offset: usize, data: &[u8]) -> Result<(), Error> {
+        let address = self.range(offset, data.len())?;
+        if self.process.write_memory_slice(address, data, false)? != data.len()
+        {
+            unsafe { SetLastError(ERROR_PARTIAL_COPY) };
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+impl<M> RemoteAllocation<'_, M>
+where
+    M: HasProcessVmOperation + HasProcessVmRead,
+{
+    /// Reads bytes from the memory into a buffer, starting `offset` bytes
+    /// into it.
+    ///
+    /// This fails with `ERROR_INVALID_PARAMETER` if the buffer does not fit
+    /// into the memory.
+    pub fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), Error> {
+        let address = self.range(offset, buf.len())?;
+        if self.process.read_memory_slice(address, buf)? != buf.len() {
+            unsafe { SetLastError(ERROR_PARTIAL_COPY) };
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+impl<M: HasProcessVmOperation> Drop for RemoteAllocation<'_, M> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+impl<M: HasProcessVmOperation> Debug for RemoteAllocation<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteAllocation")
+            .field("address", &self.address)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// A change of the protection of memory in another process, which is
+/// undone when this is dropped.
+///
+/// See `ProcessHandle::protect`.
+pub struct ProtectionGuard<'a, M: HasProcessVmOperation> {
+    process: &'a ProcessHandle<M>,
+    address: usize,
+    size: usize,
+    old_protection: DWORD,
+}
+
+impl<M: HasProcessVmOperation> ProtectionGuard<'_, M> {
+    /// Returns the protection that the memory had before, and that is
+    /// restored when the guard is dropped. If the range spanned pages with
+    /// different protections, this is the protection of the first page.
+    pub fn old_protection(&self) -> DWORD {
+        self.old_protection
+    }
+
+    /// Keeps the new protection instead of restoring the old one.
+    pub fn keep(self) {
+        mem::forget(self);
+    }
+}
+
+impl<M: HasProcessVmOperation> Drop for ProtectionGuard<'_, M> {
+    fn drop(&mut self) {
+        let mut previous = 0;
+        unsafe {
+            VirtualProtectEx(
+                self.process.inner.as_ptr(),
+                self.address as *mut _,
+                self.size,
+                self.old_protection,
+                &mut previous,
+            )
+        };
+    }
+}
+
+impl<M: HasProcessVmOperation> Debug for ProtectionGuard<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtectionGuard")
+            .field("address", &self.address)
+            .field("size", &self.size)
+            .field("old_protection", &self.old_protection)
+            .finish()
+    }
+}
+
+impl<M: HasProcessVmOperation> ProcessHandle<M> {
+    /// Allocates committed memory in the process with the given protection,
+    /// one of the `PAGE_*` constants such as `PAGE_READWRITE`.
+    ///
+    /// The memory is zeroed, and freed when the returned allocation is
+    /// dropped, unless it is leaked with [`RemoteAllocation::leak`].
+    ///
+    /// This corresponds to calling [`VirtualAllocEx`], and
+    /// [`VirtualFreeEx`] on drop.
+    ///
+    /// [`VirtualAllocEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualallocex
+    /// [`VirtualFreeEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualfreeex
+    pub fn alloc(
+        &self,
+        size: usize,
+        protection: DWORD,
+    ) -> Result<RemoteAllocation<'_, M>, Error> {
+        let () = M::ASSERT;
+        let address = unsafe {
+            VirtualAllocEx(
+                self.inner.as_ptr(),
+                ptr::null_mut(),
+                size,
+                MEM_COMMIT | MEM_RESERVE,
+                protection,
+            )
+        };
+        if address.is_null() {
+            return Err(Error(PhantomData));
+        }
+        Ok(RemoteAllocation { process: self, address: address as usize, size })
+    }
+
+    /// Allocates committed memory in the process like
+    /// `ProcessHandle::alloc`, preferably backed by physical memory of the
+    /// given NUMA node, e.g. the node of the processors that use it.
+    ///
+    /// The node is only a preference: when it runs out of memory, pages
+    /// come from other nodes instead.
+    ///
+    /// This corresponds to calling [`VirtualAllocExNuma`], and
+    /// [`VirtualFreeEx`] on drop.
+    ///
+    /// [`VirtualAllocExNuma`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualallocexnuma
+    /// [`VirtualFreeEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualfreeex
+    pub fn alloc_on_node(
+        &self,
+        size: usize,
+        protection: DWORD,
+        node: u32,
+    ) -> Result<RemoteAllocation<'_, M>, Error> {
+        let () = M::ASSERT;
+        let address = unsafe {
+            VirtualAllocExNuma(
+                self.inner.as_ptr(),
+                ptr::null_mut(),
+                size,
+                MEM_COMMIT | MEM_RESERVE,
+                protection,
+                node,
+            )
+        };
+        if address.is_null() {
+            return Err(Error(PhantomData));
+        }
+        Ok(RemoteAllocation { process: self, address: address as usize, size })
+    }
+
+    /// Changes the protection of the pages of the process that overlap the
+    /// given range to one of the `PAGE_*` constants, e.g. to make code
+    /// writable in order to patch it, until the returned guard is dropped.
+    ///
+    /// When the guard is dropped, all of the pages get the protection that
+    /// the first of them had before, so ranges that span pages with
+    /// different protections should be changed one region at a time.
+    ///
+    /// This corresponds to calling [`VirtualProtectEx`], once now and once
+    /// on drop.
+    ///
+    /// [`VirtualProtectEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualprotectex
+    pub fn protect(
+        &self,
+        address: usize,
+        size: usize,
+        protection: DWORD,
+    ) -> Result<ProtectionGuard<'_, M>, Error> {
+        let () = M::ASSERT;
+        let mut old_protection = 0;
+        let ok = unsafe {
+            VirtualProtectEx(
+                self.inner.as_ptr(),
+                address as *mut _,
+                size,
+                protection,
+                &mut old_protection,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(ProtectionGuard { process: self, address, size, old_protection })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::current_process;
+    use winapi::{
+        shared::winerror::ERROR_INVALID_PARAMETER,
+        um::winnt::{PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE},
+    };
+
+    #[test]
+    fn allocates_and_frees_memory() {
+        let process = current_process();
+        let allocation = process.alloc(100, PAGE_READWRITE).unwrap();
+        allocation.write(96, &[1, 2, 3, 4]).unwrap();
+        let mut buf = [0; 6];
+        allocation.read(94, &mut buf).unwrap();
+        assert_eq!(buf, [0, 0, 1, 2, 3, 4]);
+
+        let err = allocation.write(97, &[1, 2, 3, 4]).unwrap_err();
+        assert_eq!(err.code().as_dword(), ERROR_INVALID_PARAMETER);
+
+        // The address may be reused as soon as the memory is freed, so
+        // only the outcome of freeing it is checked.
+        allocation.free().unwrap();
+    }
+
+    #[test]
+    fn allocates_on_node() {
+        let process = current_process();
+        let allocation =
+            process.alloc_on_node(4096, PAGE_READWRITE, 0).unwrap();
+        allocation.write(0, &[1, 2, 3, 4]).unwrap();
+        let mut buf = [0; 4];
+        allocation.read(0, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+        assert_eq!(allocation.size(), 4096);
+    }
+
+    #[test]
+    fn restores_protection() {
+        let process = current_process();
+        let allocation = process.alloc(4096, PAGE_READONLY).unwrap();
+        let address = allocation.address();
+        let protection_at = |address| {
+            process
+                .memory_regions()
+                .map(Result::unwrap)
+                .find(|r| r.base_address() == address)
+                .unwrap()
+                .protection()
+        };
+
+        let guard = process.protect(address, 4096, PAGE_READWRITE).unwrap();
+        assert_eq!(guard.old_protection(), PAGE_READONLY);
+        assert_eq!(protection_at(address), PAGE_READWRITE);
+        drop(guard);
+        assert_eq!(protection_at(address), PAGE_READONLY);
+
+        process.protect(address, 4096, PAGE_NOACCESS).unwrap().keep();
+        assert_eq!(protection_at(address), PAGE_NOACCESS);
+    }
+}
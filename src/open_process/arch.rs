@@ -0,0 +1,218 @@
+use core::{ffi::c_void, marker::PhantomData, mem};
+use std::sync::OnceLock;
+
+use winapi::{
+    shared::minwindef::{BOOL, DWORD, FARPROC, USHORT},
+    um::{
+        libloaderapi::{GetModuleHandleW, GetProcAddress},
+        processthreadsapi::GetCurrentProcess,
+        winnt::{
+            HANDLE, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64,
+            IMAGE_FILE_MACHINE_ARMNT, IMAGE_FILE_MACHINE_I386,
+            IMAGE_FILE_MACHINE_UNKNOWN,
+        },
+        wow64apiset::IsWow64Process,
+    },
+};
+
+use super::{Error, HasProcessQueryLimitedInformation, ProcessHandle};
+
+/// The `ProcessMachineTypeInfo` information class, which winapi does not
+/// declare.
+const PROCESS_MACHINE_TYPE_INFO: DWORD = 9;
+
+/// `PROCESS_MACHINE_INFORMATION`, which winapi does not declare.
+#[repr(C)]
+struct ProcessMachineInformation {
+    process_machine: USHORT,
+    reserved: USHORT,
+    machine_attributes: DWORD,
+}
+
+/// `IMAGE_FILE_MACHINE_ARM64EC`, which winapi does not declare.
+const IMAGE_FILE_MACHINE_ARM64EC: USHORT = 0xa641;
+
+const GET_PROCESS_INFORMATION_NAME: &[u8] = b"GetProcessInformation\0";
+const IS_WOW64_PROCESS2_NAME: &[u8] = b"IsWow64Process2\0";
+
+type GetProcessInformationFn = unsafe extern "system" fn(
+    process: HANDLE,
+    information_class: DWORD,
+    information: *mut c_void,
+    information_size: DWORD,
+) -> BOOL;
+
+type IsWow64Process2Fn = unsafe extern "system" fn(
+    process: HANDLE,
+    process_machine: *mut USHORT,
+    native_machine: *mut USHORT,
+) -> BOOL;
+
+/// The instruction set that the code of a process is compiled for.
+///
+/// See `ProcessHandle::architecture`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProcessArch {
+    /// 32-bit x86.
+    X86,
+    /// 64-bit x86. This includes ARM64EC processes, whose images declare
+    /// the x64 machine type and which can load x64 code, so Windows reports
+    /// them as x64 processes.
+    X64,
+    /// 32-bit ARM.
+    Arm,
+    /// 64-bit ARM.
+    Arm64,
+    /// Another architecture, given by its `IMAGE_FILE_MACHINE_*` value.
+    Other(u16),
+}
+
+impl ProcessArch {
+    fn from_machine(machine: USHORT) -> ProcessArch {
+        match machine {
+            IMAGE_FILE_MACHINE_I386 => ProcessArch::X86,
+            IMAGE_FILE_MACHINE_AMD64 | IMAGE_FILE_MACHINE_ARM64EC => {
+                ProcessArch::X64
+            }
+            IMAGE_FILE_MACHINE_ARMNT => ProcessArch::Arm,
+            IMAGE_FILE_MACHINE_ARM64 => ProcessArch::Arm64,
+            machine => ProcessArch::Other(machine),
+        }
+    }
+
+    /// Returns the architecture of the current process.
+    pub(super) fn current() -> ProcessArch {
+        if cfg!(target_arch = "x86") {
+            ProcessArch::X86
+        } else if cfg!(any(target_arch = "x86_64", target_arch = "arm64ec")) {
+            ProcessArch::X64
+        } else if cfg!(target_arch = "arm") {
+            ProcessArch::Arm
+        } else {
+            ProcessArch::Arm64
+        }
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the architecture of the process, which tells how its memory
+    /// is laid out and which code can run in it.
+    ///
+    /// On Windows 11, this also tells x64 processes apart from ARM64 ones
+    /// when they are emulated on ARM64. On older versions, such processes
+    /// are reported as native ones.
+    ///
+    /// This corresponds to calling [`GetProcessInformation`] with
+    /// `ProcessMachineTypeInfo`, falling back to [`IsWow64Process2`] and
+    /// then to [`IsWow64Process`] on older versions of Windows. The first
+    /// two are looked up at runtime, so this also works on Windows 7, which
+    /// lacks both.
+    ///
+    /// [`GetProcessInformation`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocessinformation
+    /// [`IsWow64Process2`]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-iswow64process2
+    /// [`IsWow64Process`]: https://learn.microsoft.com/en-us/windows/win32/api/wow64apiset/nf-wow64apiset-iswow64process
+    pub fn architecture(&self) -> Result<ProcessArch, Error> {
+        let () = M::ASSERT;
+        let process = self.inner.as_ptr();
+        if let Some(get_process_information) = get_process_information_fn() {
+            let mut info = ProcessMachineInformation {
+                process_machine: 0,
+                reserved: 0,
+                machine_attributes: 0,
+            };
+            let ok = unsafe {
+                get_process_information(
+                    process,
+                    PROCESS_MACHINE_TYPE_INFO,
+                    (&mut info as *mut ProcessMachineInformation).cast(),
+                    mem::size_of::<ProcessMachineInformation>() as DWORD,
+                )
+            };
+            // The information class only exists on Windows 11.
+            if ok != 0 {
+                return Ok(ProcessArch::from_machine(info.process_machine));
+            }
+        }
+        if let Some(is_wow64_process2) = is_wow64_process2_fn() {
+            let (mut machine, mut native) = (0, 0);
+            let ok = unsafe {
+                is_wow64_process2(process, &mut machine, &mut native)
+            };
+            if ok == 0 {
+                return Err(Error(PhantomData));
+            }
+            // The process machine is only known for WOW64 processes.
+            if machine == IMAGE_FILE_MACHINE_UNKNOWN {
+                machine = native;
+            }
+            return Ok(ProcessArch::from_machine(machine));
+        }
+        // Before Windows 10, WOW64 only ran x86 code, and only on x64.
+        if is_wow64(process)? {
+            Ok(ProcessArch::X86)
+        } else if is_wow64(unsafe { GetCurrentProcess() })? {
+            Ok(ProcessArch::X64)
+        } else {
+            Ok(ProcessArch::current())
+        }
+    }
+}
+
+/// Returns true if the process runs under WOW64, i.e. is a 32-bit process
+/// on a 64-bit system.
+pub(super) fn is_wow64(process: HANDLE) -> Result<bool, Error> {
+    let mut wow64: BOOL = 0;
+    if unsafe { IsWow64Process(process, &mut wow64) } == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(wow64 != 0)
+}
+
+/// Returns `GetProcessInformation`, which is only available on Windows 8
+/// and newer.
+fn get_process_information_fn() -> Option<GetProcessInformationFn> {
+    static GET_PROCESS_INFORMATION: OnceLock<Option<GetProcessInformationFn>> =
+        OnceLock::new();
+    *GET_PROCESS_INFORMATION.get_or_init(|| unsafe {
+        let f = kernel32_export(GET_PROCESS_INFORMATION_NAME)?;
+        Some(mem::transmute::<FARPROC, GetProcessInformationFn>(f))
+    })
+}
+
+/// Returns `IsWow64Process2`, which is only available on Windows 10 and
+/// newer.
+fn is_wow64_process2_fn() -> Option<IsWow64Process2Fn> {
+    static IS_WOW64_PROCESS2: OnceLock<Option<IsWow64Process2Fn>> =
+        OnceLock::new();
+    *IS_WOW64_PROCESS2.get_or_init(|| unsafe {
+        let f = kernel32_export(IS_WOW64_PROCESS2_NAME)?;
+        Some(mem::transmute::<FARPROC, IsWow64Process2Fn>(f))
+    })
+}
+
+/// Looks up a function exported by `kernel32.dll` by its NUL terminated
+/// name.
+fn kernel32_export(name: &[u8]) -> Option<FARPROC> {
+    let module = super::to_wide("kernel32.dll".as_ref());
+    let module = unsafe { GetModuleHandleW(module.as_ptr()) };
+    if module.is_null() {
+        return None;
+    }
+    let f = unsafe { GetProcAddress(module, name.as_ptr().cast()) };
+    if f.is_null() {
+        return None;
+    }
+    Some(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+
+    #[test]
+    fn current_process_architecture() {
+        let arch = current_process().architecture().unwrap();
+        assert_eq!(arch, ProcessArch::current());
+    }
+}
@@ -0,0 +1,179 @@
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::Deref,
+    ptr::NonNull,
+};
+
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{
+        processthreadsapi::GetCurrentProcess,
+        winnt::{HANDLE, PROCESS_ALL_ACCESS},
+    },
+};
+
+use super::{ComptimeAccessRights, Handle, HandleMetadata, ProcessHandle};
+
+/// Returns a handle to the current process with all access rights.
+///
+/// The handle is a pseudo-handle, a special constant that always refers to
+/// the calling process. It never needs to be closed, but it is also only
+/// meaningful within the current process: use `Handle::try_clone` to get a
+/// real handle that can be passed to another process.
+///
+/// This corresponds to calling [`GetCurrentProcess`].
+///
+/// [`GetCurrentProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getcurrentprocess
+pub fn current_process(
+) -> ProcessHandleRef<'static, ComptimeAccessRights<PROCESS_ALL_ACCESS>> {
+    let handle = unsafe { GetCurrentProcess() };
+    let inner = NonNull::new(handle).expect("pseudo-handle is not null");
+    ProcessHandleRef::new(inner, PhantomData)
+}
+
+/// A borrowed handle to a process, which is never closed by this crate.
+///
+/// This dereferences to [`ProcessHandle`], so it offers the same methods,
+/// except for those that consume the handle. Functions that accept either
+/// an owned or a borrowed handle can take an `impl AsRef<ProcessHandle<M>>`,
+/// which both implement.
+pub struct ProcessHandleRef<'a, M: HandleMetadata> {
+    handle: ManuallyDrop<ProcessHandle<M>>,
+    borrow: PhantomData<&'a ProcessHandle<M>>,
+}
+
+impl<'a, M: HandleMetadata> ProcessHandleRef<'a, M> {
+    /// Borrows a raw process handle owned by someone else, e.g. one passed
+    /// to a callback or inherited from a parent process.
+    ///
+    /// `access` must be the access rights the handle was opened with. If the
+    /// handle is null, or if `M` is a [`ComptimeAccessRights`] whose rights
+    /// are not all contained in `access`, then `None` is returned.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `handle` is a valid process handle opened
+    /// with `access`, and that it stays open for the lifetime `'a`.
+    ///
+    pub unsafe fn from_raw(handle: HANDLE, access: DWORD) -> Option<Self> {
+        let inner = NonNull::new(handle)?;
+        let metadata = M::metadata_from_access(access)?;
+        Some(ProcessHandleRef::new(inner, metadata))
+    }
+
+    fn new(
+        inner: NonNull<core::ffi::c_void>,
+        metadata: M::StoredType,
+    ) -> Self {
+        // The handle is never dropped, so it is neither closed nor
+        // registered with the debugging facilities that track ownership.
+        let handle = Handle { phantom_kind: PhantomData, metadata, inner };
+        ProcessHandleRef {
+            handle: ManuallyDrop::new(handle),
+            borrow: PhantomData,
+        }
+    }
+}
+
+impl<M: HandleMetadata> ProcessHandle<M> {
+    /// Borrows this handle as a [`ProcessHandleRef`].
+    pub fn as_handle_ref(&self) -> ProcessHandleRef<'_, M> {
+        ProcessHandleRef::new(self.inner, self.metadata)
+    }
+}
+
+impl<'a, M: HandleMetadata> From<&'a ProcessHandle<M>>
+    for ProcessHandleRef<'a, M>
+{
+    fn from(handle: &'a ProcessHandle<M>) -> Self {
+        handle.as_handle_ref()
+    }
+}
+
+impl<'a, M: HandleMetadata> Clone for ProcessHandleRef<'a, M> {
+    fn clone(&self) -> Self {
+        ProcessHandleRef::new(self.handle.inner, self.handle.metadata)
+    }
+}
+
+impl<'a, M: HandleMetadata> Deref for ProcessHandleRef<'a, M> {
+    type Target = ProcessHandle<M>;
+
+    fn deref(&self) -> &ProcessHandle<M> {
+        &self.handle
+    }
+}
+
+impl<'a, M: HandleMetadata> AsRef<ProcessHandle<M>>
+    for ProcessHandleRef<'a, M>
+{
+    fn as_ref(&self) -> &ProcessHandle<M> {
+        &self.handle
+    }
+}
+
+impl<M: HandleMetadata> AsRef<ProcessHandle<M>> for ProcessHandle<M> {
+    fn as_ref(&self) -> &ProcessHandle<M> {
+        self
+    }
+}
+
+impl<'a, M: HandleMetadata> Debug for ProcessHandleRef<'a, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProcessHandleRef").field(&*self.handle).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{open_process, RuntimeAccessRights};
+    use super::*;
+    use winapi::um::{
+        processthreadsapi::GetCurrentProcess, winnt::PROCESS_ALL_ACCESS,
+    };
+
+    #[test]
+    fn borrow_pseudo_handle() {
+        // The pseudo-handle of the current process is not owned by anyone,
+        // so it can only be borrowed.
+        let handle = unsafe {
+            ProcessHandleRef::<RuntimeAccessRights>::from_raw(
+                GetCurrentProcess(),
+                PROCESS_ALL_ACCESS,
+            )
+        }
+        .unwrap();
+        assert_eq!(handle.clone().access_rights(), PROCESS_ALL_ACCESS);
+    }
+
+    #[test]
+    fn current_process_has_own_pid() {
+        let pid = std::process::id();
+        assert!(format!("{:?}", current_process())
+            .contains(&format!("pid: {pid}")));
+        // Cloning the pseudo-handle yields a real handle.
+        let owned = current_process().try_clone().unwrap();
+        assert!(format!("{:?}", owned).contains(&format!("pid: {pid}")));
+    }
+
+    #[test]
+    fn owned_and_borrowed_handles_share_methods() {
+        fn rights<H: AsRef<ProcessHandle<RuntimeAccessRights>>>(
+            h: H,
+        ) -> DWORD {
+            h.as_ref().access_rights()
+        }
+
+        let access = PROCESS_ALL_ACCESS;
+        let owned = open_process::<RuntimeAccessRights>(
+            access,
+            false,
+            std::process::id(),
+        )
+        .unwrap();
+        assert_eq!(rights(owned.as_handle_ref()), rights(&owned));
+        owned.try_close().unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+use winapi::shared::{minwindef::DWORD, winerror::ERROR_ACCESS_DENIED};
+
+use super::{
+    open_process, privileges, ComptimeAccessRights, Error, IntoAccessRights,
+    IntoProcessId, ProcessHandle, RuntimeAccessRights,
+};
+
+/// A builder for opening a process, as an alternative to calling
+/// [`open_process`] with positional arguments.
+///
+/// # Example
+///
+/// ```no_run
+/// use winapi::um::winnt::{PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+/// use winapi_util::open_process::OpenProcess;
+///
+/// let handle = OpenProcess::new(std::process::id())
+///     .access(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION)
+///     .inherit(false)
+///     .with_debug_privilege()
+///     .open()
+///     .unwrap();
+/// ```
+pub struct OpenProcess<R: IntoAccessRights = RuntimeAccessRights> {
+    process_id: DWORD,
+    access: R::RuntimeArgumentType,
+    fallback_access: Option<R::RuntimeArgumentType>,
+    inherit: bool,
+    debug_privilege: bool,
+}
+
+impl OpenProcess<RuntimeAccessRights> {
+    /// Starts building a call that opens the given process, without any
+    /// access rights so far.
+    pub fn new(process_id: impl IntoProcessId) -> Self {
+        OpenProcess {
+            process_id: process_id.into_process_id(),
+            access: 0,
+            fallback_access: None,
+            inherit: false,
+            debug_privilege: false,
+        }
+    }
+
+    /// Sets the access rights to request, which are only known at runtime.
+    pub fn access(mut self, access: DWORD) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Sets the access rights to request to ones known at compile time, so
+    /// that the opened handle has [`ComptimeAccessRights`] and the methods
+    /// requiring those rights can be called without runtime checks.
+    ///
+    /// This discards any access rights given to [`OpenProcess::access`] or
+    /// [`OpenProcess::fallback_access`].
+    ///
+    /// ```no_run
+    /// use winapi::um::winnt::{PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+    /// use winapi_util::open_process::OpenProcess;
+    ///
+    /// let handle = OpenProcess::new(std::process::id())
+    ///     .rights::<{ PROCESS_VM_READ | PROCESS_QUERY_INFORMATION }>()
+    ///     .open()
+    ///     .unwrap();
+    /// let regions = handle.find_unbacked_executable_regions().unwrap();
+    /// ```
+    pub fn rights<const N: DWORD>(
+        self,
+    ) -> OpenProcess<ComptimeAccessRights<N>> {
+        OpenProcess {
+            process_id: self.process_id,
+            access: PhantomData,
+            fallback_access: None,
+            inherit: self.inherit,
+            debug_privilege: self.debug_privilege,
+        }
+    }
+
+    /// Sets the access rights to request instead if opening the process
+    /// with the rights given to [`OpenProcess::access`] fails with
+    /// `ERROR_ACCESS_DENIED`, e.g. `PROCESS_QUERY_LIMITED_INFORMATION` to
+    /// still get basic information about protected processes.
+    ///
+    /// The rights of the returned handle reflect the rights it was actually
+    /// opened with.
+    pub fn fallback_access(mut self, access: DWORD) -> Self {
+        self.fallback_access = Some(access);
+        self
+    }
+}
+
+impl<R: IntoAccessRights> OpenProcess<R> {
+    /// Sets whether processes created by the current process inherit the
+    /// handle. Defaults to false.
+    pub fn inherit(mut self, yes: bool) -> Self {
+        self.inherit = yes;
+        self
+    }
+
+    /// Enables `SeDebugPrivilege` for the current process before opening
+    /// the process, which allows opening any process regardless of its
+    /// security descriptor.
+    ///
+    /// The privilege must be held by the user, which usually means running
+    /// elevated as an administrator. Once enabled, it stays enabled for the
+    /// rest of the lifetime of the current process.
+    pub fn with_debug_privilege(mut self) -> Self {
+        self.debug_privilege = true;
+        self
+    }
+
+    /// Opens the process.
+    ///
+    /// If `SeDebugPrivilege` was requested but could not be enabled, then
+    /// an error is returned without attempting to open the process.
+    pub fn open(self) -> Result<ProcessHandle<R::AccessRightsType>, Error> {
+        if self.debug_privilege {
+            privileges::enable_debug_privilege()?;
+        }
+        let result =
+            open_process::<R>(self.access, self.inherit, self.process_id);
+        match (result, self.fallback_access) {
+            (Err(err), Some(fallback))
+                if err.code().as_dword() == ERROR_ACCESS_DENIED =>
+            {
+                open_process::<R>(fallback, self.inherit, self.process_id)
+            }
+            (result, _) => result,
+        }
+    }
+}
+
+impl<R: IntoAccessRights> Debug for OpenProcess<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenProcess")
+            .field("process_id", &self.process_id)
+            .field(
+                "access",
+                &format_args!("{:#x}", R::rt_arg_to_dword(self.access)),
+            )
+            .field(
+                "fallback_access",
+                &self.fallback_access.map(|access| {
+                    format!("{:#x}", R::rt_arg_to_dword(access))
+                }),
+            )
+            .field("inherit", &self.inherit)
+            .field("debug_privilege", &self.debug_privilege)
+            .finish()
+    }
+}
+
+impl<R: IntoAccessRights> Clone for OpenProcess<R> {
+    fn clone(&self) -> Self {
+        OpenProcess { ..*self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::to_wide;
+    use core::ptr;
+    use std::{os::windows::io::AsRawHandle, process::Command};
+    use winapi::{
+        shared::sddl::{
+            ConvertStringSecurityDescriptorToSecurityDescriptorW,
+            SDDL_REVISION_1,
+        },
+        um::{
+            securitybaseapi::SetKernelObjectSecurity,
+            winbase::LocalFree,
+            winnt::{
+                DACL_SECURITY_INFORMATION, PROCESS_ALL_ACCESS,
+                PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+        },
+    };
+
+    #[test]
+    fn builder_opens_current_process() {
+        let handle = OpenProcess::new(std::process::id())
+            .access(PROCESS_QUERY_LIMITED_INFORMATION)
+            .inherit(true)
+            .open()
+            .unwrap();
+        assert_eq!(handle.access_rights(), PROCESS_QUERY_LIMITED_INFORMATION);
+    }
+
+    #[test]
+    fn builder_opens_with_comptime_rights() {
+        let handle = OpenProcess::new(std::process::id())
+            .access(PROCESS_ALL_ACCESS)
+            .rights::<PROCESS_QUERY_LIMITED_INFORMATION>()
+            .open()
+            .unwrap();
+        assert_eq!(handle.pid().unwrap().as_raw(), std::process::id());
+        assert_eq!(handle.access_rights(), PROCESS_QUERY_LIMITED_INFORMATION);
+    }
+
+    #[test]
+    fn fallback_access_is_used_when_denied() {
+        let mut child = Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .spawn()
+            .unwrap();
+        // Only allow everyone to query limited information about the child,
+        // so that opening it with all access rights is denied.
+        let sddl = to_wide("D:(A;;0x1000;;;WD)".as_ref());
+        let mut descriptor = ptr::null_mut();
+        let ok = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                SDDL_REVISION_1.into(),
+                &mut descriptor,
+                ptr::null_mut(),
+            )
+        };
+        assert_ne!(ok, 0);
+        let ok = unsafe {
+            SetKernelObjectSecurity(
+                child.as_raw_handle().cast(),
+                DACL_SECURITY_INFORMATION,
+                descriptor,
+            )
+        };
+        unsafe { LocalFree(descriptor) };
+
+        let handle = OpenProcess::new(&child)
+            .access(PROCESS_ALL_ACCESS)
+            .fallback_access(PROCESS_QUERY_LIMITED_INFORMATION)
+            .open();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_ne!(ok, 0);
+        assert_eq!(
+            handle.unwrap().access_rights(),
+            PROCESS_QUERY_LIMITED_INFORMATION
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem,
+};
+
+#[cfg(target_pointer_width = "64")]
+use winapi::um::{
+    processthreadsapi::GetProcessIdOfThread,
+    winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+};
+#[cfg(target_pointer_width = "64")]
+use winapi::um::{
+    winbase::{Wow64GetThreadContext, Wow64SetThreadContext},
+    winnt::{WOW64_CONTEXT_i386, WOW64_CONTEXT},
+};
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{
+        processthreadsapi::{GetThreadContext, SetThreadContext},
+        winnt::CONTEXT,
+    },
+};
+
+#[cfg(target_pointer_width = "64")]
+use super::{arch::is_wow64, open_process};
+use super::{
+    Error, HasThreadGetContext, HasThreadQueryLimitedInformation,
+    HasThreadSetContext, ThreadHandle,
+};
+
+// winapi declares `CONTEXT` without the 16-byte alignment that
+// `GetThreadContext` requires on 64-bit systems.
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct AlignedContext(CONTEXT);
+
+#[derive(Clone)]
+enum ContextKind {
+    Native(Box<AlignedContext>),
+    #[cfg(target_pointer_width = "64")]
+    Wow64(Box<WOW64_CONTEXT>),
+}
+
+/// The registers of a thread, as returned by `ThreadHandle::context`.
+///
+/// Threads of 32-bit processes running under WOW64 have an x86 context,
+/// which is available with [`Context::wow64`]. The threads of all other
+/// processes have the context of the architecture of the current process,
+/// which is available with [`Context::native`].
+#[derive(Clone)]
+pub struct Context(ContextKind);
+
+impl Context {
+    /// Returns the context if it has the architecture of the current
+    /// process.
+    pub fn native(&self) -> Option<&CONTEXT> {
+        match &self.0 {
+            ContextKind::Native(context) => Some(&context.0),
+            #[cfg(target_pointer_width = "64")]
+            ContextKind::Wow64(_) => None,
+        }
+    }
+
+    /// Returns the context if it has the architecture of the current
+    /// process, for changing its registers.
+    pub fn native_mut(&mut self) -> Option<&mut CONTEXT> {
+        match &mut self.0 {
+            ContextKind::Native(context) => Some(&mut context.0),
+            #[cfg(target_pointer_width = "64")]
+            ContextKind::Wow64(_) => None,
+        }
+    }
+
+    /// Returns the context if it is that of a thread of a WOW64 process.
+    #[cfg(target_pointer_width = "64")]
+    pub fn wow64(&self) -> Option<&WOW64_CONTEXT> {
+        match &self.0 {
+            ContextKind::Native(_) => None,
+            ContextKind::Wow64(context) => Some(context),
+        }
+    }
+
+    /// Returns the context if it is that of a thread of a WOW64 process,
+    /// for changing its registers.
+    #[cfg(target_pointer_width = "64")]
+    pub fn wow64_mut(&mut self) -> Option<&mut WOW64_CONTEXT> {
+        match &mut self.0 {
+            ContextKind::Native(_) => None,
+            ContextKind::Wow64(context) => Some(context),
+        }
+    }
+
+    /// Returns the `CONTEXT_*` or `WOW64_CONTEXT_*` flags of the registers
+    /// that the context holds.
+    pub fn flags(&self) -> DWORD {
+        match &self.0 {
+            ContextKind::Native(context) => context.0.ContextFlags,
+            #[cfg(target_pointer_width = "64")]
+            ContextKind::Wow64(context) => context.ContextFlags,
+        }
+    }
+
+    /// Returns the address of the next instruction of the thread. This is
+    /// only set if the context holds the `CONTEXT_CONTROL` registers.
+    pub fn instruction_pointer(&self) -> u64 {
+        match &self.0 {
+            ContextKind::Native(context) => native_registers(&context.0).0,
+            #[cfg(target_pointer_width = "64")]
+            ContextKind::Wow64(context) => context.Eip.into(),
+        }
+    }
+
+    /// Returns the stack pointer of the thread. This is only set if the
+    /// context holds the `CONTEXT_CONTROL` registers.
+    pub fn stack_pointer(&self) -> u64 {
+        match &self.0 {
+            ContextKind::Native(context) => native_registers(&context.0).1,
+            #[cfg(target_pointer_width = "64")]
+            ContextKind::Wow64(context) => context.Esp.into(),
+        }
+    }
+}
+
+impl Debug for Context {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("wow64", &self.native().is_none())
+            .field("flags", &self.flags())
+            .field("instruction_pointer", &self.instruction_pointer())
+            .field("stack_pointer", &self.stack_pointer())
+            .finish()
+    }
+}
+
+/// Returns the instruction and stack pointers of a native context.
+#[cfg(target_arch = "x86_64")]
+fn native_registers(context: &CONTEXT) -> (u64, u64) {
+    (context.Rip, context.Rsp)
+}
+
+/// Returns the instruction and stack pointers of a native context.
+#[cfg(target_arch = "x86")]
+fn native_registers(context: &CONTEXT) -> (u64, u64) {
+    (context.Eip.into(), context.Esp.into())
+}
+
+/// Returns the instruction and stack pointers of a native context.
+#[cfg(any(target_arch = "aarch64", target_arch = "arm"))]
+#[allow(clippy::useless_conversion)]
+fn native_registers(context: &CONTEXT) -> (u64, u64) {
+    (context.Pc.into(), context.Sp.into())
+}
+
+impl<M> ThreadHandle<M>
+where
+    M: HasThreadGetContext + HasThreadQueryLimitedInformation,
+{
+    /// Returns the registers of the thread that are selected by `flags`,
+    /// a combination of the `CONTEXT_*` constants such as `CONTEXT_FULL`.
+    ///
+    /// The thread should be suspended, e.g. with `ThreadHandle::suspend`,
+    /// since the registers of a running thread are stale by the time they
+    /// are returned. If the thread belongs to a WOW64 process, its x86
+    /// registers are returned instead, and `flags` are translated to the
+    /// matching `WOW64_CONTEXT_*` ones.
+    ///
+    /// This corresponds to calling [`GetThreadContext`], or
+    /// [`Wow64GetThreadContext`] for threads of WOW64 processes.
+    ///
+    /// [`GetThreadContext`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadcontext
+    /// [`Wow64GetThreadContext`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-wow64getthreadcontext
+    pub fn context(&self, flags: DWORD) -> Result<Context, Error> {
+        let () = <M as HasThreadGetContext>::ASSERT;
+        let () = <M as HasThreadQueryLimitedInformation>::ASSERT;
+        let handle = self.inner.as_ptr();
+        #[cfg(target_pointer_width = "64")]
+        if self.belongs_to_wow64()? {
+            let mut context: Box<WOW64_CONTEXT> =
+                Box::new(unsafe { mem::zeroed() });
+            // The low bits select the same registers on all architectures.
+            context.ContextFlags = (flags & 0xffff) | WOW64_CONTEXT_i386;
+            if unsafe { Wow64GetThreadContext(handle, &mut *context) } == 0 {
+                return Err(Error(PhantomData));
+            }
+            return Ok(Context(ContextKind::Wow64(context)));
+        }
+        let mut context = Box::new(AlignedContext(unsafe { mem::zeroed() }));
+        context.0.ContextFlags = flags;
+        if unsafe { GetThreadContext(handle, &mut context.0) } == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(Context(ContextKind::Native(context)))
+    }
+
+    /// Returns true if the thread belongs to a WOW64 process.
+    #[cfg(target_pointer_width = "64")]
+    fn belongs_to_wow64(&self) -> Result<bool, Error> {
+        let pid = unsafe { GetProcessIdOfThread(self.inner.as_ptr()) };
+        if pid == 0 {
+            return Err(Error(PhantomData));
+        }
+        let process = open_process::<
+            crate::access_rights!(PROCESS_QUERY_LIMITED_INFORMATION),
+        >(PhantomData, false, pid)?;
+        is_wow64(process.inner.as_ptr())
+    }
+}
+
+impl<M: HasThreadSetContext> ThreadHandle<M> {
+    /// Sets the registers of the thread that are selected by the flags of
+    /// the context, which is usually one returned by
+    /// `ThreadHandle::context` and then changed.
+    ///
+    /// This corresponds to calling [`SetThreadContext`], or
+    /// [`Wow64SetThreadContext`] for WOW64 contexts.
+    ///
+    /// # Safety
+    ///
+    /// Changing the registers of a thread, e.g. its instruction pointer,
+    /// can make it run arbitrary code, so the caller must make sure that
+    /// the thread can continue with the new registers.
+    ///
+    /// [`SetThreadContext`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setthreadcontext
+    /// [`Wow64SetThreadContext`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-wow64setthreadcontext
+    pub unsafe fn set_context(&self, context: &Context) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let handle = self.inner.as_ptr();
+        let ok = match &context.0 {
+            ContextKind::Native(context) => {
+                SetThreadContext(handle, &context.0)
+            }
+            #[cfg(target_pointer_width = "64")]
+            ContextKind::Wow64(context) => {
+                Wow64SetThreadContext(handle, &**context)
+            }
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::open_thread;
+    use winapi::um::{
+        processthreadsapi::GetCurrentThreadId,
+        winnt::{
+            CONTEXT_CONTROL, CONTEXT_INTEGER, THREAD_GET_CONTEXT,
+            THREAD_QUERY_LIMITED_INFORMATION, THREAD_SET_CONTEXT,
+            THREAD_SUSPEND_RESUME,
+        },
+    };
+
+    #[test]
+    fn reads_and_writes_context_of_suspended_thread() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            sender.send(unsafe { GetCurrentThreadId() }).unwrap();
+            let _ = stopped.recv();
+        });
+        let tid = receiver.recv().unwrap();
+        let handle = open_thread::<
+            crate::access_rights!(
+                THREAD_GET_CONTEXT,
+                THREAD_SET_CONTEXT,
+                THREAD_SUSPEND_RESUME,
+                THREAD_QUERY_LIMITED_INFORMATION
+            ),
+        >(PhantomData, false, tid)
+        .unwrap();
+
+        let guard = handle.suspend_guard().unwrap();
+        let context = handle.context(CONTEXT_CONTROL | CONTEXT_INTEGER);
+        let restored = context
+            .as_ref()
+            .ok()
+            .map(|context| unsafe { handle.set_context(context) });
+        drop(guard);
+        stop.send(()).unwrap();
+        worker.join().unwrap();
+
+        let context = context.unwrap();
+        restored.unwrap().unwrap();
+        assert!(context.native().is_some());
+        assert_eq!(context.flags() & CONTEXT_CONTROL, CONTEXT_CONTROL);
+        assert_ne!(context.instruction_pointer(), 0);
+        assert_ne!(context.stack_pointer(), 0);
+    }
+}
@@ -0,0 +1,264 @@
+use core::{marker::PhantomData, mem};
+
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        processthreadsapi::GetProcessHandleCount,
+        psapi::{
+            GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS,
+            PROCESS_MEMORY_COUNTERS_EX,
+        },
+        winbase::GetProcessIoCounters,
+        winnt::{HANDLE, IO_COUNTERS},
+    },
+};
+
+use super::{Error, HasProcessQueryLimitedInformation, ProcessHandle};
+
+// winapi does not declare GetGuiResources.
+#[link(name = "user32")]
+extern "system" {
+    fn GetGuiResources(hProcess: HANDLE, uiFlags: DWORD) -> DWORD;
+}
+
+/// A kind of GUI object counted by `ProcessHandle::gui_resources`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GuiResource {
+    /// The GDI objects currently in use, such as bitmaps, brushes and
+    /// fonts.
+    Gdi,
+    /// The largest number of GDI objects that have been in use at once.
+    GdiPeak,
+    /// The USER objects currently in use, such as windows, menus and
+    /// cursors.
+    User,
+    /// The largest number of USER objects that have been in use at once.
+    UserPeak,
+}
+
+impl GuiResource {
+    fn to_raw(self) -> DWORD {
+        // GR_GDIOBJECTS, GR_GDIOBJECTS_PEAK, GR_USEROBJECTS and
+        // GR_USEROBJECTS_PEAK.
+        match self {
+            GuiResource::Gdi => 0,
+            GuiResource::GdiPeak => 2,
+            GuiResource::User => 1,
+            GuiResource::UserPeak => 4,
+        }
+    }
+}
+
+/// The memory usage of a process, returned by `ProcessHandle::memory_info`.
+///
+/// All sizes are in bytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MemoryCounters {
+    working_set: usize,
+    peak_working_set: usize,
+    private_bytes: usize,
+    page_fault_count: u32,
+}
+
+impl MemoryCounters {
+    /// Returns the size of the working set, i.e. of the pages of the
+    /// process that are currently resident in physical memory.
+    pub fn working_set(&self) -> usize {
+        self.working_set
+    }
+
+    /// Returns the largest size the working set has had.
+    pub fn peak_working_set(&self) -> usize {
+        self.peak_working_set
+    }
+
+    /// Returns the amount of memory that the process has committed and
+    /// that cannot be shared with other processes, which is what Task
+    /// Manager shows as its commit size.
+    pub fn private_bytes(&self) -> usize {
+        self.private_bytes
+    }
+
+    /// Returns the number of page faults the process has caused, including
+    /// soft faults that were resolved without reading from disk.
+    pub fn page_fault_count(&self) -> u32 {
+        self.page_fault_count
+    }
+}
+
+/// The I/O performed by a process since it started, returned by
+/// `ProcessHandle::io_counters`.
+///
+/// The counters cover all I/O, including to files, devices and pipes.
+/// Operations that are neither reads nor writes, such as device control
+/// requests, are counted as other operations.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct IoCounters {
+    read_operations: u64,
+    write_operations: u64,
+    other_operations: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+    other_bytes: u64,
+}
+
+impl IoCounters {
+    /// Returns the number of read operations.
+    pub fn read_operations(&self) -> u64 {
+        self.read_operations
+    }
+
+    /// Returns the number of write operations.
+    pub fn write_operations(&self) -> u64 {
+        self.write_operations
+    }
+
+    /// Returns the number of other operations.
+    pub fn other_operations(&self) -> u64 {
+        self.other_operations
+    }
+
+    /// Returns the number of bytes read.
+    pub fn read_bytes(&self) -> u64 {
+        self.read_bytes
+    }
+
+    /// Returns the number of bytes written.
+    pub fn write_bytes(&self) -> u64 {
+        self.write_bytes
+    }
+
+    /// Returns the number of bytes transferred by other operations.
+    pub fn other_bytes(&self) -> u64 {
+        self.other_bytes
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the memory usage of the process.
+    ///
+    /// This corresponds to calling [`GetProcessMemoryInfo`] with
+    /// `PROCESS_MEMORY_COUNTERS_EX`.
+    ///
+    /// [`GetProcessMemoryInfo`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getprocessmemoryinfo
+    pub fn memory_info(&self) -> Result<MemoryCounters, Error> {
+        let () = M::ASSERT;
+        let mut counters: PROCESS_MEMORY_COUNTERS_EX =
+            unsafe { mem::zeroed() };
+        let size = mem::size_of::<PROCESS_MEMORY_COUNTERS_EX>() as DWORD;
+        counters.cb = size;
+        let ok = unsafe {
+            GetProcessMemoryInfo(
+                self.inner.as_ptr(),
+                (&mut counters as *mut PROCESS_MEMORY_COUNTERS_EX)
+                    .cast::<PROCESS_MEMORY_COUNTERS>(),
+                size,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(MemoryCounters {
+            working_set: counters.WorkingSetSize,
+            peak_working_set: counters.PeakWorkingSetSize,
+            private_bytes: counters.PrivateUsage,
+            page_fault_count: counters.PageFaultCount,
+        })
+    }
+
+    /// Returns the I/O the process has performed since it started.
+    ///
+    /// This corresponds to calling [`GetProcessIoCounters`].
+    ///
+    /// [`GetProcessIoCounters`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getprocessiocounters
+    pub fn io_counters(&self) -> Result<IoCounters, Error> {
+        let () = M::ASSERT;
+        let mut counters: IO_COUNTERS = unsafe { mem::zeroed() };
+        let ok = unsafe {
+            GetProcessIoCounters(self.inner.as_ptr(), &mut counters)
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(IoCounters {
+            read_operations: counters.ReadOperationCount,
+            write_operations: counters.WriteOperationCount,
+            other_operations: counters.OtherOperationCount,
+            read_bytes: counters.ReadTransferCount,
+            write_bytes: counters.WriteTransferCount,
+            other_bytes: counters.OtherTransferCount,
+        })
+    }
+
+    /// Returns the number of handles the process has open.
+    ///
+    /// A count that keeps growing while the process does the same work
+    /// over and over usually means that it leaks handles.
+    ///
+    /// This corresponds to calling [`GetProcessHandleCount`].
+    ///
+    /// [`GetProcessHandleCount`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocesshandlecount
+    pub fn handle_count(&self) -> Result<u32, Error> {
+        let () = M::ASSERT;
+        let mut count: DWORD = 0;
+        let ok =
+            unsafe { GetProcessHandleCount(self.inner.as_ptr(), &mut count) };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(count)
+    }
+
+    /// Returns the number of GUI objects of the given kind that the process
+    /// uses. Processes that never created any GUI objects have none.
+    ///
+    /// This corresponds to calling [`GetGuiResources`].
+    ///
+    /// [`GetGuiResources`]: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getguiresources
+    pub fn gui_resources(&self, kind: GuiResource) -> Result<u32, Error> {
+        let () = M::ASSERT;
+        // Zero is both a valid count and the failure value.
+        unsafe { SetLastError(0) };
+        let count =
+            unsafe { GetGuiResources(self.inner.as_ptr(), kind.to_raw()) };
+        if count == 0 && unsafe { GetLastError() } != 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+
+    #[test]
+    fn current_process_memory_info() {
+        let counters = current_process().memory_info().unwrap();
+        assert!(counters.working_set() > 0);
+        assert!(counters.peak_working_set() >= counters.working_set());
+        assert!(counters.private_bytes() > 0);
+    }
+
+    #[test]
+    fn io_counters_grow_with_writes() {
+        let before = current_process().io_counters().unwrap();
+        let path = std::env::temp_dir()
+            .join(format!("winapi-util-io-{}", std::process::id()));
+        std::fs::write(&path, [0u8; 4096]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let after = current_process().io_counters().unwrap();
+        assert!(after.write_operations() > before.write_operations());
+        assert!(after.write_bytes() >= before.write_bytes() + 4096);
+    }
+
+    #[test]
+    fn current_process_resources() {
+        let process = current_process();
+        assert!(process.handle_count().unwrap() > 0);
+        let gdi = process.gui_resources(GuiResource::Gdi).unwrap();
+        assert!(process.gui_resources(GuiResource::GdiPeak).unwrap() >= gdi);
+    }
+}
@@ -0,0 +1,730 @@
+use core::{marker::PhantomData, mem, ptr};
+
+use winapi::{
+    shared::{
+        basetsd::DWORD_PTR,
+        minwindef::{BOOL, ULONG},
+        ntdef::{HANDLE, PULONG},
+        winerror::ERROR_INSUFFICIENT_BUFFER,
+    },
+    um::{
+        errhandlingapi::GetLastError,
+        processthreadsapi::GetThreadIdealProcessorEx,
+        processtopologyapi::{
+            GetProcessGroupAffinity, GetThreadGroupAffinity,
+            SetThreadGroupAffinity,
+        },
+        winbase::{GetProcessAffinityMask, SetThreadAffinityMask},
+        winnt::{
+            CpuSetInformation, GROUP_AFFINITY, PROCESSOR_NUMBER,
+            PSYSTEM_CPU_SET_INFORMATION, SYSTEM_CPU_SET_INFORMATION,
+        },
+    },
+};
+
+use crate::numa::GroupAffinity;
+
+use super::{
+    Error, HasProcessQueryLimitedInformation, HasProcessSetInformation,
+    HasProcessSetLimitedInformation, HasThreadQueryLimitedInformation,
+    HasThreadSetInformation, HasThreadSetLimitedInformation, ProcessHandle,
+    ThreadHandle,
+};
+
+// The CPU set functions are only declared as comments in winapi.
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetSystemCpuSetInformation(
+        Information: PSYSTEM_CPU_SET_INFORMATION,
+        BufferLength: ULONG,
+        ReturnedLength: PULONG,
+        Process: HANDLE,
+        Flags: ULONG,
+    ) -> BOOL;
+    fn GetProcessDefaultCpuSets(
+        Process: HANDLE,
+        CpuSetIds: PULONG,
+        CpuSetIdCount: ULONG,
+        RequiredIdCount: PULONG,
+    ) -> BOOL;
+    fn SetProcessDefaultCpuSets(
+        Process: HANDLE,
+        CpuSetIds: *const ULONG,
+        CpuSetIdCount: ULONG,
+    ) -> BOOL;
+    fn GetThreadSelectedCpuSets(
+        Thread: HANDLE,
+        CpuSetIds: PULONG,
+        CpuSetIdCount: ULONG,
+        RequiredIdCount: PULONG,
+    ) -> BOOL;
+    fn SetThreadSelectedCpuSets(
+        Thread: HANDLE,
+        CpuSetIds: *const ULONG,
+        CpuSetIdCount: ULONG,
+    ) -> BOOL;
+    // winapi declares the mask as a DWORD, which truncates it on 64-bit
+    // targets.
+    fn SetProcessAffinityMask(
+        hProcess: HANDLE,
+        dwProcessAffinityMask: DWORD_PTR,
+    ) -> BOOL;
+}
+
+/// A CPU set, i.e. a logical processor as seen by the scheduler.
+///
+/// See [`system_cpu_sets`].
+#[derive(Clone, Copy, Debug)]
+pub struct CpuSet {
+    id: u32,
+    group: u16,
+    logical_processor_index: u8,
+    core_index: u8,
+    last_level_cache_index: u8,
+    numa_node_index: u8,
+    efficiency_class: u8,
+    parked: bool,
+    allocated: bool,
+    realtime: bool,
+}
+
+impl CpuSet {
+    /// Returns the identifier of the CPU set, which is what
+    /// `ProcessHandle::set_default_cpu_sets` expects.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the processor group of the logical processor.
+    pub fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Returns the logical processor as a single processor within its
+    /// group, e.g. to combine CPU sets into an affinity.
+    pub fn affinity(&self) -> GroupAffinity {
+        GroupAffinity::new(self.group, 1 << self.logical_processor_index)
+    }
+
+    /// Returns the index of the logical processor within its group.
+    pub fn logical_processor_index(&self) -> u8 {
+        self.logical_processor_index
+    }
+
+    /// Returns a group-relative index of the physical core. CPU sets with
+    /// the same core index share a core.
+    pub fn core_index(&self) -> u8 {
+        self.core_index
+    }
+
+    /// Returns a group-relative index of the last level cache shared by
+    /// the logical processor.
+    pub fn last_level_cache_index(&self) -> u8 {
+        self.last_level_cache_index
+    }
+
+    /// Returns the NUMA node of the logical processor.
+    pub fn numa_node_index(&self) -> u8 {
+        self.numa_node_index
+    }
+
+    /// Returns the efficiency class of the core. Higher values mean better
+    /// performance at the cost of power, e.g. performance cores have a
+    /// higher class than efficiency cores on hybrid processors.
+    pub fn efficiency_class(&self) -> u8 {
+        self.efficiency_class
+    }
+
+    /// Returns true if the logical processor is parked to save power.
+    pub fn is_parked(&self) -> bool {
+        self.parked
+    }
+
+    /// Returns true if the CPU set is reserved for exclusive use by some
+    /// process.
+    pub fn is_allocated(&self) -> bool {
+        self.allocated
+    }
+
+    /// Returns true if the CPU set is reserved for a real-time process.
+    pub fn is_realtime(&self) -> bool {
+        self.realtime
+    }
+}
+
+/// The affinity masks of a process and of the system, returned by
+/// `ProcessHandle::affinity_mask`.
+///
+/// Bit `n` of a mask stands for the logical processor with index `n` in the
+/// processor group of the process.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct AffinityMasks {
+    process: usize,
+    system: usize,
+}
+
+impl AffinityMasks {
+    /// Returns the processors the threads of the process may run on.
+    pub fn process(&self) -> usize {
+        self.process
+    }
+
+    /// Returns the processors that are configured in the system.
+    pub fn system(&self) -> usize {
+        self.system
+    }
+}
+
+/// A logical processor, identified by its processor group and its index
+/// within the group, as returned by `ThreadHandle::ideal_processor`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProcessorNumber {
+    group: u16,
+    number: u8,
+}
+
+impl ProcessorNumber {
+    /// Returns the processor group of the logical processor.
+    pub fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Returns the index of the logical processor within its group.
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Returns the logical processor as a single processor within its
+    /// group.
+    pub fn affinity(&self) -> GroupAffinity {
+        GroupAffinity::new(self.group, 1 << self.number)
+    }
+}
+
+/// Returns the CPU sets of the system, one per logical processor.
+///
+/// This requires Windows 10 or newer.
+///
+/// This corresponds to calling [`GetSystemCpuSetInformation`].
+///
+/// [`GetSystemCpuSetInformation`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getsystemcpusetinformation
+pub fn system_cpu_sets() -> Result<Vec<CpuSet>, Error> {
+    let mut len: ULONG = 0;
+    let ok = unsafe {
+        GetSystemCpuSetInformation(
+            ptr::null_mut(),
+            0,
+            &mut len,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if ok == 0 && unsafe { GetLastError() } != ERROR_INSUFFICIENT_BUFFER {
+        return Err(Error(PhantomData));
+    }
+    // Use a u64 buffer so that every entry is suitably aligned.
+    let mut buf = vec![0u64; len as usize / 8 + 1];
+    let ok = unsafe {
+        GetSystemCpuSetInformation(
+            buf.as_mut_ptr().cast(),
+            (buf.len() * 8) as ULONG,
+            &mut len,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+
+    let mut sets = vec![];
+    let mut offset = 0;
+    // The entries have a variable size, given by their first field.
+    while offset + mem::size_of::<SYSTEM_CPU_SET_INFORMATION>() <= len as usize
+    {
+        let info = unsafe {
+            &*(buf.as_ptr().cast::<u8>().add(offset)
+                as *const SYSTEM_CPU_SET_INFORMATION)
+        };
+        if info.Size == 0 {
+            break;
+        }
+        if info.Type == CpuSetInformation {
+            let set = &info.CpuSet;
+            sets.push(CpuSet {
+                id: set.Id,
+                group: set.Group,
+                logical_processor_index: set.LogicalProcessorIndex,
+                core_index: set.CoreIndex,
+                last_level_cache_index: set.LastLevelCacheIndex,
+                numa_node_index: set.NumaNodeIndex,
+                efficiency_class: set.EfficiencyClass,
+                parked: set.Parked() != 0,
+                allocated: set.Allocated() != 0,
+                realtime: set.RealTime() != 0,
+            });
+        }
+        offset += info.Size as usize;
+    }
+    Ok(sets)
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the identifiers of the default CPU sets of the process, i.e.
+    /// the CPU sets its threads run on unless they select others. An empty
+    /// list means that no default CPU sets are assigned.
+    ///
+    /// This requires Windows 10 or newer.
+    ///
+    /// This corresponds to calling [`GetProcessDefaultCpuSets`].
+    ///
+    /// [`GetProcessDefaultCpuSets`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocessdefaultcpusets
+    pub fn default_cpu_sets(&self) -> Result<Vec<u32>, Error> {
+        let () = M::ASSERT;
+        let mut ids: Vec<ULONG> = vec![];
+        loop {
+            let mut required: ULONG = 0;
+            let ok = unsafe {
+                GetProcessDefaultCpuSets(
+                    self.inner.as_ptr(),
+                    ids.as_mut_ptr(),
+                    ids.len() as ULONG,
+                    &mut required,
+                )
+            };
+            if ok != 0 {
+                ids.truncate(required as usize);
+                return Ok(ids);
+            }
+            if unsafe { GetLastError() } != ERROR_INSUFFICIENT_BUFFER {
+                return Err(Error(PhantomData));
+            }
+            ids.resize(required as usize, 0);
+        }
+    }
+
+    /// Returns the affinity mask of the process, together with that of the
+    /// system.
+    ///
+    /// If the threads of the process span several processor groups, then
+    /// both masks are zero.
+    ///
+    /// This corresponds to calling [`GetProcessAffinityMask`].
+    ///
+    /// [`GetProcessAffinityMask`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getprocessaffinitymask
+    pub fn affinity_mask(&self) -> Result<AffinityMasks, Error> {
+        let () = M::ASSERT;
+        let (mut process, mut system) = (0, 0);
+        let ok = unsafe {
+            GetProcessAffinityMask(
+                self.inner.as_ptr(),
+                &mut process,
+                &mut system,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(AffinityMasks { process, system })
+    }
+
+    /// Returns the processor groups that the threads of the process have
+    /// run on. A process starts out in a single group, and only spans more
+    /// if its threads are explicitly assigned to other groups.
+    ///
+    /// This corresponds to calling [`GetProcessGroupAffinity`].
+    ///
+    /// [`GetProcessGroupAffinity`]: https://learn.microsoft.com/en-us/windows/win32/api/processtopologyapi/nf-processtopologyapi-getprocessgroupaffinity
+    pub fn processor_groups(&self) -> Result<Vec<u16>, Error> {
+        let () = M::ASSERT;
+        let mut groups: Vec<u16> = vec![0; 1];
+        loop {
+            let mut count = groups.len() as u16;
+            let ok = unsafe {
+                GetProcessGroupAffinity(
+                    self.inner.as_ptr(),
+                    &mut count,
+                    groups.as_mut_ptr(),
+                )
+            };
+            if ok != 0 {
+                groups.truncate(usize::from(count));
+                return Ok(groups);
+            }
+            if unsafe { GetLastError() } != ERROR_INSUFFICIENT_BUFFER {
+                return Err(Error(PhantomData));
+            }
+            groups.resize(usize::from(count), 0);
+        }
+    }
+}
+
+impl<M: HasProcessSetLimitedInformation> ProcessHandle<M> {
+    /// Sets the default CPU sets of the process, given by the identifiers
+    /// returned by [`CpuSet::id`]. Passing an empty slice removes the
+    /// default CPU sets, so that threads may run on any processor again.
+    ///
+    /// Unlike affinity masks, CPU sets span processor groups, and the
+    /// system may still run other processes on them.
+    ///
+    /// This requires Windows 10 or newer.
+    ///
+    /// This corresponds to calling [`SetProcessDefaultCpuSets`].
+    ///
+    /// [`SetProcessDefaultCpuSets`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setprocessdefaultcpusets
+    pub fn set_default_cpu_sets(&self, ids: &[u32]) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let ptr = if ids.is_empty() { ptr::null() } else { ids.as_ptr() };
+        let ok = unsafe {
+            SetProcessDefaultCpuSets(
+                self.inner.as_ptr(),
+                ptr,
+                ids.len() as ULONG,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+impl<M: HasProcessSetInformation> ProcessHandle<M> {
+    /// Restricts the threads of the process to the processors in `mask`,
+    /// which must be a subset of the system affinity mask.
+    ///
+    /// This corresponds to calling [`SetProcessAffinityMask`].
+    ///
+    /// [`SetProcessAffinityMask`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-setprocessaffinitymask
+    pub fn set_affinity_mask(&self, mask: usize) -> Result<(), Error> {
+        let () = M::ASSERT;
+        if unsafe { SetProcessAffinityMask(self.inner.as_ptr(), mask) } == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+
+    /// Restricts the threads of the process to the logical processor of a
+    /// single CPU set, e.g. to keep benchmarks from migrating between cores.
+    ///
+    /// Affinity masks are relative to the processor group of the process,
+    /// so the CPU set must belong to that group. Unlike with
+    /// `ProcessHandle::set_default_cpu_sets`, the threads cannot run
+    /// anywhere else, even if they select other CPU sets.
+    pub fn pin_to_cpu_set(&self, set: &CpuSet) -> Result<(), Error> {
+        self.set_affinity_mask(set.affinity().mask())
+    }
+}
+
+impl<M: HasThreadQueryLimitedInformation> ThreadHandle<M> {
+    /// Returns the ideal processor of the thread, which the scheduler
+    /// prefers to run it on.
+    ///
+    /// This corresponds to calling [`GetThreadIdealProcessorEx`].
+    ///
+    /// [`GetThreadIdealProcessorEx`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadidealprocessorex
+    pub fn ideal_processor(&self) -> Result<ProcessorNumber, Error> {
+        let () = M::ASSERT;
+        let mut processor: PROCESSOR_NUMBER = unsafe { mem::zeroed() };
+        let ok = unsafe {
+            GetThreadIdealProcessorEx(self.inner.as_ptr(), &mut processor)
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(ProcessorNumber {
+            group: processor.Group,
+            number: processor.Number,
+        })
+    }
+
+    /// Returns the processor group of the thread and the processors within
+    /// it that the thread may run on.
+    ///
+    /// This corresponds to calling [`GetThreadGroupAffinity`].
+    ///
+    /// [`GetThreadGroupAffinity`]: https://learn.microsoft.com/en-us/windows/win32/api/processtopologyapi/nf-processtopologyapi-getthreadgroupaffinity
+    pub fn group_affinity(&self) -> Result<GroupAffinity, Error> {
+        let () = M::ASSERT;
+        let mut affinity: GROUP_AFFINITY = unsafe { mem::zeroed() };
+        let ok = unsafe {
+            GetThreadGroupAffinity(self.inner.as_ptr(), &mut affinity)
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(GroupAffinity::from_raw(&affinity))
+    }
+
+    /// Returns the identifiers of the CPU sets selected for the thread,
+    /// which override the default CPU sets of its process. An empty list
+    /// means that the thread has no CPU sets selected.
+    ///
+    /// This requires Windows 10 or newer.
+    ///
+    /// This corresponds to calling [`GetThreadSelectedCpuSets`].
+    ///
+    /// [`GetThreadSelectedCpuSets`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadselectedcpusets
+    pub fn selected_cpu_sets(&self) -> Result<Vec<u32>, Error> {
+        let () = M::ASSERT;
+        let mut ids: Vec<ULONG> = vec![];
+        loop {
+            let mut required: ULONG = 0;
+            let ok = unsafe {
+                GetThreadSelectedCpuSets(
+                    self.inner.as_ptr(),
+                    ids.as_mut_ptr(),
+                    ids.len() as ULONG,
+                    &mut required,
+                )
+            };
+            if ok != 0 {
+                ids.truncate(required as usize);
+                return Ok(ids);
+            }
+            if unsafe { GetLastError() } != ERROR_INSUFFICIENT_BUFFER {
+                return Err(Error(PhantomData));
+            }
+            ids.resize(required as usize, 0);
+        }
+    }
+}
+
+impl<M: HasThreadSetLimitedInformation> ThreadHandle<M> {
+    /// Selects the CPU sets that the thread runs on, given by the
+    /// identifiers returned by [`CpuSet::id`], in place of the default CPU
+    /// sets of its process. Passing an empty slice clears the selection.
+    ///
+    /// This requires Windows 10 or newer.
+    ///
+    /// This corresponds to calling [`SetThreadSelectedCpuSets`].
+    ///
+    /// [`SetThreadSelectedCpuSets`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setthreadselectedcpusets
+    pub fn set_selected_cpu_sets(&self, ids: &[u32]) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let ptr = if ids.is_empty() { ptr::null() } else { ids.as_ptr() };
+        let ok = unsafe {
+            SetThreadSelectedCpuSets(
+                self.inner.as_ptr(),
+                ptr,
+                ids.len() as ULONG,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+impl<M> ThreadHandle<M>
+where
+    M: HasThreadQueryLimitedInformation + HasThreadSetLimitedInformation,
+{
+    /// Restricts the thread to the processors in `mask`, which must be a
+    /// subset of the affinity mask of its process, and returns the previous
+    /// affinity mask of the thread.
+    ///
+    /// As with `ProcessHandle::set_affinity_mask`, the mask is relative to
+    /// the processor group of the thread.
+    ///
+    /// This corresponds to calling [`SetThreadAffinityMask`].
+    ///
+    /// [`SetThreadAffinityMask`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-setthreadaffinitymask
+    pub fn set_affinity_mask(&self, mask: usize) -> Result<usize, Error> {
+        let () = <M as HasThreadQueryLimitedInformation>::ASSERT;
+        let () = <M as HasThreadSetLimitedInformation>::ASSERT;
+        match unsafe { SetThreadAffinityMask(self.inner.as_ptr(), mask) } {
+            0 => Err(Error(PhantomData)),
+            previous => Ok(previous),
+        }
+    }
+}
+
+impl<M> ThreadHandle<M>
+where
+    M: HasThreadQueryLimitedInformation + HasThreadSetInformation,
+{
+    /// Moves the thread to the processor group of `affinity` and restricts
+    /// it to the processors of that group in its mask, and returns the
+    /// previous group affinity of the thread.
+    ///
+    /// Unlike `ThreadHandle::set_affinity_mask`, this can move threads
+    /// between processor groups on machines with more than 64 logical
+    /// processors.
+    ///
+    /// This corresponds to calling [`SetThreadGroupAffinity`].
+    ///
+    /// [`SetThreadGroupAffinity`]: https://learn.microsoft.com/en-us/windows/win32/api/processtopologyapi/nf-processtopologyapi-setthreadgroupaffinity
+    pub fn set_group_affinity(
+        &self,
+        affinity: GroupAffinity,
+    ) -> Result<GroupAffinity, Error> {
+        let () = <M as HasThreadQueryLimitedInformation>::ASSERT;
+        let () = <M as HasThreadSetInformation>::ASSERT;
+        let affinity = affinity.to_raw();
+        let mut previous: GROUP_AFFINITY = unsafe { mem::zeroed() };
+        let ok = unsafe {
+            SetThreadGroupAffinity(
+                self.inner.as_ptr(),
+                &affinity,
+                &mut previous,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(GroupAffinity::from_raw(&previous))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{
+        current_process, open_process, open_thread, RuntimeAccessRights,
+    };
+    use std::process::Command;
+    use winapi::um::{
+        processthreadsapi::GetCurrentThreadId,
+        winnt::{
+            PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_INFORMATION,
+            PROCESS_SET_LIMITED_INFORMATION, THREAD_QUERY_LIMITED_INFORMATION,
+            THREAD_SET_INFORMATION, THREAD_SET_LIMITED_INFORMATION,
+        },
+    };
+
+    #[test]
+    fn default_cpu_sets_round_trip() {
+        let sets = system_cpu_sets().unwrap();
+        assert!(!sets.is_empty());
+
+        // Use a child process, since the tests share the current one.
+        let mut child = Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .spawn()
+            .unwrap();
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_LIMITED_INFORMATION
+                | PROCESS_SET_LIMITED_INFORMATION,
+            false,
+            &child,
+        )
+        .unwrap();
+        let ids: Vec<u32> = sets.iter().map(|set| set.id()).collect();
+        let set = handle.set_default_cpu_sets(&ids);
+        let selected = handle.default_cpu_sets();
+        let cleared = handle.set_default_cpu_sets(&[]);
+        let empty = handle.default_cpu_sets();
+        let groups = handle.processor_groups();
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        set.unwrap();
+        assert_eq!(selected.unwrap(), ids);
+        cleared.unwrap();
+        assert!(empty.unwrap().is_empty());
+        assert!(!groups.unwrap().is_empty());
+    }
+
+    #[test]
+    fn selected_cpu_sets_round_trip() {
+        let ids: Vec<u32> =
+            system_cpu_sets().unwrap().iter().map(|set| set.id()).collect();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            sender.send(unsafe { GetCurrentThreadId() }).unwrap();
+            let _ = stopped.recv();
+        });
+        let tid = receiver.recv().unwrap();
+        let thread = open_thread::<
+            crate::access_rights!(
+                THREAD_QUERY_LIMITED_INFORMATION,
+                THREAD_SET_LIMITED_INFORMATION
+            ),
+        >(PhantomData, false, tid)
+        .unwrap();
+
+        let before = thread.selected_cpu_sets();
+        let set = thread.set_selected_cpu_sets(&ids);
+        let selected = thread.selected_cpu_sets();
+        let cleared = thread.set_selected_cpu_sets(&[]);
+        let after = thread.selected_cpu_sets();
+        stop.send(()).unwrap();
+        worker.join().unwrap();
+
+        assert!(before.unwrap().is_empty());
+        set.unwrap();
+        assert_eq!(selected.unwrap(), ids);
+        cleared.unwrap();
+        assert!(after.unwrap().is_empty());
+    }
+
+    #[test]
+    fn affinity_mask_round_trip() {
+        // Use a child process, since the tests share the current one.
+        let mut child = Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .spawn()
+            .unwrap();
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_SET_INFORMATION,
+            false,
+            &child,
+        )
+        .unwrap();
+        let original = handle.affinity_mask();
+        // Keep only the lowest processor the process may run on.
+        let lowest = original.as_ref().ok().map(|original| {
+            original.process() & original.process().wrapping_neg()
+        });
+        let set = lowest.map(|lowest| handle.set_affinity_mask(lowest));
+        let pinned = handle.affinity_mask();
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let original = original.unwrap();
+        assert_eq!(original.process() & !original.system(), 0);
+        set.unwrap().unwrap();
+        assert_eq!(Some(pinned.unwrap().process()), lowest);
+    }
+
+    #[test]
+    fn thread_affinity_round_trip() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            sender.send(unsafe { GetCurrentThreadId() }).unwrap();
+            let _ = stopped.recv();
+        });
+        let tid = receiver.recv().unwrap();
+        let thread = open_thread::<
+            crate::access_rights!(
+                THREAD_QUERY_LIMITED_INFORMATION,
+                THREAD_SET_INFORMATION
+            ),
+        >(PhantomData, false, tid)
+        .unwrap();
+        let process = current_process().affinity_mask().unwrap().process();
+        let lowest = process & process.wrapping_neg();
+
+        let original = thread.set_affinity_mask(lowest);
+        let pinned = thread.group_affinity();
+        let group = pinned.as_ref().map(|pinned| pinned.group()).unwrap_or(0);
+        let widened =
+            thread.set_group_affinity(GroupAffinity::new(group, process));
+        let after = thread.group_affinity();
+        let ideal = thread.ideal_processor();
+        stop.send(()).unwrap();
+        worker.join().unwrap();
+
+        assert_eq!(original.unwrap() & !process, 0);
+        let pinned = pinned.unwrap();
+        assert_eq!(pinned.mask(), lowest);
+        assert_eq!(widened.unwrap(), pinned);
+        assert_eq!(after.unwrap(), GroupAffinity::new(group, process));
+        let ideal = ideal.unwrap();
+        assert_eq!(ideal.affinity().group(), ideal.group());
+    }
+}
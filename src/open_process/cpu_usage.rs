@@ -0,0 +1,149 @@
+//! Measuring how much CPU time processes consume.
+//!
+//! A [`CpuSampler`] turns the cumulative CPU times of a process into a
+//! usage percentage, the way Task Manager shows it:
+//!
+//! ```no_run
+//! use std::{thread, time::Duration};
+//! use winapi_util::open_process::{cpu_usage::CpuSampler, current_process};
+//!
+//! let mut sampler = CpuSampler::new(&current_process()).unwrap();
+//! loop {
+//!     thread::sleep(Duration::from_secs(1));
+//!     println!("{:.1}%", sampler.sample().unwrap());
+//! }
+//! ```
+
+use core::{marker::PhantomData, mem, time::Duration};
+
+use winapi::{
+    shared::minwindef::FILETIME,
+    um::{
+        processthreadsapi::GetSystemTimes,
+        winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+    },
+};
+
+use super::{
+    times::to_duration, ComptimeAccessRights, Error,
+    HasProcessQueryLimitedInformation, ProcessHandle,
+};
+
+/// Samples the CPU usage of a process.
+///
+/// Each call to [`CpuSampler::sample`] returns the share of the total CPU
+/// time of the system that the process used since the previous call, or
+/// since the sampler was created. The share is normalized over all logical
+/// processors, so that a process keeping one of eight processors busy uses
+/// 12.5%. Multiply by the number of processors to get the usage relative to
+/// a single processor instead.
+#[derive(Debug)]
+pub struct CpuSampler {
+    process:
+        ProcessHandle<ComptimeAccessRights<PROCESS_QUERY_LIMITED_INFORMATION>>,
+    last: Snapshot,
+}
+
+/// The cumulative CPU times of a process and of the system.
+#[derive(Clone, Copy, Debug)]
+struct Snapshot {
+    process: Duration,
+    system: Duration,
+}
+
+impl CpuSampler {
+    /// Creates a sampler for the process, taking the first snapshot of its
+    /// CPU times.
+    ///
+    /// The sampler keeps its own handle to the process, so it stays usable
+    /// after the given handle is closed.
+    pub fn new<M: HasProcessQueryLimitedInformation>(
+        process: &ProcessHandle<M>,
+    ) -> Result<CpuSampler, Error> {
+        let () = M::ASSERT;
+        let process = process.with_reduced_access::<ComptimeAccessRights<
+            PROCESS_QUERY_LIMITED_INFORMATION,
+        >>(PhantomData)?;
+        let last = snapshot(&process)?;
+        Ok(CpuSampler { process, last })
+    }
+
+    /// Returns the CPU usage of the process since the previous sample, as
+    /// a percentage between 0 and 100.
+    ///
+    /// If no time has passed since the previous sample, the usage is 0.
+    ///
+    /// This corresponds to calling [`GetProcessTimes`] and
+    /// [`GetSystemTimes`].
+    ///
+    /// [`GetProcessTimes`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocesstimes
+    /// [`GetSystemTimes`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getsystemtimes
+    pub fn sample(&mut self) -> Result<f64, Error> {
+        let now = snapshot(&self.process)?;
+        let usage = usage(&self.last, &now);
+        self.last = now;
+        Ok(usage)
+    }
+}
+
+fn snapshot(
+    process: &ProcessHandle<
+        ComptimeAccessRights<PROCESS_QUERY_LIMITED_INFORMATION>,
+    >,
+) -> Result<Snapshot, Error> {
+    let times = process.times()?;
+    let mut idle: FILETIME = unsafe { mem::zeroed() };
+    let (mut kernel, mut user) = (idle, idle);
+    if unsafe { GetSystemTimes(&mut idle, &mut kernel, &mut user) } == 0 {
+        return Err(Error(PhantomData));
+    }
+    // The kernel time of the system includes its idle time, so the sum is
+    // the time that has elapsed on all processors together.
+    Ok(Snapshot {
+        process: times.kernel() + times.user(),
+        system: to_duration(&kernel) + to_duration(&user),
+    })
+}
+
+fn usage(last: &Snapshot, now: &Snapshot) -> f64 {
+    // Both times only ever grow, but the counters are read separately, so
+    // a sample can appear to go backwards by a tick.
+    let process = now.process.saturating_sub(last.process);
+    let system = now.system.saturating_sub(last.system);
+    if system.is_zero() {
+        return 0.0;
+    }
+    let share = process.as_secs_f64() / system.as_secs_f64() * 100.0;
+    share.clamp(0.0, 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+    use std::time::Instant;
+
+    #[test]
+    fn usage_is_normalized() {
+        let snapshot = |process, system| Snapshot {
+            process: Duration::from_millis(process),
+            system: Duration::from_millis(system),
+        };
+        let last = snapshot(100, 1_000);
+        assert_eq!(usage(&last, &snapshot(200, 1_800)), 12.5);
+        assert_eq!(usage(&last, &last), 0.0);
+        assert_eq!(usage(&last, &snapshot(300, 1_100)), 100.0);
+        assert_eq!(usage(&last, &snapshot(90, 990)), 0.0);
+    }
+
+    #[test]
+    fn busy_loop_uses_cpu() {
+        let mut sampler = CpuSampler::new(&current_process()).unwrap();
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(200) {
+            core::hint::spin_loop();
+        }
+        let usage = sampler.sample().unwrap();
+        assert!(usage > 0.0 && usage <= 100.0);
+    }
+}
@@ -0,0 +1,190 @@
+use core::{ffi::c_void, marker::PhantomData, mem, ptr, slice};
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStringExt,
+    sync::OnceLock,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FARPROC},
+        ntdef::{HRESULT, PCWSTR, PWSTR},
+        winerror::{ERROR_NOT_SUPPORTED, FACILITY_WIN32},
+    },
+    um::{
+        errhandlingapi::SetLastError,
+        libloaderapi::{GetModuleHandleW, GetProcAddress},
+        winbase::LocalFree,
+        winnt::HANDLE,
+    },
+};
+
+use super::{
+    to_wide, Error, HasThreadQueryLimitedInformation,
+    HasThreadSetLimitedInformation, ThreadHandle,
+};
+
+const GET_THREAD_DESCRIPTION_NAME: &[u8] = b"GetThreadDescription\0";
+const SET_THREAD_DESCRIPTION_NAME: &[u8] = b"SetThreadDescription\0";
+
+type GetThreadDescriptionFn = unsafe extern "system" fn(
+    thread: HANDLE,
+    description: *mut PWSTR,
+) -> HRESULT;
+
+type SetThreadDescriptionFn =
+    unsafe extern "system" fn(thread: HANDLE, description: PCWSTR) -> HRESULT;
+
+impl<M: HasThreadQueryLimitedInformation> ThreadHandle<M> {
+    /// Returns the description of the thread, which debuggers and profilers
+    /// show as its name. Threads without a description have an empty one.
+    ///
+    /// Thread descriptions were added in Windows 10, version 1607, and this
+    /// fails with `ERROR_NOT_SUPPORTED` on older versions of Windows.
+    ///
+    /// This corresponds to calling [`GetThreadDescription`].
+    ///
+    /// [`GetThreadDescription`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreaddescription
+    pub fn description(&self) -> Result<OsString, Error> {
+        let () = M::ASSERT;
+        let get_thread_description =
+            get_thread_description_fn().ok_or_else(not_supported)?;
+        let mut description: PWSTR = ptr::null_mut();
+        let hr = unsafe {
+            get_thread_description(self.inner.as_ptr(), &mut description)
+        };
+        check(hr)?;
+        let description = unsafe {
+            let len = (0..).take_while(|&i| *description.add(i) != 0).count();
+            let units = slice::from_raw_parts(description, len);
+            let owned = OsString::from_wide(units);
+            LocalFree(description as *mut c_void);
+            owned
+        };
+        Ok(description)
+    }
+}
+
+impl<M: HasThreadSetLimitedInformation> ThreadHandle<M> {
+    /// Sets the description of the thread, which debuggers and profilers
+    /// show as its name, e.g. in crash dumps.
+    ///
+    /// Thread descriptions were added in Windows 10, version 1607, and this
+    /// fails with `ERROR_NOT_SUPPORTED` on older versions of Windows.
+    ///
+    /// This corresponds to calling [`SetThreadDescription`].
+    ///
+    /// [`SetThreadDescription`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setthreaddescription
+    pub fn set_description(&self, description: &OsStr) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let set_thread_description =
+            set_thread_description_fn().ok_or_else(not_supported)?;
+        let description = to_wide(description);
+        let hr = unsafe {
+            set_thread_description(self.inner.as_ptr(), description.as_ptr())
+        };
+        check(hr)
+    }
+}
+
+/// Sets the last error to `ERROR_NOT_SUPPORTED`.
+fn not_supported() -> Error {
+    unsafe { SetLastError(ERROR_NOT_SUPPORTED) };
+    Error(PhantomData)
+}
+
+/// Turns a failed `HRESULT` into an error, setting the last error to the
+/// Win32 error code that it wraps, or to the `HRESULT` itself.
+fn check(hr: HRESULT) -> Result<(), Error> {
+    if hr >= 0 {
+        return Ok(());
+    }
+    let hr = hr as DWORD;
+    let code = if (hr >> 16) & 0x1fff == FACILITY_WIN32 as DWORD {
+        hr & 0xffff
+    } else {
+        hr
+    };
+    unsafe { SetLastError(code) };
+    Err(Error(PhantomData))
+}
+
+/// Returns the address of a function of `kernel32.dll`, if it exists.
+fn kernel32_fn(name: &[u8]) -> Option<FARPROC> {
+    unsafe {
+        let module_name = to_wide("kernel32.dll".as_ref());
+        let module = GetModuleHandleW(module_name.as_ptr());
+        if module.is_null() {
+            return None;
+        }
+        let f = GetProcAddress(module, name.as_ptr().cast());
+        if f.is_null() {
+            return None;
+        }
+        Some(f)
+    }
+}
+
+/// Returns `GetThreadDescription`, which is only available on Windows 10,
+/// version 1607, and newer.
+fn get_thread_description_fn() -> Option<GetThreadDescriptionFn> {
+    static GET_THREAD_DESCRIPTION: OnceLock<Option<GetThreadDescriptionFn>> =
+        OnceLock::new();
+    *GET_THREAD_DESCRIPTION.get_or_init(|| {
+        let f = kernel32_fn(GET_THREAD_DESCRIPTION_NAME)?;
+        Some(unsafe { mem::transmute::<FARPROC, GetThreadDescriptionFn>(f) })
+    })
+}
+
+/// Returns `SetThreadDescription`, which is only available on Windows 10,
+/// version 1607, and newer.
+fn set_thread_description_fn() -> Option<SetThreadDescriptionFn> {
+    static SET_THREAD_DESCRIPTION: OnceLock<Option<SetThreadDescriptionFn>> =
+        OnceLock::new();
+    *SET_THREAD_DESCRIPTION.get_or_init(|| {
+        let f = kernel32_fn(SET_THREAD_DESCRIPTION_NAME)?;
+        Some(unsafe { mem::transmute::<FARPROC, SetThreadDescriptionFn>(f) })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::open_thread;
+    use core::marker::PhantomData;
+    use std::ffi::OsStr;
+    use winapi::um::{
+        processthreadsapi::GetCurrentThreadId,
+        winnt::{
+            THREAD_QUERY_LIMITED_INFORMATION, THREAD_SET_LIMITED_INFORMATION,
+        },
+    };
+
+    #[test]
+    fn sets_and_gets_description() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            sender.send(unsafe { GetCurrentThreadId() }).unwrap();
+            let _ = stopped.recv();
+        });
+        let tid = receiver.recv().unwrap();
+        let handle = open_thread::<
+            crate::access_rights!(
+                THREAD_QUERY_LIMITED_INFORMATION,
+                THREAD_SET_LIMITED_INFORMATION
+            ),
+        >(PhantomData, false, tid)
+        .unwrap();
+
+        let before = handle.description();
+        let set = handle.set_description(OsStr::new("winapi-util worker"));
+        let after = handle.description();
+        stop.send(()).unwrap();
+        worker.join().unwrap();
+
+        // Threads spawned by std are unnamed unless given a name.
+        assert_eq!(before.unwrap(), "");
+        set.unwrap();
+        assert_eq!(after.unwrap(), "winapi-util worker");
+    }
+}
@@ -0,0 +1,326 @@
+use core::{ffi::c_void, marker::PhantomData, ptr::NonNull};
+
+use winapi::{
+    shared::minwindef::{BOOL, DWORD, FALSE},
+    shared::winerror::ERROR_ACCESS_DENIED,
+    um::{
+        errhandlingapi::SetLastError,
+        handleapi::DuplicateHandle,
+        processthreadsapi::GetCurrentProcess,
+        winnt::{
+            DUPLICATE_CLOSE_SOURCE, DUPLICATE_SAME_ACCESS, HANDLE,
+            PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION,
+            PROCESS_SET_INFORMATION, PROCESS_SET_LIMITED_INFORMATION,
+        },
+    },
+};
+
+use super::{
+    Error, Handle, HandleMetadata, HandleType, HasProcessDupHandle,
+    IntoAccessRights, ProcessHandle,
+};
+
+/// A handle value that is valid in another process, obtained via
+/// `Handle::duplicate_into` or `Handle::move_into`.
+///
+/// This is just a number from the point of view of the current process. It
+/// is never closed by this crate: it is meant to be passed to the target
+/// process (e.g., over a pipe or on its command line), which then owns it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RawDuplicatedHandle(usize);
+
+impl RawDuplicatedHandle {
+    /// Returns the handle value as seen by the target process.
+    pub fn as_raw(&self) -> HANDLE {
+        self.0 as HANDLE
+    }
+
+    /// Returns the handle value as an integer, which is convenient for
+    /// passing it to the target process.
+    pub fn value(&self) -> usize {
+        self.0
+    }
+}
+
+/// Options controlling how `Handle::duplicate_into` and
+/// `Handle::move_into` create the new handle.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DuplicateOptions {
+    same_access: bool,
+}
+
+impl DuplicateOptions {
+    /// Creates a new set of options, requesting exactly the access rights
+    /// given to the duplication call.
+    pub fn new() -> DuplicateOptions {
+        DuplicateOptions::default()
+    }
+
+    /// When enabled, the new handle gets the same access rights as the
+    /// source handle and the `desired_access` argument is ignored.
+    ///
+    /// This corresponds to `DUPLICATE_SAME_ACCESS`.
+    pub fn same_access(mut self, yes: bool) -> DuplicateOptions {
+        self.same_access = yes;
+        self
+    }
+
+    fn to_dword(self) -> DWORD {
+        if self.same_access {
+            DUPLICATE_SAME_ACCESS
+        } else {
+            0
+        }
+    }
+}
+
+impl<T: HandleType, M: HandleMetadata> Handle<T, M> {
+    /// Creates a new, independently owned handle to the same object.
+    ///
+    /// The new handle has the same access rights as this one, and the same
+    /// compile-time or runtime access-rights metadata. It is not inheritable.
+    ///
+    /// This corresponds to calling [`DuplicateHandle`] with
+    /// `DUPLICATE_SAME_ACCESS` within the current process.
+    ///
+    /// [`DuplicateHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        let inner = duplicate_local(self.inner, 0, DUPLICATE_SAME_ACCESS)?;
+        Ok(Handle::from_opened(inner, self.metadata))
+    }
+}
+
+impl<T: HandleType, M: HandleMetadata> Handle<T, M> {
+    /// Duplicates this handle into the process represented by `target`,
+    /// e.g. to hand a resource to a child process.
+    ///
+    /// `target` must have been opened with `PROCESS_DUP_HANDLE`, which is
+    /// checked at compile time if its rights are known then. The new
+    /// handle is owned by the target process; this handle stays open.
+    ///
+    /// This corresponds to calling [`DuplicateHandle`].
+    ///
+    /// [`DuplicateHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle
+    pub fn duplicate_into<M2: HasProcessDupHandle>(
+        &self,
+        target: &ProcessHandle<M2>,
+        desired_access: DWORD,
+        inherit: bool,
+        options: DuplicateOptions,
+    ) -> Result<RawDuplicatedHandle, Error> {
+        duplicate_remote(
+            self.inner,
+            target,
+            desired_access,
+            inherit,
+            options.to_dword(),
+        )
+    }
+
+    /// Like `duplicate_into`, but closes this handle in the same operation,
+    /// effectively moving ownership of it to the target process.
+    ///
+    /// The source handle is closed even if the duplication fails.
+    ///
+    /// This corresponds to calling [`DuplicateHandle`] with
+    /// `DUPLICATE_CLOSE_SOURCE`.
+    ///
+    /// [`DuplicateHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle
+    pub fn move_into<M2: HasProcessDupHandle>(
+        self,
+        target: &ProcessHandle<M2>,
+        desired_access: DWORD,
+        inherit: bool,
+        options: DuplicateOptions,
+    ) -> Result<RawDuplicatedHandle, Error> {
+        // DuplicateHandle takes care of closing the source handle.
+        let this = core::mem::ManuallyDrop::new(self);
+        Self::before_close(this.inner);
+        duplicate_remote(
+            this.inner,
+            target,
+            desired_access,
+            inherit,
+            options.to_dword() | DUPLICATE_CLOSE_SOURCE,
+        )
+    }
+}
+
+fn duplicate_remote<M: HasProcessDupHandle>(
+    handle: NonNull<c_void>,
+    target: &ProcessHandle<M>,
+    desired_access: DWORD,
+    inherit: bool,
+    options: DWORD,
+) -> Result<RawDuplicatedHandle, Error> {
+    let () = M::ASSERT;
+    let mut out: HANDLE = core::ptr::null_mut();
+    let inherit: BOOL = if inherit { 1 } else { 0 };
+    let ok = unsafe {
+        DuplicateHandle(
+            GetCurrentProcess(),
+            handle.as_ptr(),
+            target.inner.as_ptr(),
+            &mut out,
+            desired_access,
+            inherit,
+            options,
+        )
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(RawDuplicatedHandle(out as usize))
+}
+
+impl<M: HandleMetadata> ProcessHandle<M> {
+    /// Creates a new handle to the same process with a subset of the access
+    /// rights of this one, e.g. to lend a `PROCESS_ALL_ACCESS` handle to
+    /// less trusted code as a `PROCESS_QUERY_LIMITED_INFORMATION` one.
+    ///
+    /// Like [`open_process`](super::open_process), the generic parameter
+    /// selects whether the new rights are known at compile time, and thus
+    /// the type of the returned handle.
+    ///
+    /// If the requested rights are not a subset of the rights this handle
+    /// was opened with, then an error with code `ERROR_ACCESS_DENIED` is
+    /// returned. The limited query and set rights are considered part of
+    /// their full counterparts, as Windows grants them implicitly. Note that
+    /// generic rights such as `GENERIC_READ` are not mapped to specific
+    /// rights, and so are never considered a subset.
+    ///
+    /// This corresponds to calling [`DuplicateHandle`] within the current
+    /// process.
+    ///
+    /// [`DuplicateHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle
+    pub fn with_reduced_access<R: IntoAccessRights>(
+        &self,
+        desired_access: R::RuntimeArgumentType,
+    ) -> Result<ProcessHandle<R::AccessRightsType>, Error> {
+        let current = implied_process_rights(self.access_rights());
+        let desired = R::rt_arg_to_dword(desired_access);
+        if desired & !current != 0 {
+            unsafe { SetLastError(ERROR_ACCESS_DENIED) };
+            return Err(Error(PhantomData));
+        }
+        let inner = duplicate_local(self.inner, desired, 0)?;
+        let metadata = R::rt_arg_to_metadata(desired_access);
+        Ok(Handle::from_opened(inner, metadata))
+    }
+}
+
+/// Adds the rights that Windows implicitly grants along with the given
+/// process access rights.
+fn implied_process_rights(access: DWORD) -> DWORD {
+    let mut access = access;
+    if access & PROCESS_QUERY_INFORMATION != 0 {
+        access |= PROCESS_QUERY_LIMITED_INFORMATION;
+    }
+    if access & PROCESS_SET_INFORMATION != 0 {
+        access |= PROCESS_SET_LIMITED_INFORMATION;
+    }
+    access
+}
+
+/// Duplicates the given handle within the current process.
+pub(super) fn duplicate_local(
+    handle: NonNull<c_void>,
+    desired_access: DWORD,
+    options: DWORD,
+) -> Result<NonNull<c_void>, Error> {
+    let mut target: HANDLE = core::ptr::null_mut();
+    let ok = unsafe {
+        let current = GetCurrentProcess();
+        DuplicateHandle(
+            current,
+            handle.as_ptr(),
+            current,
+            &mut target,
+            desired_access,
+            FALSE,
+            options,
+        )
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    NonNull::new(target).ok_or(Error(PhantomData))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{open_process, ComptimeAccessRights};
+    use core::marker::PhantomData;
+    use winapi::um::winnt::PROCESS_QUERY_INFORMATION;
+
+    #[test]
+    fn try_clone_keeps_both_handles_usable() {
+        let handle = open_process::<
+            ComptimeAccessRights<PROCESS_QUERY_INFORMATION>,
+        >(PhantomData, false, std::process::id())
+        .unwrap();
+        let clone = handle.try_clone().unwrap();
+        assert_ne!(handle.inner, clone.inner);
+        drop(handle);
+        clone.try_close().unwrap();
+    }
+
+    #[test]
+    fn with_reduced_access_rejects_escalation() {
+        use super::super::{ErrorCode, RuntimeAccessRights};
+        use winapi::um::winnt::{
+            PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
+        };
+
+        let handle = open_process::<
+            ComptimeAccessRights<PROCESS_QUERY_INFORMATION>,
+        >(PhantomData, false, std::process::id())
+        .unwrap();
+        let reduced = handle
+            .with_reduced_access::<ComptimeAccessRights<
+                PROCESS_QUERY_LIMITED_INFORMATION,
+            >>(PhantomData)
+            .unwrap();
+        drop(reduced);
+        let err = handle
+            .with_reduced_access::<RuntimeAccessRights>(
+                PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_TERMINATE,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.code().as_dword(),
+            ErrorCode::ERROR_ACCESS_DENIED.as_dword()
+        );
+    }
+
+    #[test]
+    fn move_into_own_process() {
+        use super::super::{ProcessHandle, RuntimeAccessRights};
+        use super::DuplicateOptions;
+        use winapi::um::winnt::PROCESS_DUP_HANDLE;
+
+        let pid = std::process::id();
+        let target = open_process::<RuntimeAccessRights>(
+            PROCESS_DUP_HANDLE,
+            false,
+            pid,
+        )
+        .unwrap();
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_INFORMATION,
+            false,
+            pid,
+        )
+        .unwrap();
+        let options = DuplicateOptions::new().same_access(true);
+        let raw = handle.move_into(&target, 0, false, options).unwrap();
+        // Since the target is the current process, we can adopt the handle.
+        let adopted = unsafe {
+            ProcessHandle::<RuntimeAccessRights>::from_raw(
+                raw.as_raw(),
+                PROCESS_QUERY_INFORMATION,
+            )
+        };
+        adopted.unwrap().try_close().unwrap();
+    }
+}
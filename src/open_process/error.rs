@@ -1,7 +1,15 @@
 use core::fmt::{self, Debug, Formatter};
 use core::{ffi::c_void, marker::PhantomData};
 use winapi::{
-    shared::{minwindef::DWORD, ntdef::LPWSTR},
+    shared::{
+        minwindef::DWORD,
+        ntdef::LPWSTR,
+        winerror::{
+            ERROR_ACCESS_DENIED, ERROR_ELEVATION_REQUIRED,
+            ERROR_INVALID_HANDLE, ERROR_INVALID_PARAMETER,
+            ERROR_NOT_ALL_ASSIGNED, ERROR_PRIVILEGE_NOT_HELD,
+        },
+    },
     um::winbase::{
         FormatMessageW, LocalFree, FORMAT_MESSAGE_ALLOCATE_BUFFER,
         FORMAT_MESSAGE_FROM_SYSTEM,
@@ -41,6 +49,111 @@ impl Error {
     }
 }
 
+impl Error {
+    /// Captures the error code along with its message and a hint on how to
+    /// resolve it, for logging or for showing to users.
+    ///
+    /// The error itself only carries the error code, so the caller passes
+    /// the name of the API that failed, e.g. `"OpenProcess"`, and a summary
+    /// of its arguments, e.g. `format_args!("pid {pid}")`.
+    ///
+    /// Like [`Error::code`], this reads the last error of the calling
+    /// thread, so it must be called before any other Windows API call.
+    pub fn report(
+        &self,
+        api: &'static str,
+        args: impl fmt::Display,
+    ) -> ErrorReport {
+        let code = self.code();
+        let message = code.format_message().trim_end().to_owned();
+        ErrorReport {
+            api,
+            args: args.to_string(),
+            code: code.as_dword(),
+            message,
+        }
+    }
+}
+
+/// A snapshot of an [`Error`], returned by [`Error::report`].
+///
+/// Its `Display` implementation renders the failed call, the code, the
+/// message and the hint on a single line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ErrorReport {
+    api: &'static str,
+    args: String,
+    code: DWORD,
+    message: String,
+}
+
+impl ErrorReport {
+    /// Returns the name of the API that failed.
+    pub fn api(&self) -> &'static str {
+        self.api
+    }
+
+    /// Returns the summary of the arguments that the API was called with.
+    pub fn args(&self) -> &str {
+        &self.args
+    }
+
+    /// Returns the error code.
+    pub fn code(&self) -> DWORD {
+        self.code
+    }
+
+    /// Returns the system message for the error code.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns true if the error is likely to go away when running with
+    /// administrative rights or additional privileges.
+    pub fn elevation_may_help(&self) -> bool {
+        matches!(
+            self.code,
+            ERROR_ACCESS_DENIED
+                | ERROR_PRIVILEGE_NOT_HELD
+                | ERROR_ELEVATION_REQUIRED
+                | ERROR_NOT_ALL_ASSIGNED
+        )
+    }
+
+    /// Returns a suggestion on how to resolve the error, if there is one.
+    pub fn hint(&self) -> Option<&'static str> {
+        Some(match self.code {
+            ERROR_ACCESS_DENIED => {
+                "run elevated, enable SeDebugPrivilege or request fewer \
+                 access rights"
+            }
+            ERROR_PRIVILEGE_NOT_HELD | ERROR_NOT_ALL_ASSIGNED => {
+                "run as a user that holds the required privilege"
+            }
+            ERROR_ELEVATION_REQUIRED => "run elevated",
+            ERROR_INVALID_PARAMETER => {
+                "the process may have exited, or the identifier is invalid"
+            }
+            ERROR_INVALID_HANDLE => "the handle was closed or never valid",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}({}) failed with error {}: {}",
+            self.api, self.args, self.code, self.message
+        )?;
+        if let Some(hint) = self.hint() {
+            write!(f, " (hint: {})", hint)?;
+        }
+        Ok(())
+    }
+}
+
 impl Debug for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let error_code = self.code();
@@ -49,6 +162,12 @@ impl Debug for Error {
     }
 }
 
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> std::io::Error {
+        std::io::Error::from_raw_os_error(err.code().as_dword() as i32)
+    }
+}
+
 impl ErrorCode {
     /// The calling process does not have the required permissions to open the target process.
     pub const ERROR_ACCESS_DENIED: Self = Self(5);
@@ -112,3 +231,22 @@ impl ErrorCode {
         formated_msg
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winapi::um::errhandlingapi::SetLastError;
+
+    #[test]
+    fn report_suggests_elevation_for_access_denied() {
+        unsafe { SetLastError(ERROR_ACCESS_DENIED) };
+        let report = Error(PhantomData).report("OpenProcess", "pid 4");
+        assert_eq!(report.api(), "OpenProcess");
+        assert_eq!(report.args(), "pid 4");
+        assert_eq!(report.code(), ERROR_ACCESS_DENIED);
+        assert!(report.elevation_may_help());
+        let rendered = report.to_string();
+        assert!(rendered.starts_with("OpenProcess(pid 4) failed with error 5"));
+        assert!(rendered.contains("hint: run elevated"));
+    }
+}
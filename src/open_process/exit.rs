@@ -0,0 +1,194 @@
+use core::marker::PhantomData;
+
+use winapi::{
+    shared::{
+        minwindef::DWORD,
+        winerror::{ERROR_ACCESS_DENIED, WAIT_TIMEOUT},
+    },
+    um::{
+        errhandlingapi::GetLastError,
+        minwinbase::STILL_ACTIVE,
+        processthreadsapi::{GetExitCodeProcess, TerminateProcess},
+        synchapi::WaitForSingleObject,
+        winbase::{WAIT_FAILED, WAIT_OBJECT_0},
+    },
+};
+
+use super::{
+    Error, HasProcessQueryLimitedInformation, HasProcessTerminate,
+    ProcessHandle,
+};
+
+/// Whether a process is still running, and its exit code if it is not.
+///
+/// See `ProcessHandle::exit_code`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ExitStatus {
+    /// The process has not exited yet.
+    StillActive,
+    /// The process has exited with the given exit code.
+    Exited(DWORD),
+}
+
+impl ExitStatus {
+    /// Returns the exit code, or `None` if the process is still running.
+    pub fn code(&self) -> Option<DWORD> {
+        match *self {
+            ExitStatus::StillActive => None,
+            ExitStatus::Exited(code) => Some(code),
+        }
+    }
+
+    /// Returns true if the process has exited with exit code 0.
+    pub fn success(&self) -> bool {
+        *self == ExitStatus::Exited(0)
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns whether the process is still running, and its exit code if
+    /// it is not.
+    ///
+    /// Windows reports running processes with the exit code `STILL_ACTIVE`
+    /// (259), so a process that exits with that very code is reported as
+    /// [`ExitStatus::StillActive`]. Waiting on the handle tells the two
+    /// apart.
+    ///
+    /// This corresponds to calling [`GetExitCodeProcess`].
+    ///
+    /// [`GetExitCodeProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getexitcodeprocess
+    pub fn exit_code(&self) -> Result<ExitStatus, Error> {
+        let () = M::ASSERT;
+        let mut code: DWORD = 0;
+        let ok = unsafe { GetExitCodeProcess(self.inner.as_ptr(), &mut code) };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(if code == STILL_ACTIVE {
+            ExitStatus::StillActive
+        } else {
+            ExitStatus::Exited(code)
+        })
+    }
+
+    /// Returns true if the process has not exited yet.
+    ///
+    /// This first checks the exit code, and only if it is `STILL_ACTIVE`
+    /// waits on the handle without blocking to rule out a process that
+    /// exited with that code. The wait needs `SYNCHRONIZE`; without it, such
+    /// a process is reported as running.
+    ///
+    /// This corresponds to calling [`GetExitCodeProcess`] and
+    /// [`WaitForSingleObject`] with a timeout of zero.
+    ///
+    /// [`GetExitCodeProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getexitcodeprocess
+    /// [`WaitForSingleObject`]: https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject
+    pub fn is_running(&self) -> Result<bool, Error> {
+        if let ExitStatus::Exited(_) = self.exit_code()? {
+            return Ok(false);
+        }
+        match unsafe { WaitForSingleObject(self.inner.as_ptr(), 0) } {
+            WAIT_OBJECT_0 => Ok(false),
+            WAIT_TIMEOUT => Ok(true),
+            WAIT_FAILED
+                if unsafe { GetLastError() } == ERROR_ACCESS_DENIED =>
+            {
+                Ok(true)
+            }
+            _ => Err(Error(PhantomData)),
+        }
+    }
+}
+
+impl<M: HasProcessTerminate> ProcessHandle<M> {
+    /// Terminates the process, making it exit with the given exit code.
+    ///
+    /// Termination is asynchronous: the process may still be running when
+    /// this returns, until all of its pending I/O has been cancelled. Wait
+    /// on the handle to know when it is gone. Terminating a process that
+    /// has already exited fails with `ERROR_ACCESS_DENIED`.
+    ///
+    /// This corresponds to calling [`TerminateProcess`].
+    ///
+    /// [`TerminateProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-terminateprocess
+    pub fn terminate(&self, exit_code: u32) -> Result<(), Error> {
+        let () = M::ASSERT;
+        if unsafe { TerminateProcess(self.inner.as_ptr(), exit_code) } == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{
+        current_process, open_process, ComptimeAccessRights,
+    };
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    #[test]
+    fn current_process_is_still_active() {
+        let status = current_process().exit_code().unwrap();
+        assert_eq!(status, ExitStatus::StillActive);
+        assert_eq!(status.code(), None);
+        assert!(current_process().is_running().unwrap());
+    }
+
+    #[test]
+    fn exited_child_reports_its_code() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/c", "exit 3"])
+            .spawn()
+            .unwrap();
+        let handle = open_process::<
+            ComptimeAccessRights<PROCESS_QUERY_LIMITED_INFORMATION>,
+        >(PhantomData, false, &child)
+        .unwrap();
+        child.wait().unwrap();
+        assert_eq!(handle.exit_code().unwrap(), ExitStatus::Exited(3));
+        assert!(!handle.is_running().unwrap());
+    }
+
+    #[test]
+    fn still_active_exit_code_is_not_running() {
+        use winapi::um::winnt::SYNCHRONIZE;
+
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/c", "exit 259"])
+            .spawn()
+            .unwrap();
+        let handle = open_process::<
+            crate::access_rights!(
+                PROCESS_QUERY_LIMITED_INFORMATION,
+                SYNCHRONIZE
+            ),
+        >(PhantomData, false, &child)
+        .unwrap();
+        child.wait().unwrap();
+        assert_eq!(handle.exit_code().unwrap(), ExitStatus::StillActive);
+        assert!(!handle.is_running().unwrap());
+    }
+
+    #[test]
+    fn terminated_child_exits_with_code() {
+        use winapi::um::winnt::PROCESS_TERMINATE;
+
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/q", "/k"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<
+            crate::access_rights!(
+                PROCESS_TERMINATE,
+                PROCESS_QUERY_LIMITED_INFORMATION
+            ),
+        >(PhantomData, false, &child)
+        .unwrap();
+        handle.terminate(7).unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(7));
+        assert_eq!(handle.exit_code().unwrap(), ExitStatus::Exited(7));
+    }
+}
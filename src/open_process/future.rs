@@ -0,0 +1,171 @@
+use core::{
+    ffi::c_void,
+    fmt::{self, Debug, Formatter},
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    ptr,
+    task::{Context, Poll, Waker},
+};
+use std::sync::{Arc, Mutex};
+
+use winapi::{
+    shared::ntdef::BOOLEAN,
+    um::{
+        handleapi::INVALID_HANDLE_VALUE,
+        threadpoollegacyapiset::UnregisterWaitEx,
+        winbase::{RegisterWaitForSingleObject, INFINITE},
+        winnt::{HANDLE, SYNCHRONIZE, WT_EXECUTEONLYONCE},
+    },
+};
+
+use super::{ComptimeAccessRights, Error, HasSynchronize, ProcessHandle};
+
+/// A future that resolves once a process exits, obtained via
+/// `ProcessHandle::wait_async`.
+///
+/// The future does not depend on any particular async runtime.
+pub struct ProcessExit {
+    shared: Arc<Shared>,
+    wait: HANDLE,
+    // The process handle must stay open for as long as the wait is
+    // registered.
+    _process: ProcessHandle<ComptimeAccessRights<SYNCHRONIZE>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    // Whether the process has exited, and the waker of the last poll.
+    state: Mutex<(bool, Option<Waker>)>,
+}
+
+// SAFETY: The wait handle is only used to unregister the wait, which can be
+// done from any thread.
+unsafe impl Send for ProcessExit {}
+unsafe impl Sync for ProcessExit {}
+
+impl<M: HasSynchronize> ProcessHandle<M> {
+    /// Returns a future that resolves once the process exits.
+    ///
+    /// The wait is carried out by the system thread pool, so no thread is
+    /// blocked and nothing is polled in the meantime. The future keeps its
+    /// own handle to the process, so it stays usable after this handle is
+    /// closed.
+    ///
+    /// This corresponds to calling [`RegisterWaitForSingleObject`], with a
+    /// callback that wakes the task awaiting the future.
+    ///
+    /// [`RegisterWaitForSingleObject`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-registerwaitforsingleobject
+    pub fn wait_async(&self) -> Result<ProcessExit, Error> {
+        let () = M::ASSERT;
+        let process = self
+            .with_reduced_access::<ComptimeAccessRights<SYNCHRONIZE>>(
+                PhantomData,
+            )?;
+        let shared = Arc::new(Shared::default());
+        let mut wait: HANDLE = ptr::null_mut();
+        let ok = unsafe {
+            RegisterWaitForSingleObject(
+                &mut wait,
+                process.inner.as_ptr(),
+                Some(on_exit),
+                Arc::as_ptr(&shared) as *mut c_void,
+                INFINITE,
+                WT_EXECUTEONLYONCE,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(ProcessExit { shared, wait, _process: process })
+    }
+}
+
+unsafe extern "system" fn on_exit(shared: *mut c_void, _timed_out: BOOLEAN) {
+    // The future owns a reference to the shared state, and unregisters the
+    // wait before releasing it.
+    let shared = &*(shared as *const Shared);
+    let mut state = shared.state.lock().unwrap();
+    state.0 = true;
+    if let Some(waker) = state.1.take() {
+        waker.wake();
+    }
+}
+
+impl Future for ProcessExit {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.0 {
+            return Poll::Ready(());
+        }
+        state.1 = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Debug for ProcessExit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let exited = self.shared.state.lock().unwrap().0;
+        f.debug_struct("ProcessExit").field("exited", &exited).finish()
+    }
+}
+
+impl Drop for ProcessExit {
+    fn drop(&mut self) {
+        // Waiting for a running callback to complete ensures that it never
+        // accesses the shared state after it has been released.
+        unsafe { UnregisterWaitEx(self.wait, INVALID_HANDLE_VALUE) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::open_process;
+    use std::{
+        task::Wake,
+        thread::{self, Thread},
+    };
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn future_resolves_on_exit() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/q", "/k"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<crate::access_rights!(SYNCHRONIZE)>(
+            PhantomData,
+            false,
+            &child,
+        )
+        .unwrap();
+        let exit = handle.wait_async().unwrap();
+        drop(handle);
+        drop(child.stdin.take());
+        block_on(exit);
+        assert!(child.try_wait().unwrap().is_some());
+    }
+}
@@ -0,0 +1,211 @@
+// The D3DKMT declarations below mirror the names used by d3dkmthk.h.
+#![allow(non_camel_case_types, non_snake_case)]
+
+use core::{mem, ptr};
+use std::time::Duration;
+
+use winapi::shared::{
+    minwindef::{BOOL, UINT, ULONG},
+    ntdef::{HANDLE, LUID, NTSTATUS},
+};
+
+use super::{ntdll, Error, HasProcessQueryLimitedInformation, ProcessHandle};
+
+// The D3DKMT thunks are not exposed by winapi, so the subset we need is
+// declared here.
+
+type D3DKMT_HANDLE = UINT;
+
+const D3DKMT_QUERYSTATISTICS_ADAPTER: UINT = 0;
+const D3DKMT_QUERYSTATISTICS_PROCESS_SEGMENT: UINT = 4;
+const D3DKMT_QUERYSTATISTICS_PROCESS_NODE: UINT = 6;
+
+#[repr(C)]
+struct D3DKMT_ADAPTERINFO {
+    hAdapter: D3DKMT_HANDLE,
+    AdapterLuid: LUID,
+    NumOfSources: ULONG,
+    bPrecisePresentRegionsPreferred: BOOL,
+}
+
+#[repr(C)]
+struct D3DKMT_ENUMADAPTERS2 {
+    NumAdapters: ULONG,
+    pAdapters: *mut D3DKMT_ADAPTERINFO,
+}
+
+#[repr(C)]
+struct D3DKMT_CLOSEADAPTER {
+    hAdapter: D3DKMT_HANDLE,
+}
+
+/// `D3DKMT_QUERYSTATISTICS_RESULT` is a large union of which we only read
+/// the leading fields of a few members, so it is kept opaque.
+#[repr(C, align(8))]
+struct D3DKMT_QUERYSTATISTICS_RESULT([u8; 776]);
+
+#[repr(C)]
+struct D3DKMT_QUERYSTATISTICS {
+    Type: UINT,
+    AdapterLuid: LUID,
+    hProcess: HANDLE,
+    QueryResult: D3DKMT_QUERYSTATISTICS_RESULT,
+    /// The `SegmentId` or `NodeId` member of the trailing query union.
+    QueryId: ULONG,
+}
+
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(mem::size_of::<D3DKMT_QUERYSTATISTICS>() == 808);
+
+#[link(name = "gdi32")]
+extern "system" {
+    fn D3DKMTEnumAdapters2(pData: *mut D3DKMT_ENUMADAPTERS2) -> NTSTATUS;
+    fn D3DKMTCloseAdapter(pData: *const D3DKMT_CLOSEADAPTER) -> NTSTATUS;
+    // Declared as taking a const pointer, but the result is written back
+    // into the structure.
+    fn D3DKMTQueryStatistics(pData: *mut D3DKMT_QUERYSTATISTICS) -> NTSTATUS;
+}
+
+/// GPU usage of a process on a single graphics adapter.
+///
+/// See `ProcessHandle::gpu_usage`.
+#[derive(Clone, Debug)]
+pub struct GpuAdapterUsage {
+    adapter_luid: u64,
+    running_time: Duration,
+    committed_bytes: u64,
+}
+
+impl GpuAdapterUsage {
+    /// Returns the locally unique identifier of the adapter, with the high
+    /// part in the upper 32 bits. This matches the `luid_0x..._0x...` part of
+    /// the "GPU Engine" performance counter instance names.
+    pub fn adapter_luid(&self) -> u64 {
+        self.adapter_luid
+    }
+
+    /// Returns the total time the process has kept the engines of this
+    /// adapter busy, summed over all engines.
+    ///
+    /// Sample this twice and divide the difference by the elapsed wall-clock
+    /// time to get a utilization figure like Task Manager's.
+    pub fn running_time(&self) -> Duration {
+        self.running_time
+    }
+
+    /// Returns the number of bytes of video memory committed by the process
+    /// on this adapter, summed over all memory segments.
+    pub fn committed_bytes(&self) -> u64 {
+        self.committed_bytes
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Queries the GPU running time and video memory commitment of the
+    /// process on every graphics adapter in the system.
+    ///
+    /// The handle must have been opened with at least
+    /// `PROCESS_QUERY_LIMITED_INFORMATION`. This requires Windows 8 or
+    /// newer.
+    ///
+    /// This corresponds to calling [`D3DKMTQueryStatistics`] for every node
+    /// (engine) and memory segment of every adapter.
+    ///
+    /// [`D3DKMTQueryStatistics`]: https://learn.microsoft.com/en-us/windows-hardware/drivers/ddi/d3dkmthk/nf-d3dkmthk-d3dkmtquerystatistics
+    pub fn gpu_usage(&self) -> Result<Vec<GpuAdapterUsage>, Error> {
+        let () = M::ASSERT;
+        let adapters = enum_adapters()?;
+        let result = adapters
+            .iter()
+            .map(|adapter| self.adapter_usage(adapter.AdapterLuid))
+            .collect();
+        for adapter in &adapters {
+            let close = D3DKMT_CLOSEADAPTER { hAdapter: adapter.hAdapter };
+            unsafe { D3DKMTCloseAdapter(&close) };
+        }
+        result
+    }
+
+    fn adapter_usage(&self, luid: LUID) -> Result<GpuAdapterUsage, Error> {
+        let stats = query(D3DKMT_QUERYSTATISTICS_ADAPTER, luid, None, 0)?;
+        // D3DKMT_QUERYSTATISTICS_ADAPTER_INFORMATION starts with the
+        // segment count followed by the node count.
+        let segments = read_u32(&stats, 0);
+        let nodes = read_u32(&stats, 4);
+
+        let process = Some(self.inner.as_ptr());
+        let mut running_time = 0u64;
+        for node in 0..nodes {
+            let kind = D3DKMT_QUERYSTATISTICS_PROCESS_NODE;
+            let stats = query(kind, luid, process, node)?;
+            // D3DKMT_QUERYSTATISTICS_PROCESS_NODE_INFORMATION starts with
+            // the running time in 100ns units.
+            running_time += read_u64(&stats, 0);
+        }
+        let mut committed_bytes = 0u64;
+        for segment in 0..segments {
+            let kind = D3DKMT_QUERYSTATISTICS_PROCESS_SEGMENT;
+            let stats = query(kind, luid, process, segment)?;
+            // D3DKMT_QUERYSTATISTICS_PROCESS_SEGMENT_INFORMATION starts with
+            // the number of bytes committed.
+            committed_bytes += read_u64(&stats, 0);
+        }
+        Ok(GpuAdapterUsage {
+            adapter_luid: (u64::from(luid.HighPart as u32) << 32)
+                | u64::from(luid.LowPart),
+            running_time: Duration::from_nanos(running_time * 100),
+            committed_bytes,
+        })
+    }
+}
+
+fn enum_adapters() -> Result<Vec<D3DKMT_ADAPTERINFO>, Error> {
+    // The first call only reports the number of adapters.
+    let mut data =
+        D3DKMT_ENUMADAPTERS2 { NumAdapters: 0, pAdapters: ptr::null_mut() };
+    ntdll::check(unsafe { D3DKMTEnumAdapters2(&mut data) })?;
+    let mut adapters: Vec<D3DKMT_ADAPTERINFO> =
+        (0..data.NumAdapters).map(|_| unsafe { mem::zeroed() }).collect();
+    data.pAdapters = adapters.as_mut_ptr();
+    ntdll::check(unsafe { D3DKMTEnumAdapters2(&mut data) })?;
+    adapters.truncate(data.NumAdapters as usize);
+    Ok(adapters)
+}
+
+fn query(
+    kind: UINT,
+    luid: LUID,
+    process: Option<HANDLE>,
+    id: ULONG,
+) -> Result<D3DKMT_QUERYSTATISTICS_RESULT, Error> {
+    let mut stats: D3DKMT_QUERYSTATISTICS = unsafe { mem::zeroed() };
+    stats.Type = kind;
+    stats.AdapterLuid = luid;
+    stats.hProcess = process.unwrap_or(ptr::null_mut());
+    stats.QueryId = id;
+    ntdll::check(unsafe { D3DKMTQueryStatistics(&mut stats) })?;
+    Ok(stats.QueryResult)
+}
+
+fn read_u32(result: &D3DKMT_QUERYSTATISTICS_RESULT, offset: usize) -> u32 {
+    let bytes = &result.0[offset..offset + 4];
+    u32::from_ne_bytes(bytes.try_into().unwrap())
+}
+
+fn read_u64(result: &D3DKMT_QUERYSTATISTICS_RESULT, offset: usize) -> u64 {
+    let bytes = &result.0[offset..offset + 8];
+    u64::from_ne_bytes(bytes.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::current_process;
+
+    #[test]
+    fn queries_gpu_usage_of_current_process() {
+        let usage = current_process().gpu_usage().unwrap();
+        for adapter in &usage {
+            assert_ne!(adapter.adapter_luid(), 0);
+        }
+    }
+}
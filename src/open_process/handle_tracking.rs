@@ -0,0 +1,173 @@
+//! A registry of every handle opened by this crate, for hunting down
+//! handle leaks.
+//!
+//! When the `handle_tracking` feature is enabled, every handle created by
+//! this crate is recorded along with its kind, its access rights and a
+//! backtrace of where it was opened, until it is closed. Use [`report`] to
+//! list the handles that are currently open, or [`report_at_exit`] to have
+//! them printed when the process exits.
+//!
+//! This covers process, thread and token handles as well as the handles
+//! owned by other values, such as remote threads, snapshots, job objects
+//! and exit signals. Handles that are opened and closed within a single
+//! call are not recorded.
+//!
+//! Capturing a backtrace for every handle is expensive, so this is meant
+//! for debugging sessions rather than production builds.
+
+use core::{
+    ffi::c_void,
+    fmt::{self, Display, Formatter},
+    ptr::NonNull,
+};
+use std::{
+    backtrace::Backtrace,
+    collections::BTreeMap,
+    sync::{Arc, Mutex, Once},
+};
+
+use winapi::{ctypes::c_int, shared::minwindef::DWORD};
+
+extern "C" {
+    // Provided by the C runtime that every Rust program links against.
+    fn atexit(callback: extern "C" fn()) -> c_int;
+}
+
+static OPEN: Mutex<BTreeMap<usize, TrackedHandle>> =
+    Mutex::new(BTreeMap::new());
+
+/// A handle that has been opened by this crate and not yet closed.
+///
+/// See [`report`].
+#[derive(Clone, Debug)]
+pub struct TrackedHandle {
+    kind: &'static str,
+    handle: usize,
+    access: DWORD,
+    backtrace: Arc<Backtrace>,
+}
+
+impl TrackedHandle {
+    /// Returns the kind of the handle, e.g. `ProcessHandle`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Returns the raw handle value.
+    pub fn handle(&self) -> usize {
+        self.handle
+    }
+
+    /// Returns the access rights the handle was opened with.
+    pub fn access(&self) -> DWORD {
+        self.access
+    }
+
+    /// Returns the backtrace captured when the handle was opened.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+}
+
+impl Display for TrackedHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {:#x} with access {:#x}, opened at:",
+            self.kind, self.handle, self.access
+        )?;
+        write!(f, "{}", self.backtrace)
+    }
+}
+
+/// Returns the handles opened by this crate that are still open, in order
+/// of their handle values.
+pub fn report() -> Vec<TrackedHandle> {
+    open().values().cloned().collect()
+}
+
+/// Arranges for the handles that are still open to be printed to standard
+/// error when the process exits normally.
+///
+/// Calling this more than once has no further effect.
+pub fn report_at_exit() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| unsafe {
+        atexit(print_report);
+    });
+}
+
+extern "C" fn print_report() {
+    let handles = report();
+    if handles.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{} handle(s) opened by winapi-util were leaked:",
+        handles.len()
+    );
+    for handle in handles {
+        eprintln!("{}", handle);
+    }
+}
+
+pub(super) fn opened(
+    handle: NonNull<c_void>,
+    kind: &'static str,
+    access: DWORD,
+) {
+    let value = handle.as_ptr() as usize;
+    let tracked = TrackedHandle {
+        kind,
+        handle: value,
+        access,
+        backtrace: Arc::new(Backtrace::force_capture()),
+    };
+    open().insert(value, tracked);
+}
+
+pub(super) fn closed(handle: NonNull<c_void>) {
+    open().remove(&(handle.as_ptr() as usize));
+}
+
+fn open() -> std::sync::MutexGuard<'static, BTreeMap<usize, TrackedHandle>> {
+    // A panic while holding the lock does not leave the map inconsistent.
+    OPEN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{
+        open_process, snapshot::Snapshot, RuntimeAccessRights,
+    };
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    #[test]
+    fn open_handles_are_reported_until_closed() {
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_LIMITED_INFORMATION,
+            false,
+            std::process::id(),
+        )
+        .unwrap();
+        let value = handle.inner.as_ptr() as usize;
+        let tracked = report().into_iter().find(|h| h.handle() == value);
+        let tracked = tracked.unwrap();
+        assert_eq!(tracked.kind(), "ProcessHandle");
+        assert_eq!(tracked.access(), PROCESS_QUERY_LIMITED_INFORMATION);
+        handle.try_close().unwrap();
+        // The value may be reused by another test right away, so look for
+        // this particular record.
+        let same =
+            |h: &TrackedHandle| Arc::ptr_eq(&h.backtrace, &tracked.backtrace);
+        assert!(!report().iter().any(same));
+    }
+
+    #[test]
+    fn snapshots_are_reported() {
+        let processes = Snapshot::processes().unwrap();
+        assert!(report().iter().any(|h| h.kind() == "Snapshot"));
+        drop(processes);
+    }
+}
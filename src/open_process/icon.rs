@@ -0,0 +1,271 @@
+use core::{marker::PhantomData, mem, ptr};
+use std::path::Path;
+
+use winapi::{
+    ctypes::c_int,
+    shared::{
+        minwindef::{DWORD, UINT},
+        windef::{HBITMAP, HDC, HICON},
+        winerror::{
+            ERROR_INVALID_PARAMETER, ERROR_NOT_SUPPORTED,
+            ERROR_RESOURCE_TYPE_NOT_FOUND,
+        },
+    },
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        shellapi::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON},
+        wingdi::{
+            DeleteObject, GetDIBits, GetObjectW, BITMAP, BITMAPINFO,
+            BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+        },
+        winnt::LPCWSTR,
+        winuser::{DestroyIcon, GetDC, GetIconInfo, ReleaseDC, ICONINFO},
+    },
+};
+
+use super::{
+    canonicalize_image_path, to_wide, Error,
+    HasProcessQueryLimitedInformation, ProcessHandle,
+};
+
+// Not exposed by winapi. This is the only documented API that extracts an
+// icon at an arbitrary size instead of the system's small or large size.
+#[link(name = "user32")]
+extern "system" {
+    fn PrivateExtractIconsW(
+        szFileName: LPCWSTR,
+        nIconIndex: c_int,
+        cxIcon: c_int,
+        cyIcon: c_int,
+        phicon: *mut HICON,
+        piconid: *mut UINT,
+        nIcons: UINT,
+        flags: UINT,
+    ) -> UINT;
+}
+
+/// An icon rendered to 32-bit pixels.
+///
+/// See [`extract_icon`].
+#[derive(Clone, Debug)]
+pub struct Icon {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Icon {
+    /// Returns the width of the icon, in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the icon, in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the pixel data as rows of BGRA quadruplets, top row first.
+    ///
+    /// The alpha channel is straight (not premultiplied).
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Consumes the icon and returns its pixel data.
+    ///
+    /// See [`Icon::pixels`] for the layout.
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Extracts the icon of the executable image of the process.
+    ///
+    /// The handle must have been opened with at least
+    /// `PROCESS_QUERY_LIMITED_INFORMATION`.
+    ///
+    /// This resolves the image path and then calls [`extract_icon`] on it.
+    pub fn image_icon(&self, size: u32) -> Result<Icon, Error> {
+        let image = canonicalize_image_path(self.image_file_name()?)?;
+        extract_icon(image, size)
+    }
+}
+
+/// Extracts the main icon of the file at the given path, rendered as a
+/// square of `size` pixels.
+///
+/// If the file does not contain an icon of its own (e.g., an executable
+/// without an icon resource), then the icon the shell displays for it is
+/// returned instead. In that case, the icon has the system's large icon
+/// size rather than the requested one. If there is no icon at all, then an
+/// error with code `ERROR_RESOURCE_TYPE_NOT_FOUND` is returned, and
+/// monochrome icons fail with `ERROR_NOT_SUPPORTED`.
+///
+/// This corresponds to calling [`PrivateExtractIconsW`], falling back to
+/// [`SHGetFileInfoW`].
+///
+/// [`PrivateExtractIconsW`]: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-privateextracticonsw
+/// [`SHGetFileInfoW`]: https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-shgetfileinfow
+pub fn extract_icon<P: AsRef<Path>>(
+    path: P,
+    size: u32,
+) -> Result<Icon, Error> {
+    let path = to_wide(path.as_ref().as_os_str());
+    let Ok(size) = c_int::try_from(size) else {
+        unsafe { SetLastError(ERROR_INVALID_PARAMETER) };
+        return Err(Error(PhantomData));
+    };
+
+    let mut hicon: HICON = ptr::null_mut();
+    let mut id: UINT = 0;
+    let n = unsafe {
+        PrivateExtractIconsW(
+            path.as_ptr(),
+            0,
+            size,
+            size,
+            &mut hicon,
+            &mut id,
+            1,
+            0,
+        )
+    };
+    if n == UINT::MAX {
+        return Err(Error(PhantomData));
+    }
+    if n == 0 || hicon.is_null() {
+        let mut info: SHFILEINFOW = unsafe { mem::zeroed() };
+        let ok = unsafe {
+            SHGetFileInfoW(
+                path.as_ptr(),
+                0,
+                &mut info,
+                mem::size_of::<SHFILEINFOW>() as UINT,
+                SHGFI_ICON | SHGFI_LARGEICON,
+            )
+        };
+        if ok == 0 || info.hIcon.is_null() {
+            unsafe { SetLastError(ERROR_RESOURCE_TYPE_NOT_FOUND) };
+            return Err(Error(PhantomData));
+        }
+        hicon = info.hIcon;
+    }
+    let icon = unsafe { render(hicon) };
+    // Keep the error code of a failed render across the cleanup.
+    unsafe {
+        let code = GetLastError();
+        DestroyIcon(hicon);
+        SetLastError(code);
+    }
+    icon
+}
+
+/// Renders the given icon to BGRA pixels.
+///
+/// # Safety
+///
+/// `hicon` must be a valid icon handle.
+unsafe fn render(hicon: HICON) -> Result<Icon, Error> {
+    let mut info: ICONINFO = mem::zeroed();
+    if GetIconInfo(hicon, &mut info) == 0 {
+        return Err(Error(PhantomData));
+    }
+    let icon = render_bitmaps(info.hbmColor, info.hbmMask);
+    let code = GetLastError();
+    // GetIconInfo hands us copies of the bitmaps that we must free.
+    if !info.hbmColor.is_null() {
+        DeleteObject(info.hbmColor.cast());
+    }
+    if !info.hbmMask.is_null() {
+        DeleteObject(info.hbmMask.cast());
+    }
+    SetLastError(code);
+    icon
+}
+
+unsafe fn render_bitmaps(
+    color: HBITMAP,
+    mask: HBITMAP,
+) -> Result<Icon, Error> {
+    // Monochrome icons have no color bitmap.
+    if color.is_null() {
+        SetLastError(ERROR_NOT_SUPPORTED);
+        return Err(Error(PhantomData));
+    }
+    let mut bm: BITMAP = mem::zeroed();
+    let len = mem::size_of::<BITMAP>() as c_int;
+    if GetObjectW(color.cast(), len, (&mut bm as *mut BITMAP).cast()) == 0 {
+        return Err(Error(PhantomData));
+    }
+    let (width, height) = (bm.bmWidth, bm.bmHeight);
+
+    let dc = GetDC(ptr::null_mut());
+    if dc.is_null() {
+        return Err(Error(PhantomData));
+    }
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    let ok = read_bits(dc, color, width, height, &mut pixels);
+    // Icons without an alpha channel carry their transparency in the mask,
+    // where set bits mark transparent pixels.
+    let ok = if ok && pixels.chunks_exact(4).all(|px| px[3] == 0) {
+        let mut mask_pixels = vec![0u8; pixels.len()];
+        let ok = !mask.is_null()
+            && read_bits(dc, mask, width, height, &mut mask_pixels);
+        for (px, m) in pixels.chunks_exact_mut(4).zip(mask_pixels.chunks(4)) {
+            px[3] = if ok && m[0] != 0 { 0 } else { 255 };
+        }
+        true
+    } else {
+        ok
+    };
+    let code = GetLastError();
+    ReleaseDC(ptr::null_mut(), dc);
+    if !ok {
+        SetLastError(code);
+        return Err(Error(PhantomData));
+    }
+    Ok(Icon { width: width as u32, height: height as u32, pixels })
+}
+
+/// Reads the given bitmap as top-down 32-bit pixels into `out`.
+unsafe fn read_bits(
+    dc: HDC,
+    bitmap: HBITMAP,
+    width: c_int,
+    height: c_int,
+    out: &mut [u8],
+) -> bool {
+    let mut bi: BITMAPINFO = mem::zeroed();
+    bi.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as DWORD;
+    bi.bmiHeader.biWidth = width;
+    // A negative height requests a top-down bitmap.
+    bi.bmiHeader.biHeight = -height;
+    bi.bmiHeader.biPlanes = 1;
+    bi.bmiHeader.biBitCount = 32;
+    bi.bmiHeader.biCompression = BI_RGB;
+    let lines = GetDIBits(
+        dc,
+        bitmap,
+        0,
+        height as UINT,
+        out.as_mut_ptr().cast(),
+        &mut bi,
+        DIB_RGB_COLORS,
+    );
+    lines == height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notepad_icon_has_requested_size() {
+        let icon =
+            extract_icon(r"C:\Windows\System32\notepad.exe", 48).unwrap();
+        assert_eq!((icon.width(), icon.height()), (48, 48));
+        assert_eq!(icon.pixels().len(), 48 * 48 * 4);
+    }
+}
@@ -0,0 +1,332 @@
+use core::marker::PhantomData;
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
+use winapi::{
+    shared::{
+        minwindef::DWORD,
+        winerror::{ERROR_INSUFFICIENT_BUFFER, ERROR_PATH_NOT_FOUND},
+    },
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        fileapi::{
+            GetLogicalDriveStringsW, GetLongPathNameW, QueryDosDeviceW,
+        },
+        psapi::GetProcessImageFileNameW,
+        winbase::QueryFullProcessImageNameW,
+    },
+};
+
+use super::{
+    from_io_error, Error, HasProcessQueryLimitedInformation, ProcessHandle,
+};
+use crate::{file, Handle};
+
+/// The largest path, in UTF-16 code units, that the kernel will hand us.
+pub(super) const MAX_PATH_WIDE: usize = 32_768;
+
+/// Not declared by winapi.
+const PROCESS_NAME_NATIVE: DWORD = 0x0000_0001;
+
+/// The form of a path returned by `ProcessHandle::image_path`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PathForm {
+    /// A Win32 path such as `C:\Windows\notepad.exe`.
+    Win32,
+    /// A native NT device path such as
+    /// `\Device\HarddiskVolume3\Windows\notepad.exe`.
+    Native,
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the path of the executable image of the process in the native
+    /// NT device form, e.g. `\Device\HarddiskVolume3\Windows\notepad.exe`.
+    ///
+    /// The handle must have been opened with at least
+    /// `PROCESS_QUERY_LIMITED_INFORMATION`. Use [`canonicalize_image_path`]
+    /// to turn the result into a Win32 path.
+    ///
+    /// This corresponds to calling [`GetProcessImageFileNameW`].
+    ///
+    /// [`GetProcessImageFileNameW`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getprocessimagefilenamew
+    pub fn image_file_name(&self) -> Result<PathBuf, Error> {
+        let () = M::ASSERT;
+        let mut buf = vec![0u16; 260];
+        loop {
+            let len = unsafe {
+                GetProcessImageFileNameW(
+                    self.inner.as_ptr(),
+                    buf.as_mut_ptr(),
+                    buf.len() as DWORD,
+                )
+            } as usize;
+            // A return value equal to the buffer size means the name may
+            // have been truncated, so grow and try again.
+            if len != 0 && len < buf.len() {
+                buf.truncate(len);
+                return Ok(PathBuf::from(OsString::from_wide(&buf)));
+            }
+            if buf.len() >= MAX_PATH_WIDE {
+                return Err(Error(PhantomData));
+            }
+            buf.resize(buf.len() * 2, 0);
+        }
+    }
+
+    /// Returns the path of the executable image of the process in the given
+    /// form.
+    ///
+    /// The handle must have been opened with at least
+    /// `PROCESS_QUERY_LIMITED_INFORMATION`.
+    ///
+    /// This corresponds to calling [`QueryFullProcessImageNameW`].
+    ///
+    /// [`QueryFullProcessImageNameW`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-queryfullprocessimagenamew
+    pub fn image_path(&self, form: PathForm) -> Result<PathBuf, Error> {
+        let () = M::ASSERT;
+        let flags = match form {
+            PathForm::Win32 => 0,
+            PathForm::Native => PROCESS_NAME_NATIVE,
+        };
+        let mut buf = vec![0u16; 260];
+        loop {
+            let mut len = buf.len() as DWORD;
+            let ok = unsafe {
+                QueryFullProcessImageNameW(
+                    self.inner.as_ptr(),
+                    flags,
+                    buf.as_mut_ptr(),
+                    &mut len,
+                )
+            };
+            if ok != 0 {
+                buf.truncate(len as usize);
+                return Ok(PathBuf::from(OsString::from_wide(&buf)));
+            }
+            let too_small =
+                unsafe { GetLastError() } == ERROR_INSUFFICIENT_BUFFER;
+            if !too_small || buf.len() >= MAX_PATH_WIDE {
+                return Err(Error(PhantomData));
+            }
+            buf.resize(buf.len() * 2, 0);
+        }
+    }
+}
+
+/// Returns true if and only if the executable image of the given process and
+/// the file at the given path are the same file on disk.
+///
+/// The comparison is done on file identity (volume serial number and file
+/// index) rather than on path strings, so it sees through 8.3 short names,
+/// symbolic links, junctions and differences between the Win32, NT device
+/// and UNC spellings of the same path.
+///
+/// If either file could not be opened or queried, then an error is returned.
+pub fn same_binary<M: HasProcessQueryLimitedInformation, P: AsRef<Path>>(
+    process: &ProcessHandle<M>,
+    path: P,
+) -> Result<bool, Error> {
+    let image = canonicalize_image_path(process.image_file_name()?)?;
+    let information = |path: &Path| {
+        Handle::from_path_any(path)
+            .and_then(file::information)
+            .map_err(from_io_error)
+    };
+    let a = information(&image)?;
+    let b = information(path.as_ref())?;
+    Ok(a.volume_serial_number() == b.volume_serial_number()
+        && a.file_index() == b.file_index())
+}
+
+/// Converts an image path in any of the forms Windows reports into a Win32
+/// path.
+///
+/// The accepted forms are:
+///
+/// * Win32 paths such as `C:\Windows\notepad.exe`, including the verbatim
+///   `\\?\C:\...` and `\\?\UNC\server\share\...` spellings.
+/// * NT device paths such as `\Device\HarddiskVolume3\Windows\notepad.exe`
+///   or `\??\C:\Windows\notepad.exe`, as returned by
+///   `ProcessHandle::image_file_name`.
+/// * UNC paths such as `\\server\share\tool.exe` and their NT spelling
+///   `\Device\Mup\server\share\tool.exe`.
+///
+/// Drive-letter device paths are resolved by querying the DOS device
+/// mappings of the current session. If the file exists, 8.3 short names in
+/// the result are expanded to their long form.
+///
+/// If an NT device path does not correspond to any drive letter, then an
+/// error with code `ERROR_PATH_NOT_FOUND` is returned.
+pub fn canonicalize_image_path<P: AsRef<Path>>(
+    path: P,
+) -> Result<PathBuf, Error> {
+    let wide: Vec<u16> = path.as_ref().as_os_str().encode_wide().collect();
+    let win32 = if let Some(rest) = strip_prefix_ci(&wide, r"\\?\UNC\") {
+        prepend(r"\\", rest)
+    } else if let Some(rest) = strip_prefix_ci(&wide, r"\\?\") {
+        rest.to_vec()
+    } else if let Some(rest) = strip_prefix_ci(&wide, r"\??\UNC\") {
+        prepend(r"\\", rest)
+    } else if let Some(rest) = strip_prefix_ci(&wide, r"\??\") {
+        rest.to_vec()
+    } else if let Some(rest) = strip_prefix_ci(&wide, r"\Device\Mup\") {
+        prepend(r"\\", rest)
+    } else if let Some(rest) =
+        strip_prefix_ci(&wide, r"\Device\LanmanRedirector\")
+    {
+        prepend(r"\\", rest)
+    } else if strip_prefix_ci(&wide, r"\Device\").is_some() {
+        device_to_drive(&wide)?
+    } else {
+        wide
+    };
+    Ok(PathBuf::from(OsString::from_wide(&long_path_name(win32))))
+}
+
+/// Replaces the `\Device\...` prefix of the given path with the drive letter
+/// that maps to that device.
+fn device_to_drive(path: &[u16]) -> Result<Vec<u16>, Error> {
+    let mut drives = vec![0u16; 512];
+    let len = unsafe {
+        GetLogicalDriveStringsW(drives.len() as DWORD, drives.as_mut_ptr())
+    } as usize;
+    if len == 0 || len > drives.len() {
+        return Err(Error(PhantomData));
+    }
+    // The buffer is a sequence of NUL terminated strings such as `C:\`.
+    for drive in drives[..len].split(|&c| c == 0).filter(|d| d.len() >= 2) {
+        let mut name = drive[..2].to_vec();
+        name.push(0);
+        let mut target = vec![0u16; 1024];
+        let n = unsafe {
+            QueryDosDeviceW(
+                name.as_ptr(),
+                target.as_mut_ptr(),
+                target.len() as DWORD,
+            )
+        };
+        if n == 0 {
+            continue;
+        }
+        // The target list may contain several NUL separated entries, the
+        // first of which is the current mapping.
+        let device = target.split(|&c| c == 0).next().unwrap_or(&[]);
+        if device.is_empty() || path.len() <= device.len() {
+            continue;
+        }
+        if eq_ignore_case(&path[..device.len()], device)
+            && path[device.len()] == u16::from(b'\\')
+        {
+            let mut out = drive[..2].to_vec();
+            out.extend_from_slice(&path[device.len()..]);
+            return Ok(out);
+        }
+    }
+    // No drive letter maps to the device.
+    unsafe { SetLastError(ERROR_PATH_NOT_FOUND) };
+    Err(Error(PhantomData))
+}
+
+/// Expands 8.3 short names in the given path. If the expansion fails (e.g.,
+/// because the file does not exist), then the path is returned unchanged.
+fn long_path_name(path: Vec<u16>) -> Vec<u16> {
+    let mut input = path.clone();
+    input.push(0);
+    let mut buf = vec![0u16; input.len().max(260)];
+    loop {
+        let len = unsafe {
+            GetLongPathNameW(
+                input.as_ptr(),
+                buf.as_mut_ptr(),
+                buf.len() as DWORD,
+            )
+        } as usize;
+        if len == 0 {
+            return path;
+        }
+        // On success the length excludes the NUL terminator. Otherwise, it
+        // is the required buffer size including the terminator.
+        if len < buf.len() {
+            buf.truncate(len);
+            return buf;
+        }
+        buf.resize(len, 0);
+    }
+}
+
+fn strip_prefix_ci<'a>(path: &'a [u16], prefix: &str) -> Option<&'a [u16]> {
+    let prefix: Vec<u16> = OsStr::new(prefix).encode_wide().collect();
+    if path.len() >= prefix.len()
+        && eq_ignore_case(&path[..prefix.len()], &prefix)
+    {
+        Some(&path[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn prepend(prefix: &str, rest: &[u16]) -> Vec<u16> {
+    let mut out: Vec<u16> = OsStr::new(prefix).encode_wide().collect();
+    out.extend_from_slice(rest);
+    out
+}
+
+/// Compares two UTF-16 strings, ignoring ASCII case. This is sufficient for
+/// device names and path prefixes, which are always ASCII.
+fn eq_ignore_case(a: &[u16], b: &[u16]) -> bool {
+    let lower = |c: u16| match u8::try_from(c) {
+        Ok(c) => u16::from(c.to_ascii_lowercase()),
+        Err(_) => c,
+    };
+    a.len() == b.len() && a.iter().zip(b).all(|(&x, &y)| lower(x) == lower(y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonicalize_verbatim_and_unc_forms() {
+        let cases = [
+            (r"\\?\C:\x\y.exe", r"C:\x\y.exe"),
+            (r"\??\C:\x\y.exe", r"C:\x\y.exe"),
+            (r"\\?\UNC\srv\share\y.exe", r"\\srv\share\y.exe"),
+            (r"\Device\Mup\srv\share\y.exe", r"\\srv\share\y.exe"),
+            (r"\\srv\share\y.exe", r"\\srv\share\y.exe"),
+        ];
+        for (input, expected) in cases {
+            let got = canonicalize_image_path(input).unwrap();
+            assert_eq!(got, Path::new(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn image_path_in_both_forms() {
+        let process = crate::open_process::current_process();
+        let win32 = process.image_path(PathForm::Win32).unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(win32.canonicalize().unwrap(), exe.canonicalize().unwrap());
+        let native = process.image_path(PathForm::Native).unwrap();
+        assert!(native.starts_with(r"\Device\"));
+        assert_eq!(canonicalize_image_path(native).unwrap(), win32);
+    }
+
+    #[test]
+    fn same_binary_as_current_exe() {
+        use crate::open_process::{open_process, RuntimeAccessRights};
+        use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_LIMITED_INFORMATION,
+            false,
+            std::process::id(),
+        )
+        .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert!(same_binary(&handle, &exe).unwrap());
+        assert!(!same_binary(&handle, r"C:\Windows\System32\cmd.exe").unwrap());
+    }
+}
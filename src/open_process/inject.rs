@@ -0,0 +1,176 @@
+//! Loading DLLs into other processes.
+//!
+//! [`load_library`] makes a process load a DLL as if it had called
+//! `LoadLibraryW` itself, which runs the `DllMain` of the DLL on a new
+//! thread of that process:
+//!
+//! ```no_run
+//! use core::marker::PhantomData;
+//! use std::path::Path;
+//! use winapi_util::open_process::{inject, open_process, AllAccess};
+//!
+//! let process = open_process::<AllAccess>(PhantomData, false, 1234).unwrap();
+//! let dll = Path::new(r"C:\tools\hook.dll");
+//! let module = inject::load_library(&process, dll).unwrap();
+//! println!("loaded at {:#x}", module.base_address());
+//! ```
+
+use core::marker::PhantomData;
+use std::{ffi::OsStr, path::Path};
+
+use winapi::{
+    shared::winerror::{ERROR_MOD_NOT_FOUND, ERROR_NOT_SUPPORTED},
+    um::{errhandlingapi::SetLastError, winnt::PAGE_READWRITE},
+};
+
+use super::{
+    eq_ignore_ascii_case, to_wide, Error, ExitStatus, HasProcessCreateThread,
+    HasProcessQueryInformation, HasProcessQueryLimitedInformation,
+    HasProcessVmOperation, HasProcessVmRead, HasProcessVmWrite, ProcessArch,
+    ProcessHandle, RemoteModule,
+};
+
+/// Makes a process load a DLL, and returns the module of the DLL in the
+/// process.
+///
+/// The path is resolved by the process, so it should be absolute: a
+/// relative path is looked up in the DLL search path of the process, which
+/// starts with the directory of its executable. If the DLL is already
+/// loaded, its reference count is incremented and the module is returned.
+///
+/// Only processes of the same architecture as the current one are
+/// supported, and others fail with `ERROR_NOT_SUPPORTED`, since the thread
+/// that loads the DLL has to start in code of the architecture of the
+/// process. If the process fails to load the DLL, e.g. because it does not
+/// exist, or because its `DllMain` fails, this fails with
+/// `ERROR_MOD_NOT_FOUND`, as the actual error is only known to the process.
+///
+/// This blocks until the DLL is loaded, and thus until its `DllMain`
+/// returns, which never happens if the process is suspended or deadlocked
+/// on the loader lock.
+///
+/// This corresponds to calling [`VirtualAllocEx`] and
+/// [`WriteProcessMemory`] to copy the path into the process, resolving
+/// `LoadLibraryW` in its `kernel32.dll`, and calling
+/// [`CreateRemoteThread`] to run it.
+///
+/// [`VirtualAllocEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualallocex
+/// [`WriteProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-writeprocessmemory
+/// [`CreateRemoteThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-createremotethread
+pub fn load_library<M>(
+    process: &ProcessHandle<M>,
+    dll_path: &Path,
+) -> Result<RemoteModule, Error>
+where
+    M: HasProcessCreateThread
+        + HasProcessQueryInformation
+        + HasProcessQueryLimitedInformation
+        + HasProcessVmOperation
+        + HasProcessVmRead
+        + HasProcessVmWrite,
+{
+    if process.architecture()? != ProcessArch::current() {
+        unsafe { SetLastError(ERROR_NOT_SUPPORTED) };
+        return Err(Error(PhantomData));
+    }
+    let load_library = find_module(process.modules()?, "kernel32.dll")?
+        .export_address(process, "LoadLibraryW")?;
+    let Some(load_library) = load_library else {
+        unsafe { SetLastError(ERROR_MOD_NOT_FOUND) };
+        return Err(Error(PhantomData));
+    };
+
+    let path = to_wide(dll_path.as_os_str());
+    let bytes: Vec<u8> = path.iter().flat_map(|u| u.to_ne_bytes()).collect();
+    let allocation = process.alloc(bytes.len(), PAGE_READWRITE)?;
+    allocation.write(0, &bytes)?;
+    let thread = unsafe {
+        process.create_remote_thread(load_library, allocation.address(), 0)?
+    };
+    thread.wait(None)?;
+    // The thread exits with the low 32 bits of the module handle, which is
+    // the base address of the module, or 0 if loading failed.
+    let low_bits = match thread.exit_code()? {
+        ExitStatus::Exited(code) if code != 0 => code,
+        _ => {
+            unsafe { SetLastError(ERROR_MOD_NOT_FOUND) };
+            return Err(Error(PhantomData));
+        }
+    };
+    drop(thread);
+    drop(allocation);
+
+    let file_name = dll_path.file_name().unwrap_or_default();
+    let module = process.modules()?.into_iter().find(|module| {
+        module.base_address() as u32 == low_bits
+            && names_equal(module.path().file_name(), file_name)
+    });
+    match module {
+        Some(module) => Ok(module),
+        None => {
+            // The DLL may have been unloaded by another thread already.
+            unsafe { SetLastError(ERROR_MOD_NOT_FOUND) };
+            Err(Error(PhantomData))
+        }
+    }
+}
+
+/// Finds a module by file name, or fails with `ERROR_MOD_NOT_FOUND`.
+fn find_module(
+    modules: Vec<RemoteModule>,
+    name: &str,
+) -> Result<RemoteModule, Error> {
+    let module = modules.into_iter().find(|module| {
+        names_equal(module.path().file_name(), OsStr::new(name))
+    });
+    module.ok_or_else(|| {
+        unsafe { SetLastError(ERROR_MOD_NOT_FOUND) };
+        Error(PhantomData)
+    })
+}
+
+/// Compares file names, ignoring the case of ASCII letters.
+fn names_equal(name: Option<&OsStr>, expected: &OsStr) -> bool {
+    name.is_some_and(|name| eq_ignore_ascii_case(name, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{open_process, AllAccess};
+    use std::{path::PathBuf, thread, time::Duration};
+
+    #[test]
+    fn loads_library_into_child() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .spawn()
+            .unwrap();
+        let process =
+            open_process::<AllAccess>(PhantomData, false, &child).unwrap();
+        // Wait for the loader to initialize the child.
+        for _ in 0..100 {
+            let modules = process.modules().unwrap_or_default();
+            if find_module(modules, "kernel32.dll").is_ok() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let system = std::env::var_os("SystemRoot").unwrap();
+        let dll = PathBuf::from(system).join(r"System32\version.dll");
+        let loaded = load_library(&process, &dll);
+        let missing =
+            load_library(&process, Path::new(r"C:\no\such\library.dll"))
+                .map_err(|err| err.code().as_dword());
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let module = loaded.unwrap();
+        assert!(module
+            .path()
+            .file_name()
+            .unwrap()
+            .eq_ignore_ascii_case("version.dll"));
+        assert_eq!(missing.unwrap_err(), ERROR_MOD_NOT_FOUND);
+    }
+}
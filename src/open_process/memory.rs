@@ -0,0 +1,589 @@
+use core::{
+    ffi::c_void,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr,
+};
+use std::collections::BTreeSet;
+#[cfg(feature = "ntdll")]
+use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, MAX_PATH},
+        winerror::{ERROR_INVALID_PARAMETER, ERROR_PARTIAL_COPY},
+    },
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        memoryapi::{ReadProcessMemory, VirtualQueryEx},
+        psapi::GetMappedFileNameW,
+        winnt::{
+            HANDLE, MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_FREE, MEM_IMAGE,
+            MEM_MAPPED, MEM_PRIVATE, PAGE_EXECUTE, PAGE_EXECUTE_READ,
+            PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD,
+            PAGE_NOACCESS,
+        },
+    },
+};
+
+use super::{
+    Error, HasProcessQueryInformation, HasProcessVmRead, ProcessHandle,
+};
+
+const PAGE_EXECUTE_ANY: DWORD = PAGE_EXECUTE
+    | PAGE_EXECUTE_READ
+    | PAGE_EXECUTE_READWRITE
+    | PAGE_EXECUTE_WRITECOPY;
+
+/// A region of executable memory that is not backed by a file on disk, or
+/// that belongs to an image the loader does not know about.
+///
+/// See `ProcessHandle::find_unbacked_executable_regions`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct UnbackedRegion {
+    base_address: usize,
+    allocation_base: usize,
+    size: usize,
+    protection: DWORD,
+    private: bool,
+    unlisted_image: bool,
+}
+
+impl UnbackedRegion {
+    /// Returns the address the region starts at.
+    pub fn base_address(&self) -> usize {
+        self.base_address
+    }
+
+    /// Returns the address of the allocation the region is part of.
+    pub fn allocation_base(&self) -> usize {
+        self.allocation_base
+    }
+
+    /// Returns the size of the region, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the current protection of the pages of the region, one of
+    /// the `PAGE_EXECUTE*` constants, possibly combined with modifiers such
+    /// as `PAGE_NOCACHE`.
+    pub fn protection(&self) -> DWORD {
+        self.protection
+    }
+
+    /// Returns true if the region is private memory, e.g. allocated with
+    /// `VirtualAllocEx`, and false if it is a mapped section.
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Returns true if the region is part of an image mapping whose
+    /// allocation base is not in the module list of the process, e.g. a
+    /// manually mapped DLL. Such a region may still be backed by a file.
+    pub fn is_unlisted_image(&self) -> bool {
+        self.unlisted_image
+    }
+}
+
+/// A region of the address space of a process, in which all pages have the
+/// same state, protection and type.
+///
+/// See `ProcessHandle::memory_regions`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MemoryRegion {
+    base_address: usize,
+    allocation_base: usize,
+    size: usize,
+    state: MemoryState,
+    protection: DWORD,
+    memory_type: Option<MemoryType>,
+}
+
+impl MemoryRegion {
+    fn from_info(info: &MEMORY_BASIC_INFORMATION) -> MemoryRegion {
+        let state = match info.State {
+            MEM_COMMIT => MemoryState::Commit,
+            MEM_FREE => MemoryState::Free,
+            _ => MemoryState::Reserve,
+        };
+        let memory_type = match info.Type {
+            MEM_IMAGE => Some(MemoryType::Image),
+            MEM_MAPPED => Some(MemoryType::Mapped),
+            MEM_PRIVATE => Some(MemoryType::Private),
+            _ => None,
+        };
+        MemoryRegion {
+            base_address: info.BaseAddress as usize,
+            allocation_base: info.AllocationBase as usize,
+            size: info.RegionSize,
+            state,
+            protection: info.Protect,
+            memory_type,
+        }
+    }
+
+    /// Returns the address the region starts at.
+    pub fn base_address(&self) -> usize {
+        self.base_address
+    }
+
+    /// Returns the address of the allocation the region is part of, or 0
+    /// if the region is free.
+    pub fn allocation_base(&self) -> usize {
+        self.allocation_base
+    }
+
+    /// Returns the size of the region, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the state of the pages of the region.
+    pub fn state(&self) -> MemoryState {
+        self.state
+    }
+
+    /// Returns the current protection of the pages of the region, one of
+    /// the `PAGE_*` constants, possibly combined with modifiers such as
+    /// `PAGE_GUARD`. This is only meaningful if the region is committed.
+    pub fn protection(&self) -> DWORD {
+        self.protection
+    }
+
+    /// Returns the type of the pages of the region, or `None` if the region
+    /// is free.
+    pub fn memory_type(&self) -> Option<MemoryType> {
+        self.memory_type
+    }
+
+    /// Returns true if the pages of the region are committed and can be
+    /// read, i.e. they are neither inaccessible nor guard pages.
+    pub fn is_readable(&self) -> bool {
+        self.state == MemoryState::Commit
+            && self.protection & (PAGE_NOACCESS | PAGE_GUARD) == 0
+    }
+}
+
+/// The state of the pages of a [`MemoryRegion`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MemoryState {
+    /// The pages are backed by physical memory or the paging file.
+    Commit,
+    /// The pages are reserved, but not backed by any storage.
+    Reserve,
+    /// The pages are neither committed nor reserved.
+    Free,
+}
+
+/// The type of the pages of a [`MemoryRegion`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MemoryType {
+    /// The pages map an executable image, such as a DLL.
+    Image,
+    /// The pages map a section, such as a file mapping.
+    Mapped,
+    /// The pages are private to the process, e.g. allocated with
+    /// `VirtualAllocEx` or used by a heap or a stack.
+    Private,
+}
+
+/// An iterator over the regions of the address space of a process,
+/// returned by `ProcessHandle::memory_regions`.
+///
+/// The walk ends after the first error.
+pub struct MemoryRegions<'a, M: HasProcessQueryInformation> {
+    process: &'a ProcessHandle<M>,
+    next: Option<usize>,
+}
+
+impl<M: HasProcessQueryInformation> Iterator for MemoryRegions<'_, M> {
+    type Item = Result<MemoryRegion, Error>;
+
+    fn next(&mut self) -> Option<Result<MemoryRegion, Error>> {
+        let address = self.next?;
+        match query(self.process.inner.as_ptr(), address) {
+            Ok(Some(info)) => {
+                let region = MemoryRegion::from_info(&info);
+                self.next = region.base_address.checked_add(region.size);
+                Some(Ok(region))
+            }
+            Ok(None) => {
+                self.next = None;
+                None
+            }
+            Err(err) => {
+                self.next = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<M: HasProcessQueryInformation> Debug for MemoryRegions<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryRegions").field("next", &self.next).finish()
+    }
+}
+
+impl<M: HasProcessQueryInformation> ProcessHandle<M> {
+    /// Returns the regions of the address space of the process, from the
+    /// lowest address to the highest, including free and reserved ones.
+    ///
+    /// The regions are queried one at a time as the iterator advances, so
+    /// they may change while the walk is in progress. The walk stops at the
+    /// end of the address space of the process, which for 32-bit processes
+    /// is lower than that of the current process.
+    ///
+    /// This corresponds to calling [`VirtualQueryEx`] for every region.
+    ///
+    /// [`VirtualQueryEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualqueryex
+    pub fn memory_regions(&self) -> MemoryRegions<'_, M> {
+        let () = M::ASSERT;
+        MemoryRegions { process: self, next: Some(0) }
+    }
+}
+
+impl<M: HasProcessQueryInformation + HasProcessVmRead> ProcessHandle<M> {
+    /// Returns the committed regions of executable memory in the process
+    /// that are not backed by an image or another file on disk, or that
+    /// belong to an image missing from the module list of the process.
+    ///
+    /// Code loaded by the system loader always lives in image sections that
+    /// map a file and are listed by `ProcessHandle::modules`, so executable
+    /// private memory, or an image section the loader does not know about,
+    /// hints at code that was written into the process by other means, such
+    /// as a manually mapped DLL or injected shellcode. Just-in-time
+    /// compilers, e.g. those of browsers or of the .NET runtime, produce
+    /// such regions legitimately, so the result is a starting point for
+    /// further inspection rather than a verdict.
+    ///
+    /// This corresponds to calling `ProcessHandle::modules`, then
+    /// [`VirtualQueryEx`] for every region of the address space of the
+    /// process, and [`GetMappedFileNameW`] for those that are not private.
+    ///
+    /// [`VirtualQueryEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualqueryex
+    /// [`GetMappedFileNameW`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getmappedfilenamew
+    pub fn find_unbacked_executable_regions(
+        &self,
+    ) -> Result<Vec<UnbackedRegion>, Error> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        let process = self.inner.as_ptr();
+        let modules: BTreeSet<usize> = self
+            .modules()?
+            .iter()
+            .map(|module| module.base_address())
+            .collect();
+        let mut regions = Vec::new();
+        for region in self.memory_regions() {
+            let region = region?;
+            let executable = region.state == MemoryState::Commit
+                && region.protection & PAGE_EXECUTE_ANY != 0
+                && region.protection & PAGE_GUARD == 0;
+            if !executable {
+                continue;
+            }
+            let private = region.memory_type == Some(MemoryType::Private);
+            let unlisted_image = region.memory_type == Some(MemoryType::Image)
+                && !modules.contains(&region.allocation_base);
+            let base = region.base_address as *mut c_void;
+            if private || unlisted_image || !is_file_backed(process, base) {
+                regions.push(UnbackedRegion {
+                    base_address: region.base_address,
+                    allocation_base: region.allocation_base,
+                    size: region.size,
+                    protection: region.protection,
+                    private,
+                    unlisted_image,
+                });
+            }
+        }
+        Ok(regions)
+    }
+}
+
+/// Queries the region of the address space of a process that contains an
+/// address, or returns `None` if the address is past the end of the
+/// address space.
+fn query(
+    process: HANDLE,
+    address: usize,
+) -> Result<Option<MEMORY_BASIC_INFORMATION>, Error> {
+    let mut info: MEMORY_BASIC_INFORMATION = unsafe { mem::zeroed() };
+    let written = unsafe {
+        VirtualQueryEx(
+            process,
+            address as *const _,
+            &mut info,
+            mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+        )
+    };
+    if written == 0 {
+        // Querying past the end of the address space fails with
+        // ERROR_INVALID_PARAMETER.
+        if unsafe { GetLastError() } == ERROR_INVALID_PARAMETER {
+            return Ok(None);
+        }
+        return Err(Error(PhantomData));
+    }
+    Ok(Some(info))
+}
+
+fn is_file_backed(process: HANDLE, address: *mut c_void) -> bool {
+    let mut name = [0u16; MAX_PATH];
+    let len = unsafe {
+        GetMappedFileNameW(
+            process,
+            address,
+            name.as_mut_ptr(),
+            name.len() as DWORD,
+        )
+    };
+    len != 0
+}
+
+/// Reads a value of type `T` from the memory of another process.
+///
+/// # Safety
+///
+/// Any bit pattern must be a valid value of type `T`.
+pub(super) unsafe fn read<T>(
+    process: HANDLE,
+    address: usize,
+) -> Result<T, Error> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let ok = ReadProcessMemory(
+        process,
+        address as *const _,
+        value.as_mut_ptr().cast(),
+        mem::size_of::<T>(),
+        ptr::null_mut(),
+    );
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(value.assume_init())
+}
+
+/// Reads `len` bytes from the memory of another process.
+pub(super) fn read_bytes(
+    process: HANDLE,
+    address: usize,
+    len: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![0u8; len];
+    let ok = unsafe {
+        ReadProcessMemory(
+            process,
+            address as *const _,
+            buf.as_mut_ptr().cast(),
+            len,
+            ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(buf)
+}
+
+/// The alignment of chunks that strings are read in. Pages are at least
+/// this large, so a chunk never spans readable and unreadable memory.
+const STRING_CHUNK: usize = 4096;
+
+/// Reads a string of units of `unit` bytes terminated by a zero unit from
+/// the memory of another process, and returns its bytes without the
+/// terminator. At most `max_len` units are read.
+///
+/// The string is read in chunks that end at page boundaries, so that
+/// unreadable memory after the terminator does not make the read fail.
+pub(super) fn read_terminated(
+    process: HANDLE,
+    address: usize,
+    unit: usize,
+    max_len: usize,
+) -> Result<Vec<u8>, Error> {
+    let max_bytes = max_len.saturating_mul(unit);
+    let mut buf = Vec::new();
+    let mut scanned = 0;
+    while buf.len() < max_bytes {
+        let start = address.wrapping_add(buf.len());
+        let to_boundary = STRING_CHUNK - start % STRING_CHUNK;
+        let len = to_boundary.min(max_bytes - buf.len());
+        let chunk = match read_bytes(process, start, len) {
+            Ok(chunk) => chunk,
+            // The string runs into memory that cannot be read.
+            Err(_) if !buf.is_empty() => {
+                unsafe { SetLastError(ERROR_PARTIAL_COPY) };
+                return Err(Error(PhantomData));
+            }
+            Err(err) => return Err(err),
+        };
+        buf.extend_from_slice(&chunk);
+        // Units may straddle chunks, so only whole ones are checked.
+        while scanned + unit <= buf.len() {
+            if buf[scanned..scanned + unit].iter().all(|&b| b == 0) {
+                buf.truncate(scanned);
+                return Ok(buf);
+            }
+            scanned += unit;
+        }
+    }
+    buf.truncate(max_bytes);
+    Ok(buf)
+}
+
+/// Reads a UTF-16 string of `len` bytes from the memory of another process.
+#[cfg(feature = "ntdll")]
+pub(super) fn read_wide(
+    process: HANDLE,
+    address: usize,
+    len: usize,
+) -> Result<OsString, Error> {
+    Ok(OsString::from_wide(&read_wide_units(process, address, len)?))
+}
+
+/// Reads `len` bytes of UTF-16 code units from the memory of another
+/// process.
+#[cfg(feature = "ntdll")]
+pub(super) fn read_wide_units(
+    process: HANDLE,
+    address: usize,
+    len: usize,
+) -> Result<Vec<u16>, Error> {
+    let len = len / mem::size_of::<u16>();
+    if len == 0 || address == 0 {
+        return Ok(Vec::new());
+    }
+    let mut buf = vec![0u16; len];
+    let ok = unsafe {
+        ReadProcessMemory(
+            process,
+            address as *const _,
+            buf.as_mut_ptr().cast(),
+            len * mem::size_of::<u16>(),
+            ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+    use core::ptr;
+    use winapi::um::{
+        memoryapi::{VirtualAlloc, VirtualFree},
+        winnt::{MEM_RELEASE, MEM_RESERVE, PAGE_READWRITE},
+    };
+
+    #[test]
+    fn private_executable_memory_is_found() {
+        let size = 4096;
+        let base = unsafe {
+            VirtualAlloc(
+                ptr::null_mut(),
+                size,
+                MEM_COMMIT | MEM_RESERVE,
+                PAGE_EXECUTE_READWRITE,
+            )
+        };
+        assert!(!base.is_null());
+        let regions =
+            current_process().find_unbacked_executable_regions().unwrap();
+        unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+        let region = regions
+            .iter()
+            .find(|r| r.base_address() == base as usize)
+            .unwrap();
+        assert!(region.is_private());
+        assert_eq!(region.size(), size);
+        assert_eq!(region.protection(), PAGE_EXECUTE_READWRITE);
+        assert!(!region.is_unlisted_image());
+    }
+
+    #[test]
+    fn manually_mapped_image_is_found() {
+        use std::{fs::File, os::windows::io::AsRawHandle};
+        use winapi::um::{
+            handleapi::CloseHandle,
+            memoryapi::{
+                CreateFileMappingW, MapViewOfFile, UnmapViewOfFile,
+                FILE_MAP_READ,
+            },
+            winnt::{PAGE_READONLY, SEC_IMAGE},
+        };
+
+        let file = File::open(r"C:\Windows\System32\shell32.dll").unwrap();
+        let mapping = unsafe {
+            CreateFileMappingW(
+                file.as_raw_handle().cast(),
+                ptr::null_mut(),
+                PAGE_READONLY | SEC_IMAGE,
+                0,
+                0,
+                ptr::null(),
+            )
+        };
+        assert!(!mapping.is_null());
+        let view = unsafe { MapViewOfFile(mapping, FILE_MAP_READ, 0, 0, 0) };
+        assert!(!view.is_null());
+        let regions =
+            current_process().find_unbacked_executable_regions().unwrap();
+        unsafe {
+            UnmapViewOfFile(view);
+            CloseHandle(mapping);
+        }
+        let region = regions
+            .iter()
+            .find(|r| r.allocation_base() == view as usize)
+            .unwrap();
+        assert!(region.is_unlisted_image());
+        assert!(!region.is_private());
+    }
+
+    #[test]
+    fn memory_regions_cover_allocation() {
+        let size = 3 * 4096;
+        let base = unsafe {
+            VirtualAlloc(ptr::null_mut(), size, MEM_RESERVE, PAGE_NOACCESS)
+        };
+        assert!(!base.is_null());
+        unsafe { VirtualAlloc(base, 4096, MEM_COMMIT, PAGE_READWRITE) };
+        let regions =
+            current_process().memory_regions().collect::<Result<Vec<_>, _>>();
+        unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+        let regions = regions.unwrap();
+
+        // The regions are contiguous and start at 0.
+        assert_eq!(regions[0].base_address(), 0);
+        for pair in regions.windows(2) {
+            assert_eq!(
+                pair[0].base_address() + pair[0].size(),
+                pair[1].base_address()
+            );
+        }
+        let committed = regions
+            .iter()
+            .find(|r| r.base_address() == base as usize)
+            .unwrap();
+        assert_eq!(committed.size(), 4096);
+        assert_eq!(committed.state(), MemoryState::Commit);
+        assert_eq!(committed.memory_type(), Some(MemoryType::Private));
+        assert!(committed.is_readable());
+        let reserved = regions
+            .iter()
+            .find(|r| r.base_address() == base as usize + 4096)
+            .unwrap();
+        assert_eq!(reserved.size(), 2 * 4096);
+        assert_eq!(reserved.state(), MemoryState::Reserve);
+        assert_eq!(reserved.allocation_base(), base as usize);
+        assert!(!reserved.is_readable());
+    }
+}
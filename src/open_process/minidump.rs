@@ -0,0 +1,166 @@
+use core::{ffi::c_void, marker::PhantomData, ptr};
+use std::{fs::File, os::windows::io::AsRawHandle, path::Path};
+
+use winapi::{
+    shared::minwindef::{BOOL, DWORD},
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        winnt::HANDLE,
+    },
+};
+
+use super::{
+    Error, HasProcessQueryInformation, HasProcessVmRead, ProcessHandle,
+};
+
+// winapi does not declare MiniDumpWriteDump.
+#[link(name = "dbghelp")]
+extern "system" {
+    fn MiniDumpWriteDump(
+        hProcess: HANDLE,
+        ProcessId: DWORD,
+        hFile: HANDLE,
+        DumpType: DWORD,
+        ExceptionParam: *mut c_void,
+        UserStreamParam: *mut c_void,
+        CallbackParam: *mut c_void,
+    ) -> BOOL;
+}
+
+// The `MINIDUMP_TYPE` flags used by the presets.
+const MINI_DUMP_WITH_FULL_MEMORY: DWORD = 0x0000_0002;
+const MINI_DUMP_WITH_HANDLE_DATA: DWORD = 0x0000_0004;
+const MINI_DUMP_WITH_UNLOADED_MODULES: DWORD = 0x0000_0020;
+const MINI_DUMP_FILTER_MODULE_PATHS: DWORD = 0x0000_0080;
+const MINI_DUMP_WITH_FULL_MEMORY_INFO: DWORD = 0x0000_0800;
+const MINI_DUMP_WITH_THREAD_INFO: DWORD = 0x0000_1000;
+const MINI_DUMP_FILTER_TRIAGE: DWORD = 0x0010_0000;
+
+/// What a dump written by `ProcessHandle::write_minidump` contains.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MinidumpType {
+    /// The stacks of all threads, the loaded and recently unloaded modules,
+    /// and the open handles, which is enough to see where every thread is.
+    /// Such dumps usually take a few megabytes.
+    Mini,
+    /// Everything in [`MinidumpType::Mini`], plus all of the memory of the
+    /// process, so that any data can be inspected. Such dumps are as large
+    /// as the committed memory of the process.
+    WithFullMemory,
+    /// The stacks of all threads and the loaded modules, without the paths
+    /// of the modules or any memory that may hold personal data, so that
+    /// the dump can be shared for triage.
+    Triage,
+    /// A combination of `MINIDUMP_TYPE` flags.
+    Custom(u32),
+}
+
+impl MinidumpType {
+    fn flags(self) -> DWORD {
+        match self {
+            MinidumpType::Mini => {
+                MINI_DUMP_WITH_HANDLE_DATA
+                    | MINI_DUMP_WITH_UNLOADED_MODULES
+                    | MINI_DUMP_WITH_THREAD_INFO
+            }
+            MinidumpType::WithFullMemory => {
+                MINI_DUMP_WITH_FULL_MEMORY
+                    | MINI_DUMP_WITH_FULL_MEMORY_INFO
+                    | MINI_DUMP_WITH_HANDLE_DATA
+                    | MINI_DUMP_WITH_UNLOADED_MODULES
+                    | MINI_DUMP_WITH_THREAD_INFO
+            }
+            MinidumpType::Triage => {
+                MINI_DUMP_FILTER_TRIAGE | MINI_DUMP_FILTER_MODULE_PATHS
+            }
+            MinidumpType::Custom(flags) => flags,
+        }
+    }
+}
+
+impl<M> ProcessHandle<M>
+where
+    M: HasProcessQueryInformation + HasProcessVmRead,
+{
+    /// Writes a dump of the process to a file, which is created or
+    /// truncated, for debuggers such as WinDbg or Visual Studio to open.
+    ///
+    /// The threads of the process are suspended while the dump is written,
+    /// so that it is consistent. Dumps that include handle data, such as
+    /// [`MinidumpType::Mini`] ones, also need `PROCESS_DUP_HANDLE`, which
+    /// is only checked by Windows. If writing the dump fails, the file is
+    /// removed.
+    ///
+    /// Dumping the current process this way cannot capture the stack of the
+    /// calling thread correctly; it is meant for other processes, e.g. to
+    /// diagnose one that hangs.
+    ///
+    /// This corresponds to calling [`MiniDumpWriteDump`].
+    ///
+    /// [`MiniDumpWriteDump`]: https://learn.microsoft.com/en-us/windows/win32/api/minidumpapiset/nf-minidumpapiset-minidumpwritedump
+    pub fn write_minidump(
+        &self,
+        path: &Path,
+        kind: MinidumpType,
+    ) -> Result<(), Error> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        let pid = self.pid()?;
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(err) => {
+                let code = err.raw_os_error().unwrap_or(0) as DWORD;
+                unsafe { SetLastError(code) };
+                return Err(Error(PhantomData));
+            }
+        };
+        let ok = unsafe {
+            MiniDumpWriteDump(
+                self.inner.as_ptr(),
+                pid.as_raw(),
+                file.as_raw_handle() as HANDLE,
+                kind.flags(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            // MiniDumpWriteDump reports HRESULTs, which are preserved
+            // while the partial dump is removed.
+            let code = unsafe { GetLastError() };
+            drop(file);
+            let _ = std::fs::remove_file(path);
+            unsafe { SetLastError(code) };
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{open_process, ReadAccess};
+
+    #[test]
+    fn dumps_child() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .spawn()
+            .unwrap();
+        let process =
+            open_process::<ReadAccess>(PhantomData, false, &child).unwrap();
+        let file_name = format!("winapi-util-dump-{}.dmp", child.id());
+        let path = std::env::temp_dir().join(file_name);
+        let result = process.write_minidump(&path, MinidumpType::Triage);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        result.unwrap();
+        let header = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // Minidumps start with the `MDMP` signature.
+        assert_eq!(&header[..4], b"MDMP");
+    }
+}
@@ -0,0 +1,316 @@
+use core::{marker::PhantomData, mem};
+
+use winapi::{
+    shared::{basetsd::SIZE_T, minwindef::DWORD},
+    um::{
+        processthreadsapi::{
+            GetProcessMitigationPolicy, SetProcessMitigationPolicy,
+        },
+        winnt::{
+            ProcessASLRPolicy, ProcessControlFlowGuardPolicy,
+            ProcessDEPPolicy, ProcessDynamicCodePolicy,
+            ProcessImageLoadPolicy, PROCESS_MITIGATION_ASLR_POLICY,
+            PROCESS_MITIGATION_CONTROL_FLOW_GUARD_POLICY,
+            PROCESS_MITIGATION_DEP_POLICY,
+            PROCESS_MITIGATION_DYNAMIC_CODE_POLICY,
+            PROCESS_MITIGATION_IMAGE_LOAD_POLICY,
+        },
+    },
+};
+
+use super::{
+    Error, HasProcessQueryInformation, MitigationPolicy, ProcessHandle,
+};
+
+/// Declares a mitigation policy whose settings are all bits of the `Flags`
+/// field of its `PROCESS_MITIGATION_*_POLICY` structure.
+macro_rules! flags_policy {
+    (
+        $(#[$doc:meta])*
+        $name:ident, $raw:ident, $policy:ident,
+        $(
+            $(#[$flag_doc:meta])*
+            $flag:ident / $getter:ident = $raw_get:ident / $raw_set:ident,
+        )*
+    ) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+        pub struct $name {
+            $($flag: bool,)*
+        }
+
+        impl $name {
+            /// Creates a policy with every setting turned off.
+            pub fn new() -> $name {
+                $name::default()
+            }
+
+            $(
+                $(#[$flag_doc])*
+                pub fn $flag(mut self, yes: bool) -> $name {
+                    self.$flag = yes;
+                    self
+                }
+
+                #[doc = concat!(
+                    "Returns true if [`", stringify!($name), "::",
+                    stringify!($flag), "`] is turned on.",
+                )]
+                pub fn $getter(&self) -> bool {
+                    self.$flag
+                }
+            )*
+        }
+
+        impl MitigationPolicy for $name {
+            const POLICY: DWORD = $policy;
+            type Raw = $raw;
+
+            fn from_raw(raw: $raw) -> $name {
+                $name { $($flag: raw.$raw_get() != 0,)* }
+            }
+
+            fn to_raw(self) -> $raw {
+                let mut raw = $raw { Flags: 0 };
+                $(raw.$raw_set(DWORD::from(self.$flag));)*
+                raw
+            }
+        }
+    };
+}
+
+/// The data execution prevention (DEP) policy, which keeps data pages from
+/// being executed.
+///
+/// DEP is always on for 64-bit processes.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DepPolicy {
+    enable: bool,
+    disable_atl_thunk_emulation: bool,
+    permanent: bool,
+}
+
+impl DepPolicy {
+    /// Creates a policy with every setting turned off.
+    pub fn new() -> DepPolicy {
+        DepPolicy::default()
+    }
+
+    /// Turns DEP on.
+    pub fn enable(mut self, yes: bool) -> DepPolicy {
+        self.enable = yes;
+        self
+    }
+
+    /// Returns true if [`DepPolicy::enable`] is turned on.
+    pub fn is_enabled(&self) -> bool {
+        self.enable
+    }
+
+    /// Stops the system from emulating the thunks of old versions of ATL,
+    /// which would otherwise be allowed to run from data pages.
+    pub fn disable_atl_thunk_emulation(mut self, yes: bool) -> DepPolicy {
+        self.disable_atl_thunk_emulation = yes;
+        self
+    }
+
+    /// Returns true if [`DepPolicy::disable_atl_thunk_emulation`] is
+    /// turned on.
+    pub fn is_atl_thunk_emulation_disabled(&self) -> bool {
+        self.disable_atl_thunk_emulation
+    }
+
+    /// Keeps the policy from being changed for the rest of the lifetime of
+    /// the process.
+    pub fn permanent(mut self, yes: bool) -> DepPolicy {
+        self.permanent = yes;
+        self
+    }
+
+    /// Returns true if [`DepPolicy::permanent`] is turned on.
+    pub fn is_permanent(&self) -> bool {
+        self.permanent
+    }
+}
+
+impl MitigationPolicy for DepPolicy {
+    const POLICY: DWORD = ProcessDEPPolicy;
+    type Raw = PROCESS_MITIGATION_DEP_POLICY;
+
+    fn from_raw(raw: PROCESS_MITIGATION_DEP_POLICY) -> DepPolicy {
+        DepPolicy {
+            enable: raw.Enable() != 0,
+            disable_atl_thunk_emulation: raw.DisableAtlThunkEmulation() != 0,
+            permanent: raw.Permanent != 0,
+        }
+    }
+
+    fn to_raw(self) -> PROCESS_MITIGATION_DEP_POLICY {
+        let mut raw = PROCESS_MITIGATION_DEP_POLICY {
+            Flags: 0,
+            Permanent: self.permanent.into(),
+        };
+        raw.set_Enable(self.enable.into());
+        raw.set_DisableAtlThunkEmulation(
+            self.disable_atl_thunk_emulation.into(),
+        );
+        raw
+    }
+}
+
+flags_policy!(
+    /// The address space layout randomization (ASLR) policy, which loads
+    /// images and allocates memory at unpredictable addresses.
+    AslrPolicy,
+    PROCESS_MITIGATION_ASLR_POLICY,
+    ProcessASLRPolicy,
+    /// Randomizes the addresses of allocations made without a requested
+    /// address.
+    bottom_up_randomization / is_bottom_up_randomization
+        = EnableBottomUpRandomization / set_EnableBottomUpRandomization,
+    /// Relocates images that were not built with `/DYNAMICBASE`.
+    force_relocate_images / is_force_relocate_images
+        = EnableForceRelocateImages / set_EnableForceRelocateImages,
+    /// Uses the whole 64-bit address space for bottom-up randomization.
+    high_entropy / is_high_entropy
+        = EnableHighEntropy / set_EnableHighEntropy,
+    /// Refuses to load images without relocation information when they
+    /// would have to be relocated.
+    disallow_stripped_images / is_disallow_stripped_images
+        = DisallowStrippedImages / set_DisallowStrippedImages,
+);
+
+flags_policy!(
+    /// The arbitrary code guard (ACG) policy, which keeps the process from
+    /// generating code or modifying existing executable code.
+    DynamicCodePolicy,
+    PROCESS_MITIGATION_DYNAMIC_CODE_POLICY,
+    ProcessDynamicCodePolicy,
+    /// Prohibits creating executable memory and making memory executable.
+    prohibit_dynamic_code / is_prohibit_dynamic_code
+        = ProhibitDynamicCode / set_ProhibitDynamicCode,
+    /// Lets individual threads opt out of the policy.
+    allow_thread_opt_out / is_allow_thread_opt_out
+        = AllowThreadOptOut / set_AllowThreadOptOut,
+    /// Lets other processes with the right privileges turn the policy off.
+    allow_remote_downgrade / is_allow_remote_downgrade
+        = AllowRemoteDowngrade / set_AllowRemoteDowngrade,
+);
+
+flags_policy!(
+    /// The control flow guard (CFG) policy, which checks the targets of
+    /// indirect calls. CFG can only be turned on when the process starts.
+    ControlFlowGuardPolicy,
+    PROCESS_MITIGATION_CONTROL_FLOW_GUARD_POLICY,
+    ProcessControlFlowGuardPolicy,
+    /// Checks the targets of indirect calls in images built with
+    /// `/guard:cf`.
+    enable_control_flow_guard / is_control_flow_guard_enabled
+        = EnableControlFlowGuard / set_EnableControlFlowGuard,
+    /// Treats exported functions as invalid call targets until they are
+    /// resolved dynamically.
+    enable_export_suppression / is_export_suppression_enabled
+        = EnableExportSuppression / set_EnableExportSuppression,
+    /// Refuses to load images that were not built with `/guard:cf`.
+    strict_mode / is_strict_mode = StrictMode / set_StrictMode,
+);
+
+flags_policy!(
+    /// The image load policy, which restricts where images may be loaded
+    /// from.
+    ImageLoadPolicy,
+    PROCESS_MITIGATION_IMAGE_LOAD_POLICY,
+    ProcessImageLoadPolicy,
+    /// Refuses to load images from remote locations, such as network
+    /// shares.
+    no_remote_images / is_no_remote_images
+        = NoRemoteImages / set_NoRemoteImages,
+    /// Refuses to load images whose mandatory label is low, such as files
+    /// written by sandboxed processes.
+    no_low_mandatory_label_images / is_no_low_mandatory_label_images
+        = NoLowMandatoryLabelImages / set_NoLowMandatoryLabelImages,
+    /// Searches `System32` before the application directory when loading
+    /// DLLs.
+    prefer_system32_images / is_prefer_system32_images
+        = PreferSystem32Images / set_PreferSystem32Images,
+);
+
+impl<M: HasProcessQueryInformation> ProcessHandle<M> {
+    /// Returns the mitigation policy `P` of the process, which is one of
+    /// [`DepPolicy`], [`AslrPolicy`], [`DynamicCodePolicy`],
+    /// [`ControlFlowGuardPolicy`] and [`ImageLoadPolicy`].
+    ///
+    /// This requires Windows 8 or newer.
+    ///
+    /// This corresponds to calling [`GetProcessMitigationPolicy`].
+    ///
+    /// [`GetProcessMitigationPolicy`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocessmitigationpolicy
+    pub fn mitigation_policy<P: MitigationPolicy>(&self) -> Result<P, Error> {
+        let () = M::ASSERT;
+        let mut raw: P::Raw = unsafe { mem::zeroed() };
+        let ok = unsafe {
+            GetProcessMitigationPolicy(
+                self.inner.as_ptr(),
+                P::POLICY,
+                (&mut raw as *mut P::Raw).cast(),
+                mem::size_of::<P::Raw>() as SIZE_T,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(P::from_raw(raw))
+    }
+}
+
+/// Applies a mitigation policy to the current process. Policies can only
+/// be applied to the current process, e.g. early in `main`, and most can
+/// only be tightened once they are in effect.
+///
+/// This requires Windows 8 or newer.
+///
+/// This corresponds to calling [`SetProcessMitigationPolicy`].
+///
+/// [`SetProcessMitigationPolicy`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setprocessmitigationpolicy
+pub fn set_mitigation_policy<P: MitigationPolicy>(
+    policy: P,
+) -> Result<(), Error> {
+    let mut raw = policy.to_raw();
+    let ok = unsafe {
+        SetProcessMitigationPolicy(
+            P::POLICY,
+            (&mut raw as *mut P::Raw).cast(),
+            mem::size_of::<P::Raw>() as SIZE_T,
+        )
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+
+    #[test]
+    fn policies_round_trip_through_raw() {
+        let aslr = AslrPolicy::new().high_entropy(true);
+        assert_eq!(AslrPolicy::from_raw(aslr.to_raw()), aslr);
+        assert_eq!(aslr.to_raw().Flags, 0b100);
+        let dep = DepPolicy::new().enable(true).permanent(true);
+        assert_eq!(DepPolicy::from_raw(dep.to_raw()), dep);
+    }
+
+    #[test]
+    fn current_process_policies() {
+        let process = current_process();
+        let dep = process.mitigation_policy::<DepPolicy>().unwrap();
+        if cfg!(target_pointer_width = "64") {
+            assert!(dep.is_enabled());
+        }
+        process.mitigation_policy::<AslrPolicy>().unwrap();
+        process.mitigation_policy::<ImageLoadPolicy>().unwrap();
+    }
+}
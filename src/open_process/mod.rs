@@ -1,12 +1,146 @@
+use core::ffi::c_void;
+use core::fmt::{self, Debug, Formatter};
 use core::marker::PhantomData;
 use core::ptr::NonNull;
 use winapi::shared::minwindef::BOOL;
-use winapi::um::winnt::HANDLE;
-use winapi::{shared::minwindef::DWORD, um::processthreadsapi::OpenProcess};
+use winapi::um::winnt::{
+    HANDLE, PROCESS_ALL_ACCESS, PROCESS_QUERY_INFORMATION,
+    PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
+};
+use winapi::{
+    shared::minwindef::DWORD,
+    um::processthreadsapi::{GetProcessId, OpenProcess},
+};
 
+mod allocation;
+mod arch;
+mod borrowed;
+mod builder;
+mod context;
+mod counters;
+mod cpu_sets;
+pub mod cpu_usage;
+mod description;
+mod duplicate;
 mod error;
+mod exit;
+#[cfg(feature = "async")]
+mod future;
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "handle_tracking")]
+pub mod handle_tracking;
+mod icon;
+mod image;
+pub mod inject;
+mod memory;
+mod minidump;
+mod mitigation;
+mod modules;
+mod notify;
+mod ntdll;
+#[cfg(feature = "ntdll")]
+mod parameters;
+mod parent;
+#[cfg(feature = "ntdll")]
+mod peb;
+mod priority;
+pub mod privileges;
+mod process_id;
+pub mod process_tree;
+mod recycle;
+#[cfg(feature = "ntdll")]
+mod remote_handles;
+mod remote_memory;
+mod remote_thread;
+mod rights;
+pub mod scan;
+mod signature;
+pub mod snapshot;
+pub mod supervisor;
+mod suspend;
+#[cfg(feature = "ntdll")]
+mod system_processes;
+#[cfg(feature = "test_support")]
+pub mod test_support;
+mod thread;
+#[cfg(feature = "ntdll")]
+mod thread_information;
+mod times;
+mod token;
+mod version;
+mod wait;
+mod working_set;
+pub mod wts;
 
-pub use error::{Error, ErrorCode};
+pub use allocation::{ProtectionGuard, RemoteAllocation};
+pub use arch::ProcessArch;
+pub use borrowed::{current_process, ProcessHandleRef};
+pub use builder::OpenProcess;
+pub use context::Context;
+pub use counters::{GuiResource, IoCounters, MemoryCounters};
+pub use cpu_sets::{system_cpu_sets, AffinityMasks, CpuSet, ProcessorNumber};
+pub use duplicate::{DuplicateOptions, RawDuplicatedHandle};
+pub use error::{Error, ErrorCode, ErrorReport};
+pub use exit::ExitStatus;
+#[cfg(feature = "async")]
+pub use future::ProcessExit;
+#[cfg(feature = "gpu")]
+pub use gpu::GpuAdapterUsage;
+pub use icon::{extract_icon, Icon};
+pub use image::{canonicalize_image_path, same_binary, PathForm};
+pub use memory::{
+    MemoryRegion, MemoryRegions, MemoryState, MemoryType, UnbackedRegion,
+};
+pub use minidump::MinidumpType;
+pub use mitigation::{
+    set_mitigation_policy, AslrPolicy, ControlFlowGuardPolicy, DepPolicy,
+    DynamicCodePolicy, ImageLoadPolicy,
+};
+pub use modules::RemoteModule;
+pub use notify::{ExitSignal, WaitRegistration};
+#[cfg(feature = "ntdll")]
+pub use peb::LoadedModule;
+#[cfg(feature = "ntdll")]
+pub use priority::IoPriority;
+pub use priority::{
+    begin_background_mode, begin_thread_background_mode, BackgroundMode,
+    MemoryPriority, PriorityClass, ThreadBackgroundMode, ThreadPriority,
+};
+pub use process_id::{
+    list_process_ids, open_process_identity, ProcessId, ProcessIdentity,
+};
+#[cfg(feature = "ntdll")]
+pub use remote_handles::RemoteHandle;
+pub use remote_memory::Pod;
+pub use remote_thread::RemoteThreadHandle;
+pub use rights::{
+    HasProcessCreateThread, HasProcessDupHandle, HasProcessQueryInformation,
+    HasProcessQueryLimitedInformation, HasProcessSetInformation,
+    HasProcessSetLimitedInformation, HasProcessSetQuota,
+    HasProcessSuspendResume, HasProcessTerminate, HasProcessVmOperation,
+    HasProcessVmRead, HasProcessVmWrite, HasSynchronize, HasThreadGetContext,
+    HasThreadQueryInformation, HasThreadQueryLimitedInformation,
+    HasThreadSetContext, HasThreadSetInformation,
+    HasThreadSetLimitedInformation, HasThreadSuspendResume,
+    HasThreadTerminate, HasTokenAdjustDefault, HasTokenAdjustGroups,
+    HasTokenAdjustPrivileges, HasTokenAdjustSessionId, HasTokenAssignPrimary,
+    HasTokenDuplicate, HasTokenImpersonate, HasTokenQuery,
+    HasTokenQuerySource,
+};
+pub use signature::{verify_file_signature, Signature, SignatureStatus};
+#[cfg(feature = "ntdll")]
+pub use system_processes::{
+    system_processes, SystemProcess, SystemThread, ThreadState,
+};
+pub use thread::{open_thread, SuspendGuard, ThreadHandle};
+#[cfg(feature = "ntdll")]
+pub use thread_information::ThreadBasicInformation;
+pub use times::ProcessTimes;
+pub use token::TokenHandle;
+pub use version::{version_info, VersionInfo};
+pub use wait::{wait_all, wait_any, WaitOutcome, Waitable};
+pub use working_set::WorkingSetFlags;
 
 mod sealed {
     use core::ffi::c_void;
@@ -15,7 +149,9 @@ mod sealed {
     use winapi::shared::minwindef::DWORD;
 
     pub trait HandleMetadata {
-        type StoredType;
+        type StoredType: Copy;
+        fn metadata_from_access(access: DWORD) -> Option<Self::StoredType>;
+        fn access_rights(metadata: &Self::StoredType) -> DWORD;
     }
 
     pub trait IntoAccessRights {
@@ -33,19 +169,41 @@ mod sealed {
         fn into_process_id(self) -> DWORD;
     }
 
+    pub trait MitigationPolicy: Copy {
+        /// The `PROCESS_MITIGATION_POLICY` value of the policy.
+        const POLICY: DWORD;
+        /// The `PROCESS_MITIGATION_*_POLICY` structure of the policy.
+        type Raw: Copy;
+        fn from_raw(raw: Self::Raw) -> Self;
+        fn to_raw(self) -> Self::Raw;
+    }
+
     pub struct ProcessHandleKind {}
 
+    pub struct ThreadHandleKind {}
+
+    pub struct TokenHandleKind {}
+
     pub struct Handle<T: HandleType, M: HandleMetadata> {
         // PhantomData<*const T> is an idiom for removing the bearing of T on the borrow checker.
         // See https://doc.rust-lang.org/std/marker/struct.PhantomData.html#ownership-and-the-drop-check
         // for more information.
         pub(super) phantom_kind: PhantomData<*const T>,
-        #[allow(dead_code)]
         pub(super) metadata: M::StoredType,
         pub inner: NonNull<c_void>,
     }
 
-    pub trait HandleType {}
+    pub trait HandleType {
+        /// The name under which handles of this kind are debug-printed.
+        const NAME: &'static str;
+        /// The name under which the result of `object_id` is debug-printed.
+        const ID_NAME: &'static str = "id";
+        /// Returns the identifier of the object behind the handle, if it
+        /// can be cheaply queried.
+        fn object_id(_handle: NonNull<c_void>) -> Option<DWORD> {
+            None
+        }
+    }
 
     // At the moment of writing, Option<T> cannot be used as a const generic parameter.
     pub struct AccessRights<const KNOWN: bool, const N: DWORD>;
@@ -53,7 +211,7 @@ mod sealed {
 
 use sealed::{
     AccessRights, Handle, HandleMetadata, HandleType, IntoAccessRights,
-    IntoProcessId, ProcessHandleKind,
+    IntoProcessId, MitigationPolicy, ProcessHandleKind,
 };
 
 /// A non-null handle to a process, obtained e.g. via [`open_process`].
@@ -69,7 +227,7 @@ pub type ProcessHandle<M> = Handle<ProcessHandleKind, M>;
 /// This type is meant to be used as a generic type parameter for [`open_process`] function.
 ///
 /// When supplied as a generic type parameter for [`open_process`] function, its first argument
-/// will be a [`DWORD`] value that will be passed to [`OpenProcess`] function.
+/// will be a [`DWORD`] value that will be passed to [`OpenProcess`](fn@OpenProcess) function.
 pub type RuntimeAccessRights = AccessRights</*KNOWN=*/ false, 0>;
 /// Process Security and Access Rights that are known at compile time.
 /// If you don't know the access rights at compile time, fall back to [`RuntimeAccessRights`].
@@ -79,6 +237,46 @@ pub type RuntimeAccessRights = AccessRights</*KNOWN=*/ false, 0>;
 pub type ComptimeAccessRights<const N: DWORD> =
     AccessRights</*KNOWN=*/ true, N>;
 
+/// Access rights for querying basic information about a process, which
+/// can be granted even for protected processes.
+pub type QueryLimitedAccess =
+    ComptimeAccessRights<PROCESS_QUERY_LIMITED_INFORMATION>;
+/// Access rights for querying information about a process.
+pub type QueryAccess = ComptimeAccessRights<PROCESS_QUERY_INFORMATION>;
+/// Access rights for querying information about a process and reading its
+/// memory.
+pub type ReadAccess =
+    ComptimeAccessRights<{ PROCESS_QUERY_INFORMATION | PROCESS_VM_READ }>;
+/// All access rights to a process.
+pub type AllAccess = ComptimeAccessRights<PROCESS_ALL_ACCESS>;
+
+/// Expands to the [`ComptimeAccessRights`] type combining all of the given
+/// access rights, e.g. to avoid spelling out
+/// `ComptimeAccessRights<{ PROCESS_VM_READ | PROCESS_QUERY_INFORMATION }>`.
+///
+/// Since the marker traits such as [`HasProcessVmRead`] are derived from
+/// the combined value, the resulting type implements all of the marker
+/// traits of its parts.
+///
+/// # Example
+///
+/// ```no_run
+/// use core::marker::PhantomData;
+/// use winapi::um::winnt::{PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+/// use winapi_util::{access_rights, open_process::open_process};
+///
+/// let handle = open_process::<
+///     access_rights!(PROCESS_VM_READ, PROCESS_QUERY_INFORMATION),
+/// >(PhantomData, false, std::process::id())
+/// .unwrap();
+/// ```
+#[macro_export]
+macro_rules! access_rights {
+    ($($right:path),+ $(,)?) => {
+        $crate::open_process::ComptimeAccessRights<{ 0 $(| $right)+ }>
+    };
+}
+
 impl<const N: DWORD> IntoAccessRights for ComptimeAccessRights<N> {
     const KNOWN: bool = true;
     const VALUE: DWORD = N;
@@ -113,6 +311,10 @@ impl IntoAccessRights for RuntimeAccessRights {
 
 /// Rustic wrapper around [`OpenProcess`] function.
 ///
+/// The process can be given as a `u32` or `u64` process identifier, a
+/// [`ProcessId`], or a reference to a [`std::process::Child`] or a
+/// [`ProcessEntry`](snapshot::ProcessEntry).
+///
 /// The returned handle gets automatically closed by calling [`CloseHandle`] when the handle goes out of scope.
 ///
 /// [`OpenProcess`]: https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-openprocess
@@ -120,8 +322,9 @@ impl IntoAccessRights for RuntimeAccessRights {
 pub fn open_process<R: IntoAccessRights>(
     desired_access: R::RuntimeArgumentType,
     inherit_handle: bool,
-    process_id: DWORD,
+    process_id: impl IntoProcessId,
 ) -> Result<ProcessHandle<R::AccessRightsType>, Error> {
+    let process_id: DWORD = process_id.into_process_id();
     let dw_desired_access: DWORD = R::rt_arg_to_dword(desired_access);
     let inherit_handle: BOOL = if inherit_handle { 1 } else { 0 };
 
@@ -131,19 +334,96 @@ pub fn open_process<R: IntoAccessRights>(
         unsafe { OpenProcess(dw_desired_access, inherit_handle, process_id) };
     let inner = NonNull::new(handle).ok_or(Error(PhantomData))?;
 
-    let handle = Handle { phantom_kind: PhantomData, metadata, inner };
+    let handle = Handle::from_opened(inner, metadata);
     Ok(handle)
 }
 
+impl<M: HandleMetadata> ProcessHandle<M> {
+    /// Takes ownership of a raw process handle obtained elsewhere, e.g. from
+    /// [`CreateProcessW`] or another library.
+    ///
+    /// `access` must be the access rights the handle was opened with. If the
+    /// handle is null, or if `M` is a [`ComptimeAccessRights`] whose rights
+    /// are not all contained in `access`, then `None` is returned and the
+    /// handle is left untouched.
+    ///
+    /// The returned handle gets automatically closed by calling
+    /// [`CloseHandle`] when it goes out of scope.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `handle` is a valid process handle opened
+    /// with `access`, and that nothing else closes it afterwards. In debug
+    /// builds on Windows 10 and newer, adopting a handle that is already
+    /// owned by another handle of this crate panics.
+    ///
+    /// [`CreateProcessW`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-createprocessw
+    /// [`CloseHandle`]: https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+    pub unsafe fn from_raw(handle: HANDLE, access: DWORD) -> Option<Self> {
+        let inner = NonNull::new(handle)?;
+        let metadata = M::metadata_from_access(access)?;
+        recycle::check_adopt(inner);
+        Some(Handle::from_opened(inner, metadata))
+    }
+}
+
+impl ProcessHandle<RuntimeAccessRights> {
+    /// Converts this handle into one whose access rights are known at
+    /// compile time, e.g. to pass a handle opened with rights read from a
+    /// configuration file to code that requires specific rights.
+    ///
+    /// If the rights this handle was opened with do not contain all of `N`,
+    /// then the handle is returned unchanged as the error.
+    pub fn try_into_comptime<const N: DWORD>(
+        self,
+    ) -> Result<ProcessHandle<ComptimeAccessRights<N>>, Self> {
+        let Some(metadata) = ComptimeAccessRights::<N>::metadata_from_access(
+            RuntimeAccessRights::access_rights(&self.metadata),
+        ) else {
+            return Err(self);
+        };
+        // Ownership of the handle moves to the new value.
+        let this = core::mem::ManuallyDrop::new(self);
+        Ok(Handle { phantom_kind: PhantomData, metadata, inner: this.inner })
+    }
+}
+
 impl<const N: DWORD> HandleMetadata for ComptimeAccessRights<N> {
     type StoredType = PhantomData<()>;
+    fn metadata_from_access(access: DWORD) -> Option<Self::StoredType> {
+        if access & N == N {
+            Some(PhantomData)
+        } else {
+            None
+        }
+    }
+    fn access_rights(_metadata: &Self::StoredType) -> DWORD {
+        N
+    }
 }
 
 impl HandleMetadata for RuntimeAccessRights {
     type StoredType = DWORD;
+    fn metadata_from_access(access: DWORD) -> Option<Self::StoredType> {
+        Some(access)
+    }
+    fn access_rights(metadata: &Self::StoredType) -> DWORD {
+        *metadata
+    }
 }
 
-impl HandleType for ProcessHandleKind {}
+impl HandleType for ProcessHandleKind {
+    const NAME: &'static str = "ProcessHandle";
+    const ID_NAME: &'static str = "pid";
+    fn object_id(handle: NonNull<c_void>) -> Option<DWORD> {
+        // GetProcessId returns 0 on failure, which is never a valid PID for
+        // a process one can open.
+        match unsafe { GetProcessId(handle.as_ptr()) } {
+            0 => None,
+            pid => Some(pid),
+        }
+    }
+}
 
 impl IntoProcessId for u64 {
     fn into_process_id(self) -> DWORD {
@@ -157,9 +437,183 @@ impl IntoProcessId for u32 {
     }
 }
 
+impl IntoProcessId for ProcessId {
+    fn into_process_id(self) -> DWORD {
+        self.as_raw()
+    }
+}
+
+impl IntoProcessId for &std::process::Child {
+    fn into_process_id(self) -> DWORD {
+        self.id()
+    }
+}
+
+impl<T: HandleType, M: HandleMetadata> Handle<T, M> {
+    /// Takes ownership of a handle that was just opened, duplicated or
+    /// adopted, registering it with the debugging facilities.
+    fn from_opened(inner: NonNull<c_void>, metadata: M::StoredType) -> Self {
+        recycle::track(inner);
+        #[cfg(feature = "handle_tracking")]
+        handle_tracking::opened(inner, T::NAME, M::access_rights(&metadata));
+        Handle { phantom_kind: PhantomData, metadata, inner }
+    }
+
+    /// Unregisters a handle from the debugging facilities right before it
+    /// is closed.
+    fn before_close(inner: NonNull<c_void>) {
+        recycle::untrack(inner);
+        #[cfg(feature = "handle_tracking")]
+        handle_tracking::closed(inner);
+    }
+}
+
+/// Registers a handle that the crate owns outside of a `Handle`, such as a
+/// snapshot or a job object, with the `handle_tracking` feature.
+fn track_raw(handle: HANDLE, kind: &'static str, access: DWORD) {
+    #[cfg(feature = "handle_tracking")]
+    if let Some(handle) = NonNull::new(handle) {
+        handle_tracking::opened(handle, kind, access);
+    }
+    #[cfg(not(feature = "handle_tracking"))]
+    let _ = (handle, kind, access);
+}
+
+/// Unregisters a handle that was registered with `track_raw` right before
+/// it is closed.
+fn untrack_raw(handle: HANDLE) {
+    #[cfg(feature = "handle_tracking")]
+    if let Some(handle) = NonNull::new(handle) {
+        handle_tracking::closed(handle);
+    }
+    #[cfg(not(feature = "handle_tracking"))]
+    let _ = handle;
+}
+
+impl<T: HandleType, M: HandleMetadata> Handle<T, M> {
+    /// Closes the handle by calling [`CloseHandle`], reporting whether that
+    /// succeeded.
+    ///
+    /// Dropping a handle closes it as well, but any failure is silently
+    /// ignored. Use this when the caller needs to know that the handle was
+    /// actually closed.
+    ///
+    /// [`CloseHandle`]: https://docs.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+    pub fn try_close(self) -> Result<(), Error> {
+        // The handle must not be closed a second time by Drop, regardless of
+        // the outcome.
+        let this = core::mem::ManuallyDrop::new(self);
+        Self::before_close(this.inner);
+        let is_ok: BOOL =
+            unsafe { winapi::um::handleapi::CloseHandle(this.inner.as_ptr()) };
+        if is_ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+impl<T: HandleType, M: HandleMetadata> Handle<T, M> {
+    /// Returns the access rights the handle was opened with, as requested
+    /// by the caller.
+    ///
+    /// For handles with [`ComptimeAccessRights`], this is the constant from
+    /// the type. Use [`Handle::granted_access`] to find out which rights the
+    /// kernel actually granted.
+    pub fn access_rights(&self) -> DWORD {
+        M::access_rights(&self.metadata)
+    }
+
+    /// Queries the kernel for the access rights granted to the handle.
+    ///
+    /// This may include more rights than were requested, such as
+    /// `PROCESS_QUERY_LIMITED_INFORMATION` for a handle opened with
+    /// `PROCESS_QUERY_INFORMATION`, and generic rights such as
+    /// `GENERIC_READ` are mapped to specific ones.
+    ///
+    /// This corresponds to calling [`NtQueryObject`] with
+    /// `ObjectBasicInformation`.
+    ///
+    /// [`NtQueryObject`]: https://learn.microsoft.com/en-us/windows/win32/api/winternl/nf-winternl-ntqueryobject
+    pub fn granted_access(&self) -> Result<DWORD, Error> {
+        let mut info: ntdll::PUBLIC_OBJECT_BASIC_INFORMATION =
+            unsafe { core::mem::zeroed() };
+        let status = unsafe {
+            ntdll::NtQueryObject(
+                self.inner.as_ptr(),
+                ntdll::ObjectBasicInformation,
+                (&mut info as *mut ntdll::PUBLIC_OBJECT_BASIC_INFORMATION)
+                    .cast(),
+                core::mem::size_of_val(&info) as DWORD,
+                core::ptr::null_mut(),
+            )
+        };
+        ntdll::check(status)?;
+        Ok(info.GrantedAccess)
+    }
+}
+
+impl<T: HandleType, M: HandleMetadata> Debug for Handle<T, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct(T::NAME);
+        d.field("handle", &self.inner.as_ptr())
+            .field("access", &format_args!("{:#x}", self.access_rights()));
+        if let Some(id) = T::object_id(self.inner) {
+            d.field(T::ID_NAME, &id);
+        }
+        d.finish()
+    }
+}
+
+/// Encodes the given string as a NUL terminated UTF-16 string.
+fn to_wide(s: &std::ffi::OsStr) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+
+    s.encode_wide().chain(Some(0)).collect()
+}
+
+/// Converts an error of the standard library or of the rest of this crate
+/// into an `Error` by making its OS error code the last error. Errors
+/// without one, such as paths with interior NULs, are reported as
+/// `ERROR_INVALID_PARAMETER`.
+fn from_io_error(err: std::io::Error) -> Error {
+    use winapi::{
+        shared::winerror::ERROR_INVALID_PARAMETER,
+        um::errhandlingapi::SetLastError,
+    };
+
+    let code = err
+        .raw_os_error()
+        .map_or(ERROR_INVALID_PARAMETER, |code| code as DWORD);
+    unsafe { SetLastError(code) };
+    Error(PhantomData)
+}
+
+/// Compares two strings, ignoring the case of ASCII letters, e.g. to match
+/// the file names of modules.
+fn eq_ignore_ascii_case(a: &std::ffi::OsStr, b: &std::ffi::OsStr) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+
+    let fold = |unit: u16| match u8::try_from(unit) {
+        Ok(byte) => u16::from(byte.to_ascii_lowercase()),
+        Err(_) => unit,
+    };
+    a.encode_wide().map(fold).eq(b.encode_wide().map(fold))
+}
+
+// SAFETY: A handle is an index into the handle table of the process, so it
+// is equally valid on every thread, and Windows synchronizes access to the
+// underlying kernel object. The raw pointer and the phantom type are only
+// there to model ownership; they do not point to memory owned by the
+// current thread. None of the methods of `Handle` rely on being called from
+// a particular thread, and closing happens exactly once through ownership.
+unsafe impl<T: HandleType, M: HandleMetadata> Send for Handle<T, M> {}
+unsafe impl<T: HandleType, M: HandleMetadata> Sync for Handle<T, M> {}
+
 // At the time of writing, fallible drop is not a thing
 impl<T: HandleType, M: HandleMetadata> Drop for Handle<T, M> {
     fn drop(&mut self) {
+        Self::before_close(self.inner);
         #[cfg(debug_assertions)]
         let is_ok: BOOL =
             unsafe { winapi::um::handleapi::CloseHandle(self.inner.as_mut()) };
@@ -194,4 +648,160 @@ mod tests {
         >(PhantomData, false, std::process::id());
         let _handle = handle.unwrap();
     }
+
+    #[test]
+    fn access_rights_macro_combines_rights() {
+        use winapi::um::winnt::PROCESS_VM_READ;
+
+        let handle = open_process::<
+            crate::access_rights!(PROCESS_QUERY_INFORMATION, PROCESS_VM_READ),
+        >(PhantomData, false, std::process::id())
+        .unwrap();
+        assert!(format!("{handle:?}").contains("access: 0x410"));
+    }
+
+    #[test]
+    fn granted_access_includes_implied_rights() {
+        use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+        let handle = open_process::<
+            ComptimeAccessRights<PROCESS_QUERY_INFORMATION>,
+        >(PhantomData, false, std::process::id())
+        .unwrap();
+        assert_eq!(handle.access_rights(), PROCESS_QUERY_INFORMATION);
+        let granted = handle.granted_access().unwrap();
+        assert_eq!(
+            granted & PROCESS_QUERY_LIMITED_INFORMATION,
+            PROCESS_QUERY_LIMITED_INFORMATION
+        );
+    }
+
+    #[test]
+    fn handles_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ProcessHandle<RuntimeAccessRights>>();
+        assert_send_sync::<
+            ProcessHandle<ComptimeAccessRights<PROCESS_QUERY_INFORMATION>>,
+        >();
+
+        let handle = std::sync::Arc::new(
+            open_process::<RuntimeAccessRights>(
+                PROCESS_QUERY_INFORMATION,
+                false,
+                std::process::id(),
+            )
+            .unwrap(),
+        );
+        let shared = std::sync::Arc::clone(&handle);
+        let out =
+            std::thread::spawn(move || format!("{shared:?}")).join().unwrap();
+        assert_eq!(out, format!("{handle:?}"));
+    }
+
+    #[test]
+    fn open_process_accepts_process_ids() {
+        use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+        let access = PROCESS_QUERY_LIMITED_INFORMATION;
+        let pid = std::process::id();
+        open_process::<RuntimeAccessRights>(access, false, pid).unwrap();
+        open_process::<RuntimeAccessRights>(access, false, u64::from(pid))
+            .unwrap();
+        open_process::<RuntimeAccessRights>(
+            access,
+            false,
+            ProcessId::current(),
+        )
+        .unwrap();
+
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/c", "exit"])
+            .spawn()
+            .unwrap();
+        let handle =
+            open_process::<RuntimeAccessRights>(access, false, &child)
+                .unwrap();
+        child.wait().unwrap();
+        drop(handle);
+    }
+
+    #[test]
+    fn try_close_succeeds() {
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_INFORMATION,
+            false,
+            std::process::id(),
+        )
+        .unwrap();
+        handle.try_close().unwrap();
+    }
+
+    #[test]
+    fn debug_shows_access_and_pid() {
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_INFORMATION,
+            false,
+            std::process::id(),
+        )
+        .unwrap();
+        let out = format!("{handle:?}");
+        assert!(out.starts_with("ProcessHandle {"), "{out}");
+        assert!(out.contains("access: 0x400"), "{out}");
+        assert!(
+            out.contains(&format!("pid: {}", std::process::id())),
+            "{out}"
+        );
+    }
+
+    #[test]
+    fn try_into_comptime_checks_rights() {
+        use winapi::um::winnt::{
+            PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
+        };
+
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_INFORMATION | PROCESS_QUERY_LIMITED_INFORMATION,
+            false,
+            std::process::id(),
+        )
+        .unwrap();
+        let handle = handle
+            .try_into_comptime::<PROCESS_TERMINATE>()
+            .expect_err("PROCESS_TERMINATE was not requested");
+        let handle = handle
+            .try_into_comptime::<PROCESS_QUERY_LIMITED_INFORMATION>()
+            .unwrap();
+        handle.try_close().unwrap();
+    }
+
+    #[test]
+    fn from_raw_checks_comptime_rights() {
+        use winapi::um::processthreadsapi::GetCurrentProcess;
+        use winapi::um::winnt::PROCESS_TERMINATE;
+
+        // The pseudo-handle must never be closed, so the adopted handles are
+        // forgotten instead of dropped.
+        let raw = unsafe { GetCurrentProcess() };
+        let handle = unsafe {
+            ProcessHandle::<ComptimeAccessRights<PROCESS_QUERY_INFORMATION>>::from_raw(
+                raw,
+                PROCESS_QUERY_INFORMATION,
+            )
+        };
+        core::mem::forget(handle.unwrap());
+        let handle = unsafe {
+            ProcessHandle::<ComptimeAccessRights<PROCESS_TERMINATE>>::from_raw(
+                raw,
+                PROCESS_QUERY_INFORMATION,
+            )
+        };
+        assert!(handle.is_none());
+        let null = unsafe {
+            ProcessHandle::<RuntimeAccessRights>::from_raw(
+                core::ptr::null_mut(),
+                PROCESS_QUERY_INFORMATION,
+            )
+        };
+        assert!(null.is_none());
+    }
 }
@@ -0,0 +1,432 @@
+use core::{marker::PhantomData, mem, ptr};
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStringExt,
+    path::{Path, PathBuf},
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, HMODULE},
+        winerror::{ERROR_BAD_EXE_FORMAT, ERROR_MOD_NOT_FOUND},
+    },
+    um::{
+        errhandlingapi::SetLastError,
+        psapi::{
+            EnumProcessModulesEx, GetModuleFileNameExW, GetModuleInformation,
+            LIST_MODULES_ALL, MODULEINFO,
+        },
+        winnt::HANDLE,
+    },
+};
+
+use super::{
+    eq_ignore_ascii_case,
+    image::MAX_PATH_WIDE,
+    memory::{read, read_bytes, read_terminated},
+    Error, HasProcessQueryInformation, HasProcessVmRead, ProcessHandle,
+};
+
+/// The offset of `e_lfanew` in the `IMAGE_DOS_HEADER`.
+const DOS_HEADER_LFANEW: usize = 0x3c;
+/// The signature of the `IMAGE_NT_HEADERS`, `PE\0\0`.
+const NT_SIGNATURE: u32 = 0x0000_4550;
+/// The offset of the optional header in the `IMAGE_NT_HEADERS`.
+const NT_OPTIONAL_HEADER: usize = 24;
+/// The offsets of the export data directory in the optional header of
+/// 32-bit and 64-bit images.
+const EXPORT_DIRECTORY_PE32: usize = 96;
+const EXPORT_DIRECTORY_PE32_PLUS: usize = 112;
+/// The size of the `IMAGE_EXPORT_DIRECTORY`.
+const EXPORT_DIRECTORY_SIZE: usize = 40;
+/// The number of forwarded exports that are followed, in case they form a
+/// cycle.
+const MAX_FORWARDS: usize = 8;
+/// The longest export name that is read from outside the export directory.
+const MAX_EXPORT_NAME: usize = 4096;
+
+/// A module loaded into another process.
+///
+/// See `ProcessHandle::modules`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RemoteModule {
+    base_address: usize,
+    size: u32,
+    entry_point: Option<usize>,
+    path: PathBuf,
+}
+
+impl RemoteModule {
+    /// Returns the address the module is mapped at, which is also its
+    /// `HMODULE` in the process.
+    pub fn base_address(&self) -> usize {
+        self.base_address
+    }
+
+    /// Returns the size of the mapped image, in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the address of the entry point of the module, or `None` for
+    /// modules without one, such as resource-only DLLs.
+    pub fn entry_point(&self) -> Option<usize> {
+        self.entry_point
+    }
+
+    /// Returns the full path of the module.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl RemoteModule {
+    /// Returns the address of an exported function or variable of the
+    /// module in `process`, the process the module was listed for, or
+    /// `None` if the module does not export it.
+    ///
+    /// The name may also be an ordinal written as `#` followed by its
+    /// number, such as `#12`. The export table is read from the memory of
+    /// the process, so this does not assume that the module is loaded at
+    /// the same address in the current process, or at all. Exports that
+    /// are forwarded to another module are resolved if that module is
+    /// loaded into the process, and otherwise an error with code
+    /// `ERROR_MOD_NOT_FOUND` is returned. This includes forwarders to API
+    /// sets, such as `api-ms-win-core-synch-l1-2-0.dll`, which are not
+    /// resolved.
+    ///
+    /// This is the equivalent of [`GetProcAddress`] for another process.
+    ///
+    /// [`GetProcAddress`]: https://learn.microsoft.com/en-us/windows/win32/api/libloaderapi/nf-libloaderapi-getprocaddress
+    pub fn export_address<M>(
+        &self,
+        process: &ProcessHandle<M>,
+        name: &str,
+    ) -> Result<Option<usize>, Error>
+    where
+        M: HasProcessQueryInformation + HasProcessVmRead,
+    {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        let process_handle = process.inner.as_ptr();
+        let mut base = self.base_address;
+        let mut export = name.as_bytes().to_vec();
+        let mut modules = None;
+        for _ in 0..MAX_FORWARDS {
+            let forwarder = match find_export(process_handle, base, &export)? {
+                None => return Ok(None),
+                Some(Export::Address(address)) => return Ok(Some(address)),
+                Some(Export::Forwarder(forwarder)) => forwarder,
+            };
+            // Forwarders have the form `module.name` or `module.#ordinal`.
+            let dot = forwarder.iter().position(|&b| b == b'.');
+            let Some(dot) = dot else {
+                unsafe { SetLastError(ERROR_BAD_EXE_FORMAT) };
+                return Err(Error(PhantomData));
+            };
+            let dll = String::from_utf8_lossy(&forwarder[..dot]) + ".dll";
+            let modules = match &mut modules {
+                Some(modules) => modules,
+                None => modules.insert(process.modules()?),
+            };
+            let target = modules.iter().find(|module| {
+                module.path().file_name().is_some_and(|name| {
+                    eq_ignore_ascii_case(name, OsStr::new(&*dll))
+                })
+            });
+            let Some(target) = target else {
+                unsafe { SetLastError(ERROR_MOD_NOT_FOUND) };
+                return Err(Error(PhantomData));
+            };
+            base = target.base_address;
+            export = forwarder[dot + 1..].to_vec();
+        }
+        unsafe { SetLastError(ERROR_MOD_NOT_FOUND) };
+        Err(Error(PhantomData))
+    }
+}
+
+/// Where an export of a module leads.
+enum Export {
+    Address(usize),
+    /// The export is forwarded to the export of another module, given as
+    /// `module.name`.
+    Forwarder(Vec<u8>),
+}
+
+/// Looks up an export, given by name or as `#ordinal`, in the export table
+/// of the module at `base` in another process.
+fn find_export(
+    process: HANDLE,
+    base: usize,
+    name: &[u8],
+) -> Result<Option<Export>, Error> {
+    let bad_format = || {
+        unsafe { SetLastError(ERROR_BAD_EXE_FORMAT) };
+        Err(Error(PhantomData))
+    };
+    let lfanew: u32 = unsafe { read(process, base + DOS_HEADER_LFANEW)? };
+    let nt = base + lfanew as usize;
+    if unsafe { read::<u32>(process, nt)? } != NT_SIGNATURE {
+        return bad_format();
+    }
+    let optional = nt + NT_OPTIONAL_HEADER;
+    let directory = match unsafe { read::<u16>(process, optional)? } {
+        0x10b => optional + EXPORT_DIRECTORY_PE32,
+        0x20b => optional + EXPORT_DIRECTORY_PE32_PLUS,
+        _ => return bad_format(),
+    };
+    let [dir_rva, dir_size]: [u32; 2] = unsafe { read(process, directory)? };
+    if dir_rva == 0 {
+        return Ok(None);
+    }
+    let (dir_rva, dir_size) = (dir_rva as usize, dir_size as usize);
+    let dir = read_bytes(
+        process,
+        base + dir_rva,
+        dir_size.max(EXPORT_DIRECTORY_SIZE),
+    )?;
+    let field = |offset: usize| u32_at(&dir, offset) as usize;
+    let (ordinal_base, function_count) = (field(16), field(20));
+    let (name_count, functions) = (field(24), base + field(28));
+    let (names, name_ordinals) = (base + field(32), base + field(36));
+
+    let index = match name.strip_prefix(b"#") {
+        Some(ordinal) => {
+            let ordinal = std::str::from_utf8(ordinal)
+                .ok()
+                .and_then(|ordinal| ordinal.parse::<usize>().ok());
+            match ordinal.and_then(|o| o.checked_sub(ordinal_base)) {
+                Some(index) => index,
+                None => return Ok(None),
+            }
+        }
+        None => {
+            // The names are sorted, so they can be searched by bisection.
+            let name_rvas = read_bytes(process, names, name_count * 4)?;
+            let (mut low, mut high) = (0, name_count);
+            let mut found = None;
+            while low < high {
+                let mid = low + (high - low) / 2;
+                let rva = u32_at(&name_rvas, mid * 4) as usize;
+                let candidate = match rva.checked_sub(dir_rva) {
+                    Some(offset) if offset < dir.len() => {
+                        c_string_at(&dir[offset..]).to_vec()
+                    }
+                    _ => read_terminated(
+                        process,
+                        base + rva,
+                        1,
+                        MAX_EXPORT_NAME,
+                    )?,
+                };
+                match candidate.as_slice().cmp(name) {
+                    core::cmp::Ordering::Less => low = mid + 1,
+                    core::cmp::Ordering::Greater => high = mid,
+                    core::cmp::Ordering::Equal => {
+                        found = Some(mid);
+                        break;
+                    }
+                }
+            }
+            let Some(found) = found else {
+                return Ok(None);
+            };
+            let ordinal: u16 =
+                unsafe { read(process, name_ordinals + found * 2)? };
+            usize::from(ordinal)
+        }
+    };
+    if index >= function_count {
+        return Ok(None);
+    }
+    let rva: u32 = unsafe { read(process, functions + index * 4)? };
+    let rva = rva as usize;
+    if rva == 0 {
+        return Ok(None);
+    }
+    // Forwarders point to a string within the export directory.
+    if (dir_rva..dir_rva + dir_size).contains(&rva) {
+        let forwarder = c_string_at(&dir[rva - dir_rva..]).to_vec();
+        return Ok(Some(Export::Forwarder(forwarder)));
+    }
+    Ok(Some(Export::Address(base + rva)))
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&buf[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// Returns the bytes of a buffer up to the first NUL.
+fn c_string_at(buf: &[u8]) -> &[u8] {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    &buf[..len]
+}
+
+impl<M: HasProcessQueryInformation + HasProcessVmRead> ProcessHandle<M> {
+    /// Returns the modules loaded into the process. The executable of the
+    /// process comes first.
+    ///
+    /// For a 32-bit process running under WOW64, both its 32-bit modules
+    /// and the 64-bit modules that implement WOW64 are listed. A 32-bit
+    /// process cannot list the modules of a 64-bit process, in which case
+    /// an error with code `ERROR_PARTIAL_COPY` is returned. Modules that
+    /// are unloaded while the list is being taken are left out.
+    ///
+    /// This corresponds to calling [`EnumProcessModulesEx`] with
+    /// `LIST_MODULES_ALL`, and [`GetModuleInformation`] and
+    /// [`GetModuleFileNameExW`] for every module.
+    ///
+    /// [`EnumProcessModulesEx`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-enumprocessmodulesex
+    /// [`GetModuleInformation`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getmoduleinformation
+    /// [`GetModuleFileNameExW`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getmodulefilenameexw
+    pub fn modules(&self) -> Result<Vec<RemoteModule>, Error> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        let process = self.inner.as_ptr();
+        let handles = module_handles(process)?;
+        let modules = handles
+            .into_iter()
+            .filter_map(|module| {
+                let mut info: MODULEINFO = unsafe { mem::zeroed() };
+                let ok = unsafe {
+                    GetModuleInformation(
+                        process,
+                        module,
+                        &mut info,
+                        mem::size_of::<MODULEINFO>() as DWORD,
+                    )
+                };
+                if ok == 0 {
+                    return None;
+                }
+                let entry_point = info.EntryPoint as usize;
+                Some(RemoteModule {
+                    base_address: info.lpBaseOfDll as usize,
+                    size: info.SizeOfImage,
+                    entry_point: if entry_point == 0 {
+                        None
+                    } else {
+                        Some(entry_point)
+                    },
+                    path: module_path(process, module)?,
+                })
+            })
+            .collect();
+        Ok(modules)
+    }
+}
+
+/// Returns the handles of the modules loaded into a process.
+fn module_handles(process: HANDLE) -> Result<Vec<HMODULE>, Error> {
+    let mut modules: Vec<HMODULE> = vec![ptr::null_mut(); 256];
+    loop {
+        let size = (modules.len() * mem::size_of::<HMODULE>()) as DWORD;
+        let mut needed: DWORD = 0;
+        let ok = unsafe {
+            EnumProcessModulesEx(
+                process,
+                modules.as_mut_ptr(),
+                size,
+                &mut needed,
+                LIST_MODULES_ALL,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        let len = needed as usize / mem::size_of::<HMODULE>();
+        if needed <= size {
+            modules.truncate(len);
+            return Ok(modules);
+        }
+        // Leave room for modules that are loaded in the meantime.
+        modules.resize(len * 2, ptr::null_mut());
+    }
+}
+
+/// Returns the path of a module, or `None` if it has been unloaded.
+fn module_path(process: HANDLE, module: HMODULE) -> Option<PathBuf> {
+    let mut buf = vec![0u16; 260];
+    loop {
+        let len = unsafe {
+            GetModuleFileNameExW(
+                process,
+                module,
+                buf.as_mut_ptr(),
+                buf.len() as DWORD,
+            )
+        } as usize;
+        // A return value equal to the buffer size means the path may have
+        // been truncated, so grow and try again.
+        if len != 0 && len < buf.len() {
+            buf.truncate(len);
+            return Some(PathBuf::from(OsString::from_wide(&buf)));
+        }
+        if len == 0 || buf.len() >= MAX_PATH_WIDE {
+            return None;
+        }
+        buf.resize(buf.len() * 2, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::current_process;
+    use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+
+    #[test]
+    fn current_process_modules_start_with_executable() {
+        let modules = current_process().modules().unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(
+            modules[0].path().canonicalize().unwrap(),
+            exe.canonicalize().unwrap()
+        );
+        assert!(modules[0].entry_point().is_some());
+        let ntdll = modules
+            .iter()
+            .find(|m| {
+                m.path().file_name().unwrap().eq_ignore_ascii_case("ntdll.dll")
+            })
+            .unwrap();
+        assert!(ntdll.size() > 0);
+    }
+
+    #[test]
+    fn export_address_matches_get_proc_address() {
+        let process = current_process();
+        let kernel32 = process
+            .modules()
+            .unwrap()
+            .into_iter()
+            .find(|m| {
+                m.path()
+                    .file_name()
+                    .unwrap()
+                    .eq_ignore_ascii_case("kernel32.dll")
+            })
+            .unwrap();
+        let local = |name: &[u8]| unsafe {
+            let module = GetModuleHandleW(
+                crate::open_process::to_wide("kernel32.dll".as_ref()).as_ptr(),
+            );
+            GetProcAddress(module, name.as_ptr().cast()) as usize
+        };
+        assert_eq!(
+            kernel32.export_address(&process, "GetCurrentProcessId").unwrap(),
+            Some(local(b"GetCurrentProcessId\0"))
+        );
+        // This is forwarded to `RtlAllocateHeap` in `ntdll.dll`.
+        assert_eq!(
+            kernel32.export_address(&process, "HeapAlloc").unwrap(),
+            Some(local(b"HeapAlloc\0"))
+        );
+        assert_eq!(
+            kernel32.export_address(&process, "NoSuchExport").unwrap(),
+            None
+        );
+    }
+}
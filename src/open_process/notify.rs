@@ -0,0 +1,327 @@
+use core::{
+    ffi::c_void,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ptr,
+    time::Duration,
+};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+};
+
+use winapi::{
+    shared::{
+        minwindef::{FALSE, TRUE},
+        ntdef::BOOLEAN,
+        winerror::WAIT_TIMEOUT,
+    },
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        synchapi::{CreateEventW, SetEvent, WaitForSingleObject},
+        threadpoollegacyapiset::UnregisterWaitEx,
+        winbase::{RegisterWaitForSingleObject, INFINITE, WAIT_OBJECT_0},
+        winnt::{
+            EVENT_ALL_ACCESS, HANDLE, PROCESS_QUERY_LIMITED_INFORMATION,
+            SYNCHRONIZE, WT_EXECUTEONLYONCE,
+        },
+    },
+};
+
+use super::{
+    track_raw, untrack_raw, wait::timeout_to_millis, ComptimeAccessRights,
+    Error, ExitStatus, HasProcessQueryLimitedInformation, HasSynchronize,
+    ProcessHandle, WaitOutcome,
+};
+
+/// A signal that is raised once a process exits, obtained via
+/// `ProcessHandle::exit_notifier`.
+///
+/// The signal is cheap to clone, and all clones observe the same exit, so
+/// it can be handed to every part of a program that is interested in the
+/// process without sharing the process handle itself.
+#[derive(Clone)]
+pub struct ExitSignal {
+    registration: Arc<Registration>,
+}
+
+/// A wait registered with the thread pool, which sets `event` once the
+/// process exits.
+struct Registration {
+    event: HANDLE,
+    wait: HANDLE,
+    // The process handle must stay open for as long as the wait is
+    // registered.
+    _process: ProcessHandle<ComptimeAccessRights<SYNCHRONIZE>>,
+}
+
+// SAFETY: Both handles are only ever used with functions that can be called
+// from any thread.
+unsafe impl Send for Registration {}
+unsafe impl Sync for Registration {}
+
+impl<M: HasSynchronize> ProcessHandle<M> {
+    /// Returns a signal that is raised once the process exits.
+    ///
+    /// The signal keeps its own handle to the process, so it stays usable
+    /// after this handle is closed.
+    ///
+    /// This corresponds to calling [`RegisterWaitForSingleObject`], with a
+    /// callback that sets an event.
+    ///
+    /// [`RegisterWaitForSingleObject`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-registerwaitforsingleobject
+    pub fn exit_notifier(&self) -> Result<ExitSignal, Error> {
+        let () = M::ASSERT;
+        let process = self
+            .with_reduced_access::<ComptimeAccessRights<SYNCHRONIZE>>(
+                PhantomData,
+            )?;
+        let event =
+            unsafe { CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null()) };
+        if event.is_null() {
+            return Err(Error(PhantomData));
+        }
+        track_raw(event, "Event", EVENT_ALL_ACCESS);
+        let mut wait: HANDLE = ptr::null_mut();
+        let ok = unsafe {
+            RegisterWaitForSingleObject(
+                &mut wait,
+                process.inner.as_ptr(),
+                Some(on_exit),
+                event,
+                INFINITE,
+                WT_EXECUTEONLYONCE,
+            )
+        };
+        if ok == 0 {
+            let code = unsafe { GetLastError() };
+            untrack_raw(event);
+            unsafe {
+                CloseHandle(event);
+                SetLastError(code);
+            }
+            return Err(Error(PhantomData));
+        }
+        let registration = Registration { event, wait, _process: process };
+        Ok(ExitSignal { registration: Arc::new(registration) })
+    }
+}
+
+unsafe extern "system" fn on_exit(event: *mut c_void, _timed_out: BOOLEAN) {
+    SetEvent(event);
+}
+
+impl ExitSignal {
+    pub(super) fn event(&self) -> HANDLE {
+        self.registration.event
+    }
+
+    /// Returns true if the process has exited.
+    pub fn has_exited(&self) -> bool {
+        let event = self.registration.event;
+        unsafe { WaitForSingleObject(event, 0) == WAIT_OBJECT_0 }
+    }
+
+    /// Blocks until the process exits or the timeout elapses. A timeout of
+    /// `None` waits indefinitely.
+    ///
+    /// Timeouts are rounded up to whole milliseconds.
+    pub fn wait(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<WaitOutcome, Error> {
+        let event = self.registration.event;
+        let millis = timeout_to_millis(timeout);
+        match unsafe { WaitForSingleObject(event, millis) } {
+            WAIT_OBJECT_0 => Ok(WaitOutcome::Signaled),
+            WAIT_TIMEOUT => Ok(WaitOutcome::TimedOut),
+            _ => Err(Error(PhantomData)),
+        }
+    }
+}
+
+impl Debug for ExitSignal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExitSignal")
+            .field("exited", &self.has_exited())
+            .finish()
+    }
+}
+
+type ExitCallback = Box<dyn FnOnce(ExitStatus) + Send>;
+
+/// The state passed to the thread pool callback of a [`WaitRegistration`].
+struct CallbackContext {
+    callback: Mutex<Option<ExitCallback>>,
+    process: ProcessHandle<
+        ComptimeAccessRights<
+            { SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION },
+        >,
+    >,
+}
+
+/// A callback registered to run when a process exits, obtained via
+/// `ProcessHandle::on_exit`.
+///
+/// Dropping the registration cancels the callback if it has not run yet,
+/// and waits for it to complete if it is running.
+#[must_use = "the callback is cancelled when the registration is dropped"]
+pub struct WaitRegistration {
+    wait: HANDLE,
+    context: *mut CallbackContext,
+}
+
+// SAFETY: The wait handle is only used to unregister the wait, which can be
+// done from any thread, and the context is only accessed by the callback
+// and after the wait has been unregistered.
+unsafe impl Send for WaitRegistration {}
+unsafe impl Sync for WaitRegistration {}
+
+impl<M: HasSynchronize + HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Registers a callback that is called with the exit status of the
+    /// process once it exits.
+    ///
+    /// The callback runs on a thread of the system thread pool. It should
+    /// return quickly, since it blocks a thread that other waits may need,
+    /// and the process is aborted if it panics. The registration keeps its
+    /// own handle to the process, so it stays active after this handle is
+    /// closed.
+    ///
+    /// This corresponds to calling [`RegisterWaitForSingleObject`].
+    ///
+    /// [`RegisterWaitForSingleObject`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-registerwaitforsingleobject
+    pub fn on_exit(
+        &self,
+        callback: impl FnOnce(ExitStatus) + Send + 'static,
+    ) -> Result<WaitRegistration, Error> {
+        let () = <M as HasSynchronize>::ASSERT;
+        let () = <M as HasProcessQueryLimitedInformation>::ASSERT;
+        let process = self.with_reduced_access::<ComptimeAccessRights<
+            { SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION },
+        >>(PhantomData)?;
+        let handle = process.inner.as_ptr();
+        let context = Box::into_raw(Box::new(CallbackContext {
+            callback: Mutex::new(Some(Box::new(callback))),
+            process,
+        }));
+        let mut wait: HANDLE = ptr::null_mut();
+        let ok = unsafe {
+            RegisterWaitForSingleObject(
+                &mut wait,
+                handle,
+                Some(run_exit_callback),
+                context.cast(),
+                INFINITE,
+                WT_EXECUTEONLYONCE,
+            )
+        };
+        if ok == 0 {
+            let code = unsafe { GetLastError() };
+            drop(unsafe { Box::from_raw(context) });
+            unsafe { SetLastError(code) };
+            return Err(Error(PhantomData));
+        }
+        Ok(WaitRegistration { wait, context })
+    }
+}
+
+unsafe extern "system" fn run_exit_callback(
+    context: *mut c_void,
+    _timed_out: BOOLEAN,
+) {
+    // The registration owns the context, and unregisters the wait before
+    // releasing it.
+    let context = &*(context as *const CallbackContext);
+    let callback = context.callback.lock().unwrap().take();
+    let (Some(callback), Ok(status)) = (callback, context.process.exit_code())
+    else {
+        return;
+    };
+    // Unwinding into the thread pool is undefined behavior.
+    if panic::catch_unwind(AssertUnwindSafe(|| callback(status))).is_err() {
+        std::process::abort();
+    }
+}
+
+impl Debug for WaitRegistration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaitRegistration").field("wait", &self.wait).finish()
+    }
+}
+
+impl Drop for WaitRegistration {
+    fn drop(&mut self) {
+        unsafe {
+            UnregisterWaitEx(self.wait, INVALID_HANDLE_VALUE);
+            drop(Box::from_raw(self.context));
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        // Waiting for a running callback to complete ensures that it never
+        // sets a closed event.
+        untrack_raw(self.event);
+        unsafe {
+            UnregisterWaitEx(self.wait, INVALID_HANDLE_VALUE);
+            CloseHandle(self.event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::open_process;
+
+    #[test]
+    fn signal_outlives_handle() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/q", "/k"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<crate::access_rights!(SYNCHRONIZE)>(
+            PhantomData,
+            false,
+            &child,
+        )
+        .unwrap();
+        let signal = handle.exit_notifier().unwrap();
+        drop(handle);
+        let clone = signal.clone();
+        assert!(!clone.has_exited());
+        drop(child.stdin.take());
+        assert_eq!(signal.wait(None).unwrap(), WaitOutcome::Signaled);
+        assert!(clone.has_exited());
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn callback_receives_exit_status() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/q", "/k"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<
+            crate::access_rights!(
+                SYNCHRONIZE,
+                PROCESS_QUERY_LIMITED_INFORMATION
+            ),
+        >(PhantomData, false, &child)
+        .unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let registration = handle
+            .on_exit(move |status| sender.send(status).unwrap())
+            .unwrap();
+        drop(handle);
+        drop(child.stdin.take());
+        let status = receiver.recv().unwrap();
+        assert!(matches!(status, ExitStatus::Exited(_)));
+        drop(registration);
+        child.wait().unwrap();
+    }
+}
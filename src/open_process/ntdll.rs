@@ -0,0 +1,320 @@
+//! Native API functions that winapi does not expose.
+//!
+//! These are exported by `ntdll.dll`, which is mapped into every process.
+#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+
+use core::marker::PhantomData;
+
+#[cfg(feature = "ntdll")]
+use winapi::shared::{
+    basetsd::{SIZE_T, ULONG_PTR},
+    minwindef::USHORT,
+    ntdef::{BOOLEAN, LIST_ENTRY, LONG, LONGLONG, ULONGLONG, UNICODE_STRING},
+};
+use winapi::{
+    shared::{
+        minwindef::ULONG,
+        ntdef::{HANDLE, NTSTATUS, PULONG, PVOID},
+    },
+    um::{errhandlingapi::SetLastError, winnt::ACCESS_MASK},
+};
+
+use super::Error;
+
+pub(super) const ObjectBasicInformation: ULONG = 0;
+#[cfg(feature = "ntdll")]
+pub(super) const ProcessBasicInformation: ULONG = 0;
+#[cfg(feature = "ntdll")]
+pub(super) const ProcessIoPriority: ULONG = 33;
+#[cfg(feature = "ntdll")]
+pub(super) const ProcessWow64Information: ULONG = 26;
+#[cfg(feature = "ntdll")]
+pub(super) const ObjectNameInformation: ULONG = 1;
+#[cfg(feature = "ntdll")]
+pub(super) const ObjectTypeInformation: ULONG = 2;
+#[cfg(feature = "ntdll")]
+pub(super) const SystemProcessInformation: ULONG = 5;
+#[cfg(feature = "ntdll")]
+pub(super) const ThreadBasicInformation: ULONG = 0;
+#[cfg(feature = "ntdll")]
+pub(super) const ThreadQuerySetWin32StartAddress: ULONG = 9;
+#[cfg(feature = "ntdll")]
+pub(super) const ThreadIoPriority: ULONG = 22;
+#[cfg(feature = "ntdll")]
+pub(super) const SystemExtendedHandleInformation: ULONG = 64;
+
+#[cfg(feature = "ntdll")]
+pub(super) const STATUS_INFO_LENGTH_MISMATCH: NTSTATUS =
+    0xC000_0004_u32 as NTSTATUS;
+
+#[repr(C)]
+pub(super) struct PUBLIC_OBJECT_BASIC_INFORMATION {
+    pub(super) Attributes: ULONG,
+    pub(super) GrantedAccess: ACCESS_MASK,
+    pub(super) HandleCount: ULONG,
+    pub(super) PointerCount: ULONG,
+    pub(super) Reserved: [ULONG; 10],
+}
+
+// The layouts below only declare the leading fields that are used, and are
+// only valid for processes with the same bitness as the current one.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct PROCESS_BASIC_INFORMATION {
+    pub(super) ExitStatus: NTSTATUS,
+    pub(super) PebBaseAddress: PVOID,
+    pub(super) AffinityMask: ULONG_PTR,
+    pub(super) BasePriority: LONG,
+    pub(super) UniqueProcessId: ULONG_PTR,
+    pub(super) InheritedFromUniqueProcessId: ULONG_PTR,
+}
+
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct THREAD_BASIC_INFORMATION {
+    pub(super) ExitStatus: NTSTATUS,
+    pub(super) TebBaseAddress: PVOID,
+    // The `CLIENT_ID` of the thread.
+    pub(super) UniqueProcess: HANDLE,
+    pub(super) UniqueThread: HANDLE,
+    pub(super) AffinityMask: ULONG_PTR,
+    pub(super) Priority: LONG,
+    pub(super) BasePriority: LONG,
+}
+
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+#[allow(clippy::upper_case_acronyms)]
+pub(super) struct PEB {
+    pub(super) Reserved: [BOOLEAN; 4],
+    pub(super) Mutant: HANDLE,
+    pub(super) ImageBaseAddress: PVOID,
+    pub(super) Ldr: PVOID,
+}
+
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct PEB_LDR_DATA {
+    pub(super) Length: ULONG,
+    pub(super) Initialized: BOOLEAN,
+    pub(super) SsHandle: HANDLE,
+    pub(super) InLoadOrderModuleList: LIST_ENTRY,
+}
+
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct LDR_DATA_TABLE_ENTRY {
+    pub(super) InLoadOrderLinks: LIST_ENTRY,
+    pub(super) InMemoryOrderLinks: LIST_ENTRY,
+    pub(super) InInitializationOrderLinks: LIST_ENTRY,
+    pub(super) DllBase: PVOID,
+    pub(super) EntryPoint: PVOID,
+    pub(super) SizeOfImage: ULONG,
+    pub(super) FullDllName: UNICODE_STRING,
+    pub(super) BaseDllName: UNICODE_STRING,
+    pub(super) Flags: ULONG,
+    pub(super) ObsoleteLoadCount: USHORT,
+    pub(super) TlsIndex: USHORT,
+    pub(super) HashLinks: LIST_ENTRY,
+    pub(super) TimeDateStamp: ULONG,
+}
+
+/// The 32-bit `LIST_ENTRY` of a process running under WOW64.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct LIST_ENTRY32 {
+    pub(super) Flink: u32,
+    pub(super) Blink: u32,
+}
+
+/// The 32-bit `UNICODE_STRING` of a process running under WOW64.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct UNICODE_STRING32 {
+    pub(super) Length: USHORT,
+    pub(super) MaximumLength: USHORT,
+    pub(super) Buffer: u32,
+}
+
+/// The 32-bit `PEB` of a process running under WOW64.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct PEB32 {
+    pub(super) Reserved: [BOOLEAN; 4],
+    pub(super) Mutant: u32,
+    pub(super) ImageBaseAddress: u32,
+    pub(super) Ldr: u32,
+}
+
+/// The 32-bit `PEB_LDR_DATA` of a process running under WOW64.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct PEB_LDR_DATA32 {
+    pub(super) Length: ULONG,
+    pub(super) Initialized: BOOLEAN,
+    pub(super) SsHandle: u32,
+    pub(super) InLoadOrderModuleList: LIST_ENTRY32,
+}
+
+/// The 32-bit `LDR_DATA_TABLE_ENTRY` of a process running under WOW64.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct LDR_DATA_TABLE_ENTRY32 {
+    pub(super) InLoadOrderLinks: LIST_ENTRY32,
+    pub(super) InMemoryOrderLinks: LIST_ENTRY32,
+    pub(super) InInitializationOrderLinks: LIST_ENTRY32,
+    pub(super) DllBase: u32,
+    pub(super) EntryPoint: u32,
+    pub(super) SizeOfImage: ULONG,
+    pub(super) FullDllName: UNICODE_STRING32,
+    pub(super) BaseDllName: UNICODE_STRING32,
+    pub(super) Flags: ULONG,
+    pub(super) ObsoleteLoadCount: USHORT,
+    pub(super) TlsIndex: USHORT,
+    pub(super) HashLinks: LIST_ENTRY32,
+    pub(super) TimeDateStamp: ULONG,
+}
+
+/// The header of the `SYSTEM_HANDLE_INFORMATION_EX` returned for
+/// `SystemExtendedHandleInformation`, which is followed by the entries.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct SYSTEM_HANDLE_INFORMATION_EX {
+    pub(super) NumberOfHandles: ULONG_PTR,
+    pub(super) Reserved: ULONG_PTR,
+}
+
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct SYSTEM_HANDLE_TABLE_ENTRY_INFO_EX {
+    pub(super) Object: PVOID,
+    pub(super) UniqueProcessId: ULONG_PTR,
+    pub(super) HandleValue: ULONG_PTR,
+    pub(super) GrantedAccess: ACCESS_MASK,
+    pub(super) CreatorBackTraceIndex: USHORT,
+    pub(super) ObjectTypeIndex: USHORT,
+    pub(super) HandleAttributes: ULONG,
+    pub(super) Reserved: ULONG,
+}
+
+/// An entry of the list returned for `SystemProcessInformation`, which is
+/// followed by `NumberOfThreads` entries of `SYSTEM_THREAD_INFORMATION`.
+/// The `LARGE_INTEGER`s are declared as `LONGLONG`s.
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct SYSTEM_PROCESS_INFORMATION {
+    pub(super) NextEntryOffset: ULONG,
+    pub(super) NumberOfThreads: ULONG,
+    pub(super) WorkingSetPrivateSize: LONGLONG,
+    pub(super) HardFaultCount: ULONG,
+    pub(super) NumberOfThreadsHighWatermark: ULONG,
+    pub(super) CycleTime: ULONGLONG,
+    pub(super) CreateTime: LONGLONG,
+    pub(super) UserTime: LONGLONG,
+    pub(super) KernelTime: LONGLONG,
+    pub(super) ImageName: UNICODE_STRING,
+    pub(super) BasePriority: LONG,
+    pub(super) UniqueProcessId: HANDLE,
+    pub(super) InheritedFromUniqueProcessId: HANDLE,
+    pub(super) HandleCount: ULONG,
+    pub(super) SessionId: ULONG,
+    pub(super) UniqueProcessKey: ULONG_PTR,
+    pub(super) PeakVirtualSize: SIZE_T,
+    pub(super) VirtualSize: SIZE_T,
+    pub(super) PageFaultCount: ULONG,
+    pub(super) PeakWorkingSetSize: SIZE_T,
+    pub(super) WorkingSetSize: SIZE_T,
+    pub(super) QuotaPeakPagedPoolUsage: SIZE_T,
+    pub(super) QuotaPagedPoolUsage: SIZE_T,
+    pub(super) QuotaPeakNonPagedPoolUsage: SIZE_T,
+    pub(super) QuotaNonPagedPoolUsage: SIZE_T,
+    pub(super) PagefileUsage: SIZE_T,
+    pub(super) PeakPagefileUsage: SIZE_T,
+    pub(super) PrivatePageCount: SIZE_T,
+    pub(super) ReadOperationCount: LONGLONG,
+    pub(super) WriteOperationCount: LONGLONG,
+    pub(super) OtherOperationCount: LONGLONG,
+    pub(super) ReadTransferCount: LONGLONG,
+    pub(super) WriteTransferCount: LONGLONG,
+    pub(super) OtherTransferCount: LONGLONG,
+}
+
+#[cfg(feature = "ntdll")]
+#[repr(C)]
+pub(super) struct SYSTEM_THREAD_INFORMATION {
+    pub(super) KernelTime: LONGLONG,
+    pub(super) UserTime: LONGLONG,
+    pub(super) CreateTime: LONGLONG,
+    pub(super) WaitTime: ULONG,
+    pub(super) StartAddress: PVOID,
+    // The `CLIENT_ID` of the thread.
+    pub(super) UniqueProcess: HANDLE,
+    pub(super) UniqueThread: HANDLE,
+    pub(super) Priority: LONG,
+    pub(super) BasePriority: LONG,
+    pub(super) ContextSwitches: ULONG,
+    pub(super) ThreadState: ULONG,
+    pub(super) WaitReason: ULONG,
+}
+
+#[link(name = "ntdll")]
+extern "system" {
+    pub(super) fn NtQueryObject(
+        Handle: HANDLE,
+        ObjectInformationClass: ULONG,
+        ObjectInformation: PVOID,
+        ObjectInformationLength: ULONG,
+        ReturnLength: PULONG,
+    ) -> NTSTATUS;
+    fn RtlNtStatusToDosError(Status: NTSTATUS) -> ULONG;
+}
+
+// These are undocumented, and so only available with the `ntdll` feature.
+#[cfg(feature = "ntdll")]
+#[link(name = "ntdll")]
+extern "system" {
+    pub(super) fn NtQueryInformationProcess(
+        ProcessHandle: HANDLE,
+        ProcessInformationClass: ULONG,
+        ProcessInformation: PVOID,
+        ProcessInformationLength: ULONG,
+        ReturnLength: PULONG,
+    ) -> NTSTATUS;
+    pub(super) fn NtSetInformationProcess(
+        ProcessHandle: HANDLE,
+        ProcessInformationClass: ULONG,
+        ProcessInformation: PVOID,
+        ProcessInformationLength: ULONG,
+    ) -> NTSTATUS;
+    pub(super) fn NtQuerySystemInformation(
+        SystemInformationClass: ULONG,
+        SystemInformation: PVOID,
+        SystemInformationLength: ULONG,
+        ReturnLength: PULONG,
+    ) -> NTSTATUS;
+    pub(super) fn NtQueryInformationThread(
+        ThreadHandle: HANDLE,
+        ThreadInformationClass: ULONG,
+        ThreadInformation: PVOID,
+        ThreadInformationLength: ULONG,
+        ReturnLength: PULONG,
+    ) -> NTSTATUS;
+    pub(super) fn NtSetInformationThread(
+        ThreadHandle: HANDLE,
+        ThreadInformationClass: ULONG,
+        ThreadInformation: PVOID,
+        ThreadInformationLength: ULONG,
+    ) -> NTSTATUS;
+    pub(super) fn NtSuspendProcess(ProcessHandle: HANDLE) -> NTSTATUS;
+    pub(super) fn NtResumeProcess(ProcessHandle: HANDLE) -> NTSTATUS;
+}
+
+/// Turns a failed `NTSTATUS` into an [`Error`] by storing the equivalent
+/// Win32 error code as the last error.
+pub(super) fn check(status: NTSTATUS) -> Result<(), Error> {
+    if status >= 0 {
+        return Ok(());
+    }
+    unsafe { SetLastError(RtlNtStatusToDosError(status)) };
+    Err(Error(PhantomData))
+}
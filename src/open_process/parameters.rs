@@ -0,0 +1,321 @@
+use std::{
+    ffi::OsString,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::PathBuf,
+};
+
+use winapi::shared::ntdef::HANDLE;
+
+use super::{
+    memory::{read, read_wide, read_wide_units},
+    peb::{peb_address, wow64_peb_address},
+    Error, HasProcessQueryInformation, HasProcessVmRead, ProcessHandle,
+};
+
+/// The offsets of the fields of the undocumented `PEB` and
+/// `RTL_USER_PROCESS_PARAMETERS` structures that are read, which depend on
+/// the bitness of the process.
+struct Layout {
+    pointer_size: usize,
+    process_parameters: usize,
+    current_directory: usize,
+    command_line: usize,
+    environment: usize,
+    environment_size: usize,
+}
+
+const LAYOUT_32: Layout = Layout {
+    pointer_size: 4,
+    process_parameters: 0x10,
+    current_directory: 0x24,
+    command_line: 0x40,
+    environment: 0x48,
+    environment_size: 0x290,
+};
+
+#[cfg(target_pointer_width = "64")]
+const LAYOUT_NATIVE: Layout = Layout {
+    pointer_size: 8,
+    process_parameters: 0x20,
+    current_directory: 0x38,
+    command_line: 0x70,
+    environment: 0x80,
+    environment_size: 0x3f0,
+};
+#[cfg(target_pointer_width = "32")]
+const LAYOUT_NATIVE: Layout = LAYOUT_32;
+
+/// The `RTL_USER_PROCESS_PARAMETERS` of another process, which hold its
+/// command line, environment and current directory.
+struct RemoteParameters {
+    process: HANDLE,
+    layout: &'static Layout,
+    address: usize,
+}
+
+impl RemoteParameters {
+    /// Locates the parameters of a process through its process environment
+    /// block. Returns `None` if they have not been set up yet.
+    ///
+    /// For a 32-bit process running under WOW64, the parameters are read
+    /// from its 32-bit environment block, which is what the process itself
+    /// sees. A 32-bit process cannot read the parameters of a 64-bit
+    /// process, in which case an error with code `ERROR_NOT_SUPPORTED` is
+    /// returned.
+    fn locate(process: HANDLE) -> Result<Option<RemoteParameters>, Error> {
+        let (peb, layout) = match wow64_peb_address(process)? {
+            Some(peb) => (peb, &LAYOUT_32),
+            None => (peb_address(process)?, &LAYOUT_NATIVE),
+        };
+        let mut parameters = RemoteParameters { process, layout, address: 0 };
+        parameters.address =
+            parameters.pointer(peb + layout.process_parameters)?;
+        if parameters.address == 0 {
+            return Ok(None);
+        }
+        Ok(Some(parameters))
+    }
+
+    /// Reads a pointer of the bitness of the process.
+    fn pointer(&self, address: usize) -> Result<usize, Error> {
+        Ok(if self.layout.pointer_size == 4 {
+            unsafe { read::<u32>(self.process, address)? as usize }
+        } else {
+            unsafe { read::<u64>(self.process, address)? as usize }
+        })
+    }
+
+    /// Reads the `UNICODE_STRING` at the given offset into the parameters.
+    fn string(&self, offset: usize) -> Result<OsString, Error> {
+        let address = self.address + offset;
+        let len = unsafe { read::<u16>(self.process, address)? };
+        // The buffer is aligned to the size of a pointer.
+        let buffer = self.pointer(address + self.layout.pointer_size)?;
+        read_wide(self.process, buffer, len.into())
+    }
+
+    /// Reads the environment block of the process.
+    fn environment(&self) -> Result<Vec<u16>, Error> {
+        let layout = self.layout;
+        let block = self.pointer(self.address + layout.environment)?;
+        let size = self.pointer(self.address + layout.environment_size)?;
+        read_wide_units(self.process, block, size)
+    }
+}
+
+/// Removes the trailing separator of a directory, unless it is the root of
+/// a drive.
+fn trim_separator(dir: OsString) -> OsString {
+    let mut units: Vec<u16> = dir.encode_wide().collect();
+    let is_root = match units.as_slice() {
+        [_, colon, _] => *colon == u16::from(b':'),
+        _ => false,
+    };
+    if !is_root && units.last() == Some(&u16::from(b'\\')) {
+        units.pop();
+        return OsString::from_wide(&units);
+    }
+    dir
+}
+
+/// Splits an environment block into its variables.
+///
+/// The block consists of `name=value` strings that are each terminated by
+/// a NUL, and is terminated by an empty string. The names of the variables
+/// that track the current directories of drives start with a `=`, as in
+/// `=C:=C:\Windows`, so the name extends to the first `=` after its first
+/// character.
+fn parse_environment(block: &[u16]) -> Vec<(OsString, OsString)> {
+    let eq = u16::from(b'=');
+    block
+        .split(|&unit| unit == 0)
+        .take_while(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let pos = entry[1..].iter().position(|&unit| unit == eq)? + 1;
+            Some((
+                OsString::from_wide(&entry[..pos]),
+                OsString::from_wide(&entry[pos + 1..]),
+            ))
+        })
+        .collect()
+}
+
+impl<M: HasProcessQueryInformation + HasProcessVmRead> ProcessHandle<M> {
+    /// Returns the command line the process was started with.
+    ///
+    /// The command line is read from the memory of the process, so it
+    /// reflects any changes the process made to it, and is empty if the
+    /// process has not been initialized yet. For a 32-bit process running
+    /// under WOW64, this reads the command line of its 32-bit environment
+    /// block. A 32-bit process cannot read the command line of a 64-bit
+    /// process, in which case an error with code `ERROR_NOT_SUPPORTED` is
+    /// returned.
+    ///
+    /// Use `CommandLineToArgvW` or a similar parser to split the command
+    /// line into arguments, since programs are free to parse it their own
+    /// way.
+    ///
+    /// This relies on the undocumented layout of the process environment
+    /// block, as read with `NtQueryInformationProcess` and
+    /// [`ReadProcessMemory`].
+    ///
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn command_line(&self) -> Result<OsString, Error> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        match RemoteParameters::locate(self.inner.as_ptr())? {
+            Some(parameters) => {
+                parameters.string(parameters.layout.command_line)
+            }
+            None => Ok(OsString::new()),
+        }
+    }
+
+    /// Returns the environment variables of the process, in the order they
+    /// appear in its environment block.
+    ///
+    /// The environment is read from the memory of the process, so it
+    /// reflects any changes the process made to it, and is empty if the
+    /// process has not been initialized yet. Since the environment is read
+    /// while the process keeps running, this may fail if the process
+    /// changes its environment concurrently. Like [`command_line`], this
+    /// is not supported by 32-bit processes for 64-bit processes.
+    ///
+    /// Besides the regular variables, the block contains variables whose
+    /// names start with a `=`, such as `=C:`, which track the current
+    /// directories of drives.
+    ///
+    /// This relies on the undocumented layout of the process environment
+    /// block, as read with `NtQueryInformationProcess` and
+    /// [`ReadProcessMemory`].
+    ///
+    /// [`command_line`]: ProcessHandle::command_line
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn environment(&self) -> Result<Vec<(OsString, OsString)>, Error> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        match RemoteParameters::locate(self.inner.as_ptr())? {
+            Some(parameters) => {
+                Ok(parse_environment(&parameters.environment()?))
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the current directory of the process.
+    ///
+    /// Like `std::env::current_dir`, the returned path has no trailing
+    /// separator, unless it is the root of a drive. The directory is read
+    /// while the process keeps running, so this may fail if the process
+    /// changes its current directory concurrently. Like [`command_line`],
+    /// this is not supported by 32-bit processes for 64-bit processes.
+    ///
+    /// This relies on the undocumented layout of the process environment
+    /// block, as read with `NtQueryInformationProcess` and
+    /// [`ReadProcessMemory`].
+    ///
+    /// [`command_line`]: ProcessHandle::command_line
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn current_directory(&self) -> Result<PathBuf, Error> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        match RemoteParameters::locate(self.inner.as_ptr())? {
+            // This is the `DosPath` of the `CURDIR`, which always ends with a
+            // separator.
+            Some(parameters) => Ok(trim_separator(
+                parameters.string(parameters.layout.current_directory)?,
+            )
+            .into()),
+            None => Ok(PathBuf::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{current_process, open_process};
+    use core::{marker::PhantomData, slice};
+    use std::process::{Command, Stdio};
+    use winapi::um::{
+        processenv::GetCommandLineW,
+        winnt::{PROCESS_QUERY_INFORMATION, PROCESS_VM_READ},
+    };
+
+    #[test]
+    fn current_process_command_line() {
+        let expected = unsafe {
+            let ptr = GetCommandLineW();
+            let len = (0..).take_while(|&i| *ptr.add(i) != 0).count();
+            OsString::from_wide(slice::from_raw_parts(ptr, len))
+        };
+        assert_eq!(current_process().command_line().unwrap(), expected);
+    }
+
+    #[test]
+    fn child_command_line() {
+        let mut child = Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .stdin(Stdio::null())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<
+            crate::access_rights!(PROCESS_QUERY_INFORMATION, PROCESS_VM_READ),
+        >(PhantomData, false, &child);
+        let command_line = handle.and_then(|handle| handle.command_line());
+        child.kill().unwrap();
+        child.wait().unwrap();
+        let command_line = command_line.unwrap();
+        assert!(command_line.to_string_lossy().contains("ping -n 30"));
+    }
+
+    #[test]
+    fn current_process_current_directory() {
+        assert_eq!(
+            current_process().current_directory().unwrap(),
+            std::env::current_dir().unwrap()
+        );
+    }
+
+    #[test]
+    fn trim_separator_keeps_roots() {
+        assert_eq!(trim_separator("C:\\".into()), "C:\\");
+        assert_eq!(trim_separator("C:\\Windows\\".into()), "C:\\Windows");
+    }
+
+    #[test]
+    fn parse_environment_block() {
+        let block: Vec<u16> =
+            "=C:=C:\\Windows\0PATH=a;b\0EMPTY=\0\0".encode_utf16().collect();
+        let vars = parse_environment(&block);
+        assert_eq!(
+            vars,
+            [
+                ("=C:".into(), "C:\\Windows".into()),
+                ("PATH".into(), "a;b".into()),
+                ("EMPTY".into(), "".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn child_environment() {
+        let mut child = Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .env("WINAPI_UTIL_TEST", "some value")
+            .stdin(Stdio::null())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<
+            crate::access_rights!(PROCESS_QUERY_INFORMATION, PROCESS_VM_READ),
+        >(PhantomData, false, &child);
+        let environment = handle.and_then(|handle| handle.environment());
+        child.kill().unwrap();
+        child.wait().unwrap();
+        let environment = environment.unwrap();
+        assert!(environment
+            .iter()
+            .any(|(name, value)| name == "WINAPI_UTIL_TEST"
+                && value == "some value"));
+    }
+}
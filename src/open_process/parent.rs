@@ -0,0 +1,142 @@
+use core::marker::PhantomData;
+#[cfg(feature = "ntdll")]
+use core::{mem, ptr};
+
+#[cfg(feature = "ntdll")]
+use winapi::shared::minwindef::{DWORD, ULONG};
+use winapi::{
+    shared::winerror::ERROR_INVALID_PARAMETER,
+    um::errhandlingapi::SetLastError,
+};
+
+#[cfg(feature = "ntdll")]
+use super::ntdll;
+use super::{
+    open_process, snapshot::Snapshot, Error,
+    HasProcessQueryLimitedInformation, ProcessHandle, ProcessId,
+    ProcessIdentity, QueryLimitedAccess,
+};
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the identifier of the process that created the process.
+    ///
+    /// The parent may have exited since, and its identifier may even have
+    /// been reused by an unrelated process. Use [`ProcessId::parent`] to
+    /// rule that out.
+    ///
+    /// With the `ntdll` feature, this corresponds to calling
+    /// `NtQueryInformationProcess` with `ProcessBasicInformation`, falling
+    /// back to a Toolhelp snapshot, which is always used otherwise.
+    pub fn parent_id(&self) -> Result<ProcessId, Error> {
+        let () = M::ASSERT;
+        #[cfg(feature = "ntdll")]
+        if let Ok(pid) = self.parent_id_from_basic_information() {
+            return Ok(pid);
+        }
+        parent_id_from_snapshot(self.pid()?)
+    }
+
+    #[cfg(feature = "ntdll")]
+    fn parent_id_from_basic_information(&self) -> Result<ProcessId, Error> {
+        let mut info: ntdll::PROCESS_BASIC_INFORMATION =
+            unsafe { mem::zeroed() };
+        let status = unsafe {
+            ntdll::NtQueryInformationProcess(
+                self.inner.as_ptr(),
+                ntdll::ProcessBasicInformation,
+                (&mut info as *mut ntdll::PROCESS_BASIC_INFORMATION).cast(),
+                mem::size_of::<ntdll::PROCESS_BASIC_INFORMATION>() as ULONG,
+                ptr::null_mut(),
+            )
+        };
+        ntdll::check(status)?;
+        Ok(ProcessId::from_raw(info.InheritedFromUniqueProcessId as DWORD))
+    }
+}
+
+impl ProcessId {
+    /// Returns the identity of the process that created this one, or
+    /// `None` if it has exited and its identifier is no longer in use or
+    /// has been reused.
+    ///
+    /// A reused identifier is recognized by its process having been created
+    /// after this one, which the real parent cannot have been.
+    pub fn parent(&self) -> Result<Option<ProcessIdentity>, Error> {
+        let child =
+            open_process::<QueryLimitedAccess>(PhantomData, false, *self)?;
+        let created = child.times()?.creation();
+        let parent_id = child.parent_id()?;
+        let parent = match open_process::<QueryLimitedAccess>(
+            PhantomData,
+            false,
+            parent_id,
+        ) {
+            Ok(parent) => parent,
+            // OpenProcess reports identifiers that are not in use this way.
+            Err(err) if err.code().as_dword() == ERROR_INVALID_PARAMETER => {
+                return Ok(None)
+            }
+            Err(err) => return Err(err),
+        };
+        let identity = parent.identity()?;
+        if identity.creation_time() > created {
+            return Ok(None);
+        }
+        Ok(Some(identity))
+    }
+}
+
+/// Looks up the parent of a process in a Toolhelp snapshot, which does not
+/// require any access to the process.
+fn parent_id_from_snapshot(pid: ProcessId) -> Result<ProcessId, Error> {
+    let parent = process_parents()?
+        .into_iter()
+        .find(|&(child, _)| child == pid)
+        .map(|(_, parent)| parent);
+    // A process that is missing from the snapshot has exited in the
+    // meantime, which OpenProcess would report the same way.
+    parent.ok_or_else(|| {
+        unsafe { SetLastError(ERROR_INVALID_PARAMETER) };
+        Error(PhantomData)
+    })
+}
+
+/// Returns the identifier of every running process together with that of
+/// its parent, taken from a Toolhelp snapshot.
+pub(super) fn process_parents() -> Result<Vec<(ProcessId, ProcessId)>, Error> {
+    Ok(Snapshot::processes()?
+        .map(|process| (process.pid(), process.parent_id()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{current_process, RuntimeAccessRights};
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    #[test]
+    fn child_knows_its_parent() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/q", "/k"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<RuntimeAccessRights>(
+            PROCESS_QUERY_LIMITED_INFORMATION,
+            false,
+            &child,
+        )
+        .unwrap();
+        assert_eq!(handle.parent_id().unwrap(), ProcessId::current());
+        let pid = ProcessId::from_raw(child.id());
+        assert_eq!(
+            parent_id_from_snapshot(pid).unwrap(),
+            ProcessId::current()
+        );
+        let parent = pid.parent().unwrap().unwrap();
+        assert_eq!(parent, current_process().identity().unwrap());
+        drop(child.stdin.take());
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+#[cfg(target_pointer_width = "32")]
+use core::marker::PhantomData;
+use core::{
+    mem::{self, MaybeUninit},
+    ptr,
+};
+use std::{
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+#[cfg(target_pointer_width = "64")]
+use winapi::shared::basetsd::ULONG_PTR;
+use winapi::shared::{
+    minwindef::ULONG,
+    ntdef::{HANDLE, UNICODE_STRING},
+};
+#[cfg(target_pointer_width = "32")]
+use winapi::{
+    shared::winerror::ERROR_NOT_SUPPORTED,
+    um::{errhandlingapi::SetLastError, processthreadsapi::GetCurrentProcess},
+};
+
+#[cfg(target_pointer_width = "32")]
+use super::arch::is_wow64;
+use super::{
+    memory::{read, read_wide},
+    ntdll, Error, HasProcessQueryInformation, HasProcessVmRead, ProcessHandle,
+};
+
+/// The upper bound on the number of modules that are walked, in case the
+/// list is corrupted or being modified into a cycle.
+const MAX_MODULES: usize = 1 << 16;
+
+/// A module loaded into a process, as recorded by the loader in the
+/// process environment block.
+///
+/// See `ProcessHandle::modules_via_peb`.
+#[derive(Clone, Debug)]
+pub struct LoadedModule {
+    base_address: usize,
+    size: u32,
+    entry_point: Option<usize>,
+    path: PathBuf,
+    name: OsString,
+    time_date_stamp: u32,
+}
+
+impl LoadedModule {
+    /// Returns the address the module is mapped at.
+    pub fn base_address(&self) -> usize {
+        self.base_address
+    }
+
+    /// Returns the size of the mapped image, in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the address of the entry point of the module, or `None` for
+    /// modules without one, such as resource-only DLLs.
+    pub fn entry_point(&self) -> Option<usize> {
+        self.entry_point
+    }
+
+    /// Returns the full path of the module.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the file name of the module.
+    pub fn name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// Returns the `TimeDateStamp` of the PE header of the module, which is
+    /// usually the time it was linked in seconds since the Unix epoch. For
+    /// reproducible builds, it is a hash of the image instead.
+    pub fn time_date_stamp(&self) -> u32 {
+        self.time_date_stamp
+    }
+}
+
+impl<M: HasProcessQueryInformation + HasProcessVmRead> ProcessHandle<M> {
+    /// Returns the modules loaded into the process in the order they were
+    /// loaded, so the executable comes first, followed by `ntdll.dll`.
+    ///
+    /// Unlike [`EnumProcessModules`], this reads the loader data directly
+    /// from the memory of the process, so it also works for processes that
+    /// are still starting up, e.g. ones created suspended. If the loader
+    /// data has not been set up yet, then the returned list is empty. Since
+    /// the list is read while the process keeps running, it may be
+    /// inconsistent if modules are being loaded or unloaded concurrently.
+    ///
+    /// For a 32-bit process running under WOW64, the modules are read from
+    /// its 32-bit environment block, which is what the process itself
+    /// sees, so the 64-bit modules that implement WOW64 are not listed. A
+    /// 32-bit process cannot list the modules of a 64-bit process this way,
+    /// in which case an error with code `ERROR_NOT_SUPPORTED` is returned.
+    ///
+    /// This relies on the undocumented layout of the process environment
+    /// block, as read with `NtQueryInformationProcess` and
+    /// [`ReadProcessMemory`].
+    ///
+    /// [`EnumProcessModules`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-enumprocessmodules
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn modules_via_peb(&self) -> Result<Vec<LoadedModule>, Error> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        let process = self.inner.as_ptr();
+        match wow64_peb_address(process)? {
+            Some(peb) => wow64_modules(process, peb),
+            None => native_modules(process),
+        }
+    }
+}
+
+/// Walks the loader data of a process that has the bitness of the current
+/// one.
+fn native_modules(process: HANDLE) -> Result<Vec<LoadedModule>, Error> {
+    let peb: ntdll::PEB = unsafe { read(process, peb_address(process)?)? };
+    if peb.Ldr.is_null() {
+        return Ok(Vec::new());
+    }
+    let ldr: ntdll::PEB_LDR_DATA = unsafe { read(process, peb.Ldr as usize)? };
+
+    // The list is circular, and its head lives in the loader data.
+    let head = peb.Ldr as usize + in_load_order_module_list_offset();
+    let mut modules = Vec::new();
+    let mut next = ldr.InLoadOrderModuleList.Flink as usize;
+    while next != head && next != 0 && modules.len() < MAX_MODULES {
+        // The links are the first field of the entry.
+        let entry: ntdll::LDR_DATA_TABLE_ENTRY =
+            unsafe { read(process, next)? };
+        next = entry.InLoadOrderLinks.Flink as usize;
+        modules.push(LoadedModule {
+            base_address: entry.DllBase as usize,
+            size: entry.SizeOfImage,
+            entry_point: non_zero(entry.EntryPoint as usize),
+            path: read_string(process, &entry.FullDllName)?.into(),
+            name: read_string(process, &entry.BaseDllName)?,
+            time_date_stamp: entry.TimeDateStamp,
+        });
+    }
+    Ok(modules)
+}
+
+/// Walks the 32-bit loader data of a process running under WOW64, given
+/// the address of its 32-bit environment block.
+fn wow64_modules(
+    process: HANDLE,
+    peb: usize,
+) -> Result<Vec<LoadedModule>, Error> {
+    let peb: ntdll::PEB32 = unsafe { read(process, peb)? };
+    if peb.Ldr == 0 {
+        return Ok(Vec::new());
+    }
+    let ldr: ntdll::PEB_LDR_DATA32 =
+        unsafe { read(process, peb.Ldr as usize)? };
+
+    let head = peb.Ldr as usize + in_load_order_module_list_offset32();
+    let mut modules = Vec::new();
+    let mut next = ldr.InLoadOrderModuleList.Flink as usize;
+    while next != head && next != 0 && modules.len() < MAX_MODULES {
+        let entry: ntdll::LDR_DATA_TABLE_ENTRY32 =
+            unsafe { read(process, next)? };
+        next = entry.InLoadOrderLinks.Flink as usize;
+        modules.push(LoadedModule {
+            base_address: entry.DllBase as usize,
+            size: entry.SizeOfImage,
+            entry_point: non_zero(entry.EntryPoint as usize),
+            path: read_string32(process, &entry.FullDllName)?.into(),
+            name: read_string32(process, &entry.BaseDllName)?,
+            time_date_stamp: entry.TimeDateStamp,
+        });
+    }
+    Ok(modules)
+}
+
+fn non_zero(address: usize) -> Option<usize> {
+    if address == 0 {
+        None
+    } else {
+        Some(address)
+    }
+}
+
+/// Returns the address of the process environment block of a process.
+///
+/// For a 32-bit process running under WOW64, this is the address of the
+/// 64-bit environment block if the current process is 64-bit.
+pub(super) fn peb_address(process: HANDLE) -> Result<usize, Error> {
+    let mut info: ntdll::PROCESS_BASIC_INFORMATION = unsafe { mem::zeroed() };
+    let status = unsafe {
+        ntdll::NtQueryInformationProcess(
+            process,
+            ntdll::ProcessBasicInformation,
+            (&mut info as *mut ntdll::PROCESS_BASIC_INFORMATION).cast(),
+            mem::size_of::<ntdll::PROCESS_BASIC_INFORMATION>() as ULONG,
+            ptr::null_mut(),
+        )
+    };
+    ntdll::check(status)?;
+    Ok(info.PebBaseAddress as usize)
+}
+
+fn in_load_order_module_list_offset() -> usize {
+    let data = MaybeUninit::<ntdll::PEB_LDR_DATA>::uninit();
+    let base = data.as_ptr();
+    let field = unsafe { ptr::addr_of!((*base).InLoadOrderModuleList) };
+    field as usize - base as usize
+}
+
+fn in_load_order_module_list_offset32() -> usize {
+    let data = MaybeUninit::<ntdll::PEB_LDR_DATA32>::uninit();
+    let base = data.as_ptr();
+    let field = unsafe { ptr::addr_of!((*base).InLoadOrderModuleList) };
+    field as usize - base as usize
+}
+
+fn read_string(
+    process: HANDLE,
+    string: &UNICODE_STRING,
+) -> Result<OsString, Error> {
+    read_wide(process, string.Buffer as usize, string.Length.into())
+}
+
+fn read_string32(
+    process: HANDLE,
+    string: &ntdll::UNICODE_STRING32,
+) -> Result<OsString, Error> {
+    read_wide(process, string.Buffer as usize, string.Length.into())
+}
+
+/// Returns the address of the 32-bit process environment block of a
+/// process running under WOW64, or `None` if it runs natively.
+#[cfg(target_pointer_width = "64")]
+pub(super) fn wow64_peb_address(
+    process: HANDLE,
+) -> Result<Option<usize>, Error> {
+    let mut peb: ULONG_PTR = 0;
+    let status = unsafe {
+        ntdll::NtQueryInformationProcess(
+            process,
+            ntdll::ProcessWow64Information,
+            (&mut peb as *mut ULONG_PTR).cast(),
+            mem::size_of::<ULONG_PTR>() as ULONG,
+            ptr::null_mut(),
+        )
+    };
+    ntdll::check(status)?;
+    Ok(if peb == 0 { None } else { Some(peb) })
+}
+
+/// Fails for 64-bit processes, whose memory layout cannot be read with
+/// the 32-bit definitions, and returns `None` otherwise.
+#[cfg(target_pointer_width = "32")]
+pub(super) fn wow64_peb_address(
+    process: HANDLE,
+) -> Result<Option<usize>, Error> {
+    if is_wow64(unsafe { GetCurrentProcess() })? && !is_wow64(process)? {
+        unsafe { SetLastError(ERROR_NOT_SUPPORTED) };
+        return Err(Error(PhantomData));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::current_process;
+    #[cfg(target_pointer_width = "64")]
+    use crate::open_process::open_process;
+
+    #[test]
+    fn current_process_modules_start_with_executable() {
+        let modules = current_process().modules_via_peb().unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(
+            modules[0].path().canonicalize().unwrap(),
+            exe.canonicalize().unwrap()
+        );
+        assert!(modules[0].entry_point().is_some());
+        assert!(modules
+            .iter()
+            .any(|m| m.name().eq_ignore_ascii_case("ntdll.dll")));
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn wow64_child_modules_are_32_bit() {
+        use core::marker::PhantomData;
+        use std::{
+            process::{Command, Stdio},
+            thread,
+            time::Duration,
+        };
+        use winapi::um::winnt::{PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+
+        let mut child = Command::new(r"C:\Windows\SysWOW64\cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .stdin(Stdio::null())
+            .spawn()
+            .unwrap();
+        // Give the loader time to set up its data.
+        thread::sleep(Duration::from_millis(500));
+        let handle = open_process::<
+            crate::access_rights!(PROCESS_QUERY_INFORMATION, PROCESS_VM_READ),
+        >(PhantomData, false, &child);
+        let modules = handle.and_then(|handle| handle.modules_via_peb());
+        child.kill().unwrap();
+        child.wait().unwrap();
+        let modules = modules.unwrap();
+        assert!(modules[0].name().eq_ignore_ascii_case("cmd.exe"));
+        assert!(modules[0]
+            .path()
+            .to_string_lossy()
+            .to_ascii_lowercase()
+            .contains("syswow64"));
+        assert!(!modules
+            .iter()
+            .any(|m| m.name().eq_ignore_ascii_case("wow64.dll")));
+    }
+}
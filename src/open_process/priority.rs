@@ -0,0 +1,721 @@
+#[cfg(feature = "ntdll")]
+use core::ptr;
+use core::{marker::PhantomData, mem};
+
+use winapi::{
+    ctypes::c_int,
+    shared::minwindef::{DWORD, ULONG},
+    um::{
+        processthreadsapi::{
+            GetCurrentProcess, GetCurrentThread, GetPriorityClass,
+            GetProcessInformation, GetThreadInformation, GetThreadPriority,
+            ProcessMemoryPriority, SetPriorityClass, SetProcessInformation,
+            SetThreadInformation, SetThreadPriority, ThreadMemoryPriority,
+        },
+        winbase::{
+            ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS,
+            HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+            PROCESS_MODE_BACKGROUND_BEGIN, PROCESS_MODE_BACKGROUND_END,
+            REALTIME_PRIORITY_CLASS, THREAD_MODE_BACKGROUND_BEGIN,
+            THREAD_MODE_BACKGROUND_END, THREAD_PRIORITY_ABOVE_NORMAL,
+            THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_ERROR_RETURN,
+            THREAD_PRIORITY_HIGHEST, THREAD_PRIORITY_IDLE,
+            THREAD_PRIORITY_LOWEST, THREAD_PRIORITY_NORMAL,
+            THREAD_PRIORITY_TIME_CRITICAL,
+        },
+        winnt::{
+            MEMORY_PRIORITY_BELOW_NORMAL, MEMORY_PRIORITY_LOW,
+            MEMORY_PRIORITY_MEDIUM, MEMORY_PRIORITY_NORMAL,
+            MEMORY_PRIORITY_VERY_LOW,
+        },
+    },
+};
+
+#[cfg(feature = "ntdll")]
+use super::ntdll;
+use super::{
+    Error, HasProcessQueryInformation, HasProcessQueryLimitedInformation,
+    HasProcessSetInformation, HasThreadQueryInformation,
+    HasThreadQueryLimitedInformation, HasThreadSetInformation,
+    HasThreadSetLimitedInformation, ProcessHandle, ThreadHandle,
+};
+
+/// The priority class of a process, which together with the priority of
+/// each thread determines the scheduling priority of its threads.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PriorityClass {
+    /// `IDLE_PRIORITY_CLASS`, for processes that only run when the system
+    /// is idle.
+    Idle,
+    /// `BELOW_NORMAL_PRIORITY_CLASS`.
+    BelowNormal,
+    /// `NORMAL_PRIORITY_CLASS`, the default.
+    Normal,
+    /// `ABOVE_NORMAL_PRIORITY_CLASS`.
+    AboveNormal,
+    /// `HIGH_PRIORITY_CLASS`, for time-critical tasks.
+    High,
+    /// `REALTIME_PRIORITY_CLASS`, which preempts even system threads.
+    /// Setting it requires `SeIncreaseBasePriorityPrivilege`, without which
+    /// the process is given the high priority class instead.
+    Realtime,
+}
+
+impl PriorityClass {
+    fn to_raw(self) -> DWORD {
+        match self {
+            PriorityClass::Idle => IDLE_PRIORITY_CLASS,
+            PriorityClass::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+            PriorityClass::Normal => NORMAL_PRIORITY_CLASS,
+            PriorityClass::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+            PriorityClass::High => HIGH_PRIORITY_CLASS,
+            PriorityClass::Realtime => REALTIME_PRIORITY_CLASS,
+        }
+    }
+
+    fn from_raw(raw: DWORD) -> PriorityClass {
+        // Undocumented values are reported as normal.
+        match raw {
+            IDLE_PRIORITY_CLASS => PriorityClass::Idle,
+            BELOW_NORMAL_PRIORITY_CLASS => PriorityClass::BelowNormal,
+            ABOVE_NORMAL_PRIORITY_CLASS => PriorityClass::AboveNormal,
+            HIGH_PRIORITY_CLASS => PriorityClass::High,
+            REALTIME_PRIORITY_CLASS => PriorityClass::Realtime,
+            _ => PriorityClass::Normal,
+        }
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the priority class of the process.
+    ///
+    /// This corresponds to calling [`GetPriorityClass`].
+    ///
+    /// [`GetPriorityClass`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getpriorityclass
+    pub fn priority_class(&self) -> Result<PriorityClass, Error> {
+        let () = M::ASSERT;
+        match unsafe { GetPriorityClass(self.inner.as_ptr()) } {
+            0 => Err(Error(PhantomData)),
+            raw => Ok(PriorityClass::from_raw(raw)),
+        }
+    }
+}
+
+impl<M: HasProcessSetInformation> ProcessHandle<M> {
+    /// Sets the priority class of the process.
+    ///
+    /// This corresponds to calling [`SetPriorityClass`].
+    ///
+    /// [`SetPriorityClass`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setpriorityclass
+    pub fn set_priority_class(
+        &self,
+        class: PriorityClass,
+    ) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let ok =
+            unsafe { SetPriorityClass(self.inner.as_ptr(), class.to_raw()) };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+/// The priority of a thread relative to the priority class of its process,
+/// which together determine the scheduling priority of the thread.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ThreadPriority {
+    /// `THREAD_PRIORITY_IDLE`, the lowest priority within the class.
+    Idle,
+    /// `THREAD_PRIORITY_LOWEST`.
+    Lowest,
+    /// `THREAD_PRIORITY_BELOW_NORMAL`.
+    BelowNormal,
+    /// `THREAD_PRIORITY_NORMAL`, the default.
+    Normal,
+    /// `THREAD_PRIORITY_ABOVE_NORMAL`.
+    AboveNormal,
+    /// `THREAD_PRIORITY_HIGHEST`.
+    Highest,
+    /// `THREAD_PRIORITY_TIME_CRITICAL`, the highest priority within the
+    /// class.
+    TimeCritical,
+    /// Another relative priority, which threads of processes in the
+    /// realtime priority class may have, from -7 to -3 and from 3 to 6.
+    Other(i32),
+}
+
+impl ThreadPriority {
+    fn to_raw(self) -> c_int {
+        match self {
+            ThreadPriority::Idle => THREAD_PRIORITY_IDLE as c_int,
+            ThreadPriority::Lowest => THREAD_PRIORITY_LOWEST as c_int,
+            ThreadPriority::BelowNormal => {
+                THREAD_PRIORITY_BELOW_NORMAL as c_int
+            }
+            ThreadPriority::Normal => THREAD_PRIORITY_NORMAL as c_int,
+            ThreadPriority::AboveNormal => {
+                THREAD_PRIORITY_ABOVE_NORMAL as c_int
+            }
+            ThreadPriority::Highest => THREAD_PRIORITY_HIGHEST as c_int,
+            ThreadPriority::TimeCritical => {
+                THREAD_PRIORITY_TIME_CRITICAL as c_int
+            }
+            ThreadPriority::Other(raw) => raw,
+        }
+    }
+
+    fn from_raw(raw: c_int) -> ThreadPriority {
+        // winapi declares the priorities as DWORDs, although they are
+        // signed.
+        match raw as DWORD {
+            THREAD_PRIORITY_IDLE => ThreadPriority::Idle,
+            THREAD_PRIORITY_LOWEST => ThreadPriority::Lowest,
+            THREAD_PRIORITY_BELOW_NORMAL => ThreadPriority::BelowNormal,
+            THREAD_PRIORITY_NORMAL => ThreadPriority::Normal,
+            THREAD_PRIORITY_ABOVE_NORMAL => ThreadPriority::AboveNormal,
+            THREAD_PRIORITY_HIGHEST => ThreadPriority::Highest,
+            THREAD_PRIORITY_TIME_CRITICAL => ThreadPriority::TimeCritical,
+            _ => ThreadPriority::Other(raw),
+        }
+    }
+}
+
+impl<M: HasThreadQueryLimitedInformation> ThreadHandle<M> {
+    /// Returns the priority of the thread relative to the priority class of
+    /// its process.
+    ///
+    /// This corresponds to calling [`GetThreadPriority`].
+    ///
+    /// [`GetThreadPriority`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadpriority
+    pub fn priority(&self) -> Result<ThreadPriority, Error> {
+        let () = M::ASSERT;
+        match unsafe { GetThreadPriority(self.inner.as_ptr()) } {
+            raw if raw as DWORD == THREAD_PRIORITY_ERROR_RETURN => {
+                Err(Error(PhantomData))
+            }
+            raw => Ok(ThreadPriority::from_raw(raw)),
+        }
+    }
+}
+
+impl<M: HasThreadSetLimitedInformation> ThreadHandle<M> {
+    /// Sets the priority of the thread relative to the priority class of
+    /// its process.
+    ///
+    /// This corresponds to calling [`SetThreadPriority`].
+    ///
+    /// [`SetThreadPriority`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setthreadpriority
+    pub fn set_priority(&self, priority: ThreadPriority) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let ok = unsafe {
+            SetThreadPriority(self.inner.as_ptr(), priority.to_raw())
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+/// The memory priority of a process or thread, which determines how long
+/// its pages stay in memory once they are no longer part of its working set.
+///
+/// Pages with a lower priority are repurposed first when the system needs
+/// memory, so background processes can lower their priority to avoid
+/// pushing out the pages of foreground applications.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum MemoryPriority {
+    /// `MEMORY_PRIORITY_VERY_LOW`.
+    VeryLow,
+    /// `MEMORY_PRIORITY_LOW`.
+    Low,
+    /// `MEMORY_PRIORITY_MEDIUM`.
+    Medium,
+    /// `MEMORY_PRIORITY_BELOW_NORMAL`.
+    BelowNormal,
+    /// `MEMORY_PRIORITY_NORMAL`, the default.
+    Normal,
+}
+
+impl MemoryPriority {
+    fn to_raw(self) -> ULONG {
+        match self {
+            MemoryPriority::VeryLow => MEMORY_PRIORITY_VERY_LOW,
+            MemoryPriority::Low => MEMORY_PRIORITY_LOW,
+            MemoryPriority::Medium => MEMORY_PRIORITY_MEDIUM,
+            MemoryPriority::BelowNormal => MEMORY_PRIORITY_BELOW_NORMAL,
+            MemoryPriority::Normal => MEMORY_PRIORITY_NORMAL,
+        }
+    }
+
+    fn from_raw(raw: ULONG) -> MemoryPriority {
+        // Values outside of the documented range are clamped to it.
+        match raw {
+            MEMORY_PRIORITY_LOW => MemoryPriority::Low,
+            MEMORY_PRIORITY_MEDIUM => MemoryPriority::Medium,
+            MEMORY_PRIORITY_BELOW_NORMAL => MemoryPriority::BelowNormal,
+            raw if raw <= MEMORY_PRIORITY_VERY_LOW => MemoryPriority::VeryLow,
+            _ => MemoryPriority::Normal,
+        }
+    }
+}
+
+/// `MEMORY_PRIORITY_INFORMATION`, which winapi does not declare.
+#[repr(C)]
+struct MemoryPriorityInformation {
+    memory_priority: ULONG,
+}
+
+impl<M: HasProcessQueryInformation> ProcessHandle<M> {
+    /// Returns the memory priority of the process.
+    ///
+    /// This corresponds to calling [`GetProcessInformation`] with
+    /// `ProcessMemoryPriority`.
+    ///
+    /// [`GetProcessInformation`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocessinformation
+    pub fn memory_priority(&self) -> Result<MemoryPriority, Error> {
+        let () = M::ASSERT;
+        let mut info = MemoryPriorityInformation { memory_priority: 0 };
+        let ok = unsafe {
+            GetProcessInformation(
+                self.inner.as_ptr(),
+                ProcessMemoryPriority,
+                (&mut info as *mut MemoryPriorityInformation).cast(),
+                mem::size_of::<MemoryPriorityInformation>() as DWORD,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(MemoryPriority::from_raw(info.memory_priority))
+    }
+}
+
+impl<M: HasProcessSetInformation> ProcessHandle<M> {
+    /// Sets the memory priority of the process.
+    ///
+    /// This corresponds to calling [`SetProcessInformation`] with
+    /// `ProcessMemoryPriority`.
+    ///
+    /// [`SetProcessInformation`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setprocessinformation
+    pub fn set_memory_priority(
+        &self,
+        priority: MemoryPriority,
+    ) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let mut info =
+            MemoryPriorityInformation { memory_priority: priority.to_raw() };
+        let ok = unsafe {
+            SetProcessInformation(
+                self.inner.as_ptr(),
+                ProcessMemoryPriority,
+                (&mut info as *mut MemoryPriorityInformation).cast(),
+                mem::size_of::<MemoryPriorityInformation>() as DWORD,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+impl<M: HasThreadQueryInformation> ThreadHandle<M> {
+    /// Returns the memory priority of the thread, which is the priority of
+    /// the pages that it adds to the working set of its process.
+    ///
+    /// This corresponds to calling [`GetThreadInformation`] with
+    /// `ThreadMemoryPriority`.
+    ///
+    /// [`GetThreadInformation`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadinformation
+    pub fn page_priority(&self) -> Result<MemoryPriority, Error> {
+        let () = M::ASSERT;
+        let mut info = MemoryPriorityInformation { memory_priority: 0 };
+        let ok = unsafe {
+            GetThreadInformation(
+                self.inner.as_ptr(),
+                ThreadMemoryPriority,
+                (&mut info as *mut MemoryPriorityInformation).cast(),
+                mem::size_of::<MemoryPriorityInformation>() as DWORD,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(MemoryPriority::from_raw(info.memory_priority))
+    }
+}
+
+impl<M: HasThreadSetInformation> ThreadHandle<M> {
+    /// Sets the memory priority of the thread, e.g. to keep the pages read
+    /// by a background thread from pushing out those of other threads.
+    ///
+    /// This corresponds to calling [`SetThreadInformation`] with
+    /// `ThreadMemoryPriority`.
+    ///
+    /// [`SetThreadInformation`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setthreadinformation
+    pub fn set_page_priority(
+        &self,
+        priority: MemoryPriority,
+    ) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let mut info =
+            MemoryPriorityInformation { memory_priority: priority.to_raw() };
+        let ok = unsafe {
+            SetThreadInformation(
+                self.inner.as_ptr(),
+                ThreadMemoryPriority,
+                (&mut info as *mut MemoryPriorityInformation).cast(),
+                mem::size_of::<MemoryPriorityInformation>() as DWORD,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+/// A guard that keeps the current process in background processing mode,
+/// returned by [`begin_background_mode`].
+///
+/// Background processing mode is left when the guard is dropped.
+#[derive(Debug)]
+#[must_use = "background mode ends as soon as the guard is dropped"]
+pub struct BackgroundMode {
+    _private: (),
+}
+
+/// Puts the current process into background processing mode, which lowers
+/// its CPU, memory and I/O priorities in one go until the returned guard is
+/// dropped.
+///
+/// This is only possible for the current process. If the process is
+/// already in background mode, then an error with code
+/// `ERROR_PROCESS_MODE_ALREADY_BACKGROUND` is returned.
+///
+/// This corresponds to calling [`SetPriorityClass`] with
+/// `PROCESS_MODE_BACKGROUND_BEGIN`.
+///
+/// [`SetPriorityClass`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setpriorityclass
+pub fn begin_background_mode() -> Result<BackgroundMode, Error> {
+    let process = unsafe { GetCurrentProcess() };
+    if unsafe { SetPriorityClass(process, PROCESS_MODE_BACKGROUND_BEGIN) } == 0
+    {
+        return Err(Error(PhantomData));
+    }
+    Ok(BackgroundMode { _private: () })
+}
+
+impl Drop for BackgroundMode {
+    fn drop(&mut self) {
+        unsafe {
+            SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_END)
+        };
+    }
+}
+
+/// A guard that keeps the current thread in background processing mode,
+/// returned by [`begin_thread_background_mode`].
+///
+/// Background processing mode is left when the guard is dropped, which
+/// must happen on the same thread, so the guard cannot be sent to other
+/// threads.
+#[derive(Debug)]
+#[must_use = "background mode ends as soon as the guard is dropped"]
+pub struct ThreadBackgroundMode {
+    _not_send: PhantomData<*const ()>,
+}
+
+/// Puts the current thread into background processing mode, which lowers
+/// its CPU, memory and I/O priorities in one go until the returned guard is
+/// dropped.
+///
+/// If the thread is already in background mode, then an error with code
+/// `ERROR_THREAD_MODE_ALREADY_BACKGROUND` is returned.
+///
+/// This corresponds to calling [`SetThreadPriority`] with
+/// `THREAD_MODE_BACKGROUND_BEGIN`.
+///
+/// [`SetThreadPriority`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setthreadpriority
+pub fn begin_thread_background_mode() -> Result<ThreadBackgroundMode, Error> {
+    let thread = unsafe { GetCurrentThread() };
+    let mode = THREAD_MODE_BACKGROUND_BEGIN as c_int;
+    if unsafe { SetThreadPriority(thread, mode) } == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(ThreadBackgroundMode { _not_send: PhantomData })
+}
+
+impl Drop for ThreadBackgroundMode {
+    fn drop(&mut self) {
+        let mode = THREAD_MODE_BACKGROUND_END as c_int;
+        unsafe { SetThreadPriority(GetCurrentThread(), mode) };
+    }
+}
+
+/// The I/O priority of a process or thread. The priority of a process is
+/// the default priority of the I/O requests issued by its threads, which
+/// threads can override with their own.
+///
+/// Lowering the priority keeps bulk work such as backups or indexing from
+/// slowing down the I/O of interactive applications.
+#[cfg(feature = "ntdll")]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum IoPriority {
+    /// `IoPriorityVeryLow`, for background work that can wait indefinitely.
+    VeryLow,
+    /// `IoPriorityLow`.
+    Low,
+    /// `IoPriorityNormal`, the default.
+    Normal,
+}
+
+#[cfg(feature = "ntdll")]
+impl IoPriority {
+    fn to_raw(self) -> ULONG {
+        match self {
+            IoPriority::VeryLow => 0,
+            IoPriority::Low => 1,
+            IoPriority::Normal => 2,
+        }
+    }
+
+    fn from_raw(raw: ULONG) -> IoPriority {
+        // The high and critical priorities are reserved for the system and
+        // reported as normal.
+        match raw {
+            0 => IoPriority::VeryLow,
+            1 => IoPriority::Low,
+            _ => IoPriority::Normal,
+        }
+    }
+}
+
+#[cfg(feature = "ntdll")]
+impl<M: HasProcessQueryInformation> ProcessHandle<M> {
+    /// Returns the I/O priority of the process.
+    ///
+    /// This relies on the undocumented `ProcessIoPriority` information
+    /// class of `NtQueryInformationProcess`.
+    pub fn io_priority(&self) -> Result<IoPriority, Error> {
+        let () = M::ASSERT;
+        let mut raw: ULONG = 0;
+        let status = unsafe {
+            ntdll::NtQueryInformationProcess(
+                self.inner.as_ptr(),
+                ntdll::ProcessIoPriority,
+                (&mut raw as *mut ULONG).cast(),
+                mem::size_of::<ULONG>() as ULONG,
+                ptr::null_mut(),
+            )
+        };
+        ntdll::check(status)?;
+        Ok(IoPriority::from_raw(raw))
+    }
+}
+
+#[cfg(feature = "ntdll")]
+impl<M: HasProcessSetInformation> ProcessHandle<M> {
+    /// Sets the I/O priority of the process.
+    ///
+    /// This relies on the undocumented `ProcessIoPriority` information
+    /// class of `NtSetInformationProcess`.
+    pub fn set_io_priority(&self, priority: IoPriority) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let mut raw = priority.to_raw();
+        let status = unsafe {
+            ntdll::NtSetInformationProcess(
+                self.inner.as_ptr(),
+                ntdll::ProcessIoPriority,
+                (&mut raw as *mut ULONG).cast(),
+                mem::size_of::<ULONG>() as ULONG,
+            )
+        };
+        ntdll::check(status)
+    }
+}
+
+#[cfg(feature = "ntdll")]
+impl<M: HasThreadQueryInformation> ThreadHandle<M> {
+    /// Returns the I/O priority of the thread.
+    ///
+    /// This relies on the undocumented `ThreadIoPriority` information class
+    /// of `NtQueryInformationThread`.
+    pub fn io_priority(&self) -> Result<IoPriority, Error> {
+        let () = M::ASSERT;
+        let mut raw: ULONG = 0;
+        let status = unsafe {
+            ntdll::NtQueryInformationThread(
+                self.inner.as_ptr(),
+                ntdll::ThreadIoPriority,
+                (&mut raw as *mut ULONG).cast(),
+                mem::size_of::<ULONG>() as ULONG,
+                ptr::null_mut(),
+            )
+        };
+        ntdll::check(status)?;
+        Ok(IoPriority::from_raw(raw))
+    }
+}
+
+#[cfg(feature = "ntdll")]
+impl<M: HasThreadSetInformation> ThreadHandle<M> {
+    /// Sets the I/O priority of the thread, which overrides that of its
+    /// process for the I/O requests issued by the thread.
+    ///
+    /// This relies on the undocumented `ThreadIoPriority` information class
+    /// of `NtSetInformationThread`.
+    pub fn set_io_priority(&self, priority: IoPriority) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let mut raw = priority.to_raw();
+        let status = unsafe {
+            ntdll::NtSetInformationThread(
+                self.inner.as_ptr(),
+                ntdll::ThreadIoPriority,
+                (&mut raw as *mut ULONG).cast(),
+                mem::size_of::<ULONG>() as ULONG,
+            )
+        };
+        ntdll::check(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{open_process, open_thread};
+    use std::process::{Child, Command, Stdio};
+    use winapi::um::{
+        processthreadsapi::GetCurrentThreadId,
+        winnt::{
+            PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION,
+            PROCESS_SET_INFORMATION, THREAD_QUERY_INFORMATION,
+            THREAD_QUERY_LIMITED_INFORMATION, THREAD_SET_INFORMATION,
+            THREAD_SET_LIMITED_INFORMATION,
+        },
+    };
+
+    /// Spawns a child whose priorities can be changed without affecting
+    /// the other tests, which share the test process.
+    fn spawn_child() -> Child {
+        Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .stdin(Stdio::null())
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn thread_priority_round_trip() {
+        let thread =
+            open_thread::<
+                crate::access_rights!(
+                    THREAD_QUERY_LIMITED_INFORMATION,
+                    THREAD_SET_LIMITED_INFORMATION
+                ),
+            >(PhantomData, false, unsafe { GetCurrentThreadId() })
+            .unwrap();
+        let original = thread.priority().unwrap();
+        thread.set_priority(ThreadPriority::BelowNormal).unwrap();
+        assert_eq!(thread.priority().unwrap(), ThreadPriority::BelowNormal);
+        thread.set_priority(original).unwrap();
+    }
+
+    #[test]
+    fn memory_priority_round_trip() {
+        let mut child = spawn_child();
+        let process = open_process::<
+            crate::access_rights!(
+                PROCESS_QUERY_INFORMATION,
+                PROCESS_SET_INFORMATION
+            ),
+        >(PhantomData, false, &child);
+        let priority = process.and_then(|process| {
+            process.set_memory_priority(MemoryPriority::VeryLow)?;
+            process.memory_priority()
+        });
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(priority.unwrap(), MemoryPriority::VeryLow);
+    }
+
+    #[test]
+    fn page_priority_round_trip() {
+        let thread =
+            open_thread::<
+                crate::access_rights!(
+                    THREAD_QUERY_INFORMATION,
+                    THREAD_SET_INFORMATION
+                ),
+            >(PhantomData, false, unsafe { GetCurrentThreadId() })
+            .unwrap();
+        let original = thread.page_priority().unwrap();
+        thread.set_page_priority(MemoryPriority::Low).unwrap();
+        assert_eq!(thread.page_priority().unwrap(), MemoryPriority::Low);
+        thread.set_page_priority(original).unwrap();
+    }
+
+    #[test]
+    fn priority_class_round_trip() {
+        let mut child = spawn_child();
+        let process = open_process::<
+            crate::access_rights!(
+                PROCESS_QUERY_LIMITED_INFORMATION,
+                PROCESS_SET_INFORMATION
+            ),
+        >(PhantomData, false, &child);
+        let class = process.and_then(|process| {
+            process.set_priority_class(PriorityClass::BelowNormal)?;
+            process.priority_class()
+        });
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(class.unwrap(), PriorityClass::BelowNormal);
+    }
+
+    #[test]
+    fn thread_background_mode_cannot_nest() {
+        let guard = begin_thread_background_mode().unwrap();
+        assert!(begin_thread_background_mode().is_err());
+        drop(guard);
+        drop(begin_thread_background_mode().unwrap());
+    }
+
+    #[cfg(feature = "ntdll")]
+    #[test]
+    fn io_priority_round_trip() {
+        let mut child = spawn_child();
+        let process = open_process::<
+            crate::access_rights!(
+                PROCESS_QUERY_INFORMATION,
+                PROCESS_SET_INFORMATION
+            ),
+        >(PhantomData, false, &child);
+        let priority = process.and_then(|process| {
+            process.set_io_priority(IoPriority::Low)?;
+            process.io_priority()
+        });
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(priority.unwrap(), IoPriority::Low);
+    }
+
+    #[cfg(feature = "ntdll")]
+    #[test]
+    fn thread_io_priority_round_trip() {
+        let thread =
+            open_thread::<
+                crate::access_rights!(
+                    THREAD_QUERY_INFORMATION,
+                    THREAD_SET_INFORMATION
+                ),
+            >(PhantomData, false, unsafe { GetCurrentThreadId() })
+            .unwrap();
+        let original = thread.io_priority().unwrap();
+        thread.set_io_priority(IoPriority::VeryLow).unwrap();
+        assert_eq!(thread.io_priority().unwrap(), IoPriority::VeryLow);
+        thread.set_io_priority(original).unwrap();
+    }
+}
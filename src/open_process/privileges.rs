@@ -0,0 +1,301 @@
+//! Enabling and disabling privileges in access tokens.
+//!
+//! Privileges such as `SeDebugPrivilege` are held by a token but usually
+//! disabled, and have to be enabled before they take effect:
+//!
+//! ```no_run
+//! use core::marker::PhantomData;
+//! use winapi::um::winnt::{TOKEN_ADJUST_PRIVILEGES, TOKEN_QUERY};
+//! use winapi_util::{
+//!     access_rights,
+//!     open_process::{current_process, privileges},
+//! };
+//!
+//! // Enable SeDebugPrivilege for the rest of the life of the process.
+//! privileges::enable_debug_privilege().unwrap();
+//!
+//! // Or only for as long as a guard is alive.
+//! let token = current_process()
+//!     .open_token::<access_rights!(TOKEN_ADJUST_PRIVILEGES, TOKEN_QUERY)>(
+//!         PhantomData,
+//!     )
+//!     .unwrap();
+//! let guard = token.privilege_guard("SeDebugPrivilege", true).unwrap();
+//! // ...
+//! drop(guard);
+//! ```
+
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem, ptr,
+};
+
+use winapi::{
+    shared::{
+        minwindef::DWORD, ntdef::LUID, winerror::ERROR_NOT_ALL_ASSIGNED,
+    },
+    um::{
+        errhandlingapi::GetLastError,
+        securitybaseapi::AdjustTokenPrivileges,
+        winbase::LookupPrivilegeValueW,
+        winnt::{
+            SE_DEBUG_NAME, SE_PRIVILEGE_ENABLED, TOKEN_ADJUST_PRIVILEGES,
+            TOKEN_PRIVILEGES, TOKEN_QUERY,
+        },
+    },
+};
+
+use super::{
+    current_process, to_wide, Error, HasTokenAdjustPrivileges, HasTokenQuery,
+    TokenHandle,
+};
+
+/// Whether a privilege was enabled before it was adjusted.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PreviousState {
+    /// The privilege was enabled.
+    Enabled,
+    /// The privilege was held but disabled.
+    Disabled,
+}
+
+impl PreviousState {
+    /// Returns true if the privilege was enabled.
+    pub fn is_enabled(&self) -> bool {
+        *self == PreviousState::Enabled
+    }
+}
+
+impl<M> TokenHandle<M>
+where
+    M: HasTokenAdjustPrivileges + HasTokenQuery,
+{
+    /// Enables or disables a privilege held by the token, given by its name
+    /// such as `SeDebugPrivilege`, and returns whether it was enabled
+    /// before.
+    ///
+    /// Privileges can only be enabled if the token holds them, so this
+    /// fails with `ERROR_NOT_ALL_ASSIGNED` for other privileges, e.g. for
+    /// `SeDebugPrivilege` in processes that are not elevated. Unknown names
+    /// fail with `ERROR_NO_SUCH_PRIVILEGE`.
+    ///
+    /// This corresponds to calling [`LookupPrivilegeValueW`] and
+    /// [`AdjustTokenPrivileges`].
+    ///
+    /// [`LookupPrivilegeValueW`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-lookupprivilegevaluew
+    /// [`AdjustTokenPrivileges`]: https://learn.microsoft.com/en-us/windows/win32/api/securitybaseapi/nf-securitybaseapi-adjusttokenprivileges
+    pub fn adjust_privilege(
+        &self,
+        name: &str,
+        enable: bool,
+    ) -> Result<PreviousState, Error> {
+        let luid = lookup_privilege(name)?;
+        self.adjust_luid(luid, enable)
+    }
+
+    /// Enables or disables a privilege held by the token like
+    /// `TokenHandle::adjust_privilege`, until the returned guard is
+    /// dropped, which restores its previous state.
+    pub fn privilege_guard(
+        &self,
+        name: &str,
+        enable: bool,
+    ) -> Result<PrivilegeGuard<'_, M>, Error> {
+        let luid = lookup_privilege(name)?;
+        let previous = self.adjust_luid(luid, enable)?;
+        Ok(PrivilegeGuard { token: self, luid, previous })
+    }
+
+    fn adjust_luid(
+        &self,
+        luid: LUID,
+        enable: bool,
+    ) -> Result<PreviousState, Error> {
+        let () = <M as HasTokenAdjustPrivileges>::ASSERT;
+        let () = <M as HasTokenQuery>::ASSERT;
+        let mut privileges: TOKEN_PRIVILEGES = unsafe { mem::zeroed() };
+        privileges.PrivilegeCount = 1;
+        privileges.Privileges[0].Luid = luid;
+        privileges.Privileges[0].Attributes =
+            if enable { SE_PRIVILEGE_ENABLED } else { 0 };
+        let mut previous: TOKEN_PRIVILEGES = unsafe { mem::zeroed() };
+        let mut len: DWORD = 0;
+        let ok = unsafe {
+            AdjustTokenPrivileges(
+                self.inner.as_ptr(),
+                0,
+                &mut privileges,
+                mem::size_of::<TOKEN_PRIVILEGES>() as DWORD,
+                &mut previous,
+                &mut len,
+            )
+        };
+        // AdjustTokenPrivileges succeeds even if the privilege is not held,
+        // in which case the last error is ERROR_NOT_ALL_ASSIGNED.
+        if ok == 0 || unsafe { GetLastError() } == ERROR_NOT_ALL_ASSIGNED {
+            return Err(Error(PhantomData));
+        }
+        // Privileges that already had the requested state are left out of
+        // the previous state.
+        let enabled = if previous.PrivilegeCount == 0 {
+            enable
+        } else {
+            previous.Privileges[0].Attributes & SE_PRIVILEGE_ENABLED != 0
+        };
+        Ok(if enabled {
+            PreviousState::Enabled
+        } else {
+            PreviousState::Disabled
+        })
+    }
+}
+
+/// An adjustment of a privilege in a token, which is undone when this is
+/// dropped.
+///
+/// See `TokenHandle::privilege_guard`.
+pub struct PrivilegeGuard<'a, M>
+where
+    M: HasTokenAdjustPrivileges + HasTokenQuery,
+{
+    token: &'a TokenHandle<M>,
+    luid: LUID,
+    previous: PreviousState,
+}
+
+impl<M> PrivilegeGuard<'_, M>
+where
+    M: HasTokenAdjustPrivileges + HasTokenQuery,
+{
+    /// Returns whether the privilege was enabled before, which is restored
+    /// when the guard is dropped.
+    pub fn previous_state(&self) -> PreviousState {
+        self.previous
+    }
+}
+
+impl<M> Drop for PrivilegeGuard<'_, M>
+where
+    M: HasTokenAdjustPrivileges + HasTokenQuery,
+{
+    fn drop(&mut self) {
+        let _ = self.token.adjust_luid(self.luid, self.previous.is_enabled());
+    }
+}
+
+impl<M> Debug for PrivilegeGuard<'_, M>
+where
+    M: HasTokenAdjustPrivileges + HasTokenQuery,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivilegeGuard")
+            .field("token", &self.token)
+            .field("luid", &(self.luid.HighPart, self.luid.LowPart))
+            .field("previous", &self.previous)
+            .finish()
+    }
+}
+
+/// Enables `SeDebugPrivilege` in the token of the current process, which
+/// allows opening any process regardless of its security descriptor, and
+/// returns whether it was enabled before.
+///
+/// Only elevated processes of administrators hold the privilege, so this
+/// fails with `ERROR_NOT_ALL_ASSIGNED` otherwise. The privilege stays
+/// enabled until it is disabled again with `TokenHandle::adjust_privilege`.
+pub fn enable_debug_privilege() -> Result<PreviousState, Error> {
+    let token = current_process().open_token::<crate::access_rights!(
+        TOKEN_ADJUST_PRIVILEGES,
+        TOKEN_QUERY
+    )>(PhantomData)?;
+    token.adjust_privilege(SE_DEBUG_NAME, true)
+}
+
+/// Returns the locally unique identifier of the privilege with the given
+/// name on the local system.
+fn lookup_privilege(name: &str) -> Result<LUID, Error> {
+    let name = to_wide(name.as_ref());
+    let mut luid: LUID = unsafe { mem::zeroed() };
+    let ok = unsafe {
+        LookupPrivilegeValueW(ptr::null(), name.as_ptr(), &mut luid)
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(luid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{
+        sealed::{Handle, IntoAccessRights},
+        ComptimeAccessRights,
+    };
+    use core::ptr::NonNull;
+    use winapi::{
+        shared::winerror::ERROR_NO_SUCH_PRIVILEGE,
+        um::{
+            securitybaseapi::DuplicateTokenEx,
+            winnt::{
+                SecurityImpersonation, TokenPrimary, HANDLE,
+                SE_CHANGE_NOTIFY_NAME, TOKEN_DUPLICATE,
+            },
+        },
+    };
+
+    type AdjustRights =
+        crate::access_rights!(TOKEN_ADJUST_PRIVILEGES, TOKEN_QUERY);
+
+    /// Duplicates the primary token of the current process, so that the
+    /// privileges of the copy can be changed without affecting the other
+    /// tests, which share the process.
+    fn duplicate_token() -> TokenHandle<AdjustRights> {
+        let primary = current_process()
+            .open_token::<ComptimeAccessRights<TOKEN_DUPLICATE>>(PhantomData)
+            .unwrap();
+        let mut duplicate: HANDLE = ptr::null_mut();
+        let ok = unsafe {
+            DuplicateTokenEx(
+                primary.inner.as_ptr(),
+                TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
+                ptr::null_mut(),
+                SecurityImpersonation,
+                TokenPrimary,
+                &mut duplicate,
+            )
+        };
+        assert_ne!(ok, 0);
+        Handle::from_opened(
+            NonNull::new(duplicate).unwrap(),
+            AdjustRights::rt_arg_to_metadata(PhantomData),
+        )
+    }
+
+    #[test]
+    fn guard_restores_privilege() {
+        // Every token holds SeChangeNotifyPrivilege, enabled by default.
+        let token = duplicate_token();
+        let original =
+            token.adjust_privilege(SE_CHANGE_NOTIFY_NAME, true).unwrap();
+        {
+            let guard =
+                token.privilege_guard(SE_CHANGE_NOTIFY_NAME, false).unwrap();
+            assert_eq!(guard.previous_state(), PreviousState::Enabled);
+            let state =
+                token.adjust_privilege(SE_CHANGE_NOTIFY_NAME, false).unwrap();
+            assert_eq!(state, PreviousState::Disabled);
+        }
+        let state = token
+            .adjust_privilege(SE_CHANGE_NOTIFY_NAME, original.is_enabled())
+            .unwrap();
+        assert_eq!(state, PreviousState::Enabled);
+
+        let err = token.adjust_privilege("SeNoSuchPrivilege", true);
+        assert_eq!(
+            err.unwrap_err().code().as_dword(),
+            ERROR_NO_SUCH_PRIVILEGE
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use core::{
+    fmt::{self, Display, Formatter},
+    marker::PhantomData,
+    mem,
+};
+use std::time::SystemTime;
+
+use winapi::{
+    shared::{
+        minwindef::DWORD,
+        winerror::{ERROR_ACCESS_DENIED, ERROR_INVALID_PARAMETER},
+    },
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        handleapi::CloseHandle,
+        minwinbase::STILL_ACTIVE,
+        processthreadsapi::{
+            GetCurrentProcessId, GetExitCodeProcess, GetProcessId,
+            OpenProcess, ProcessIdToSessionId,
+        },
+        psapi::EnumProcesses,
+        winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+    },
+};
+
+use super::{
+    open_process, Error, HandleMetadata, HasProcessQueryLimitedInformation,
+    IntoAccessRights, ProcessHandle, QueryLimitedAccess,
+};
+
+/// The identifier of a process.
+///
+/// Process identifiers are reused once a process has exited and all
+/// handles to it have been closed, so an identifier alone does not pin
+/// down a process. Open a handle to keep referring to the same process.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ProcessId(DWORD);
+
+impl ProcessId {
+    /// Returns the identifier of the current process.
+    ///
+    /// This corresponds to calling [`GetCurrentProcessId`].
+    ///
+    /// [`GetCurrentProcessId`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getcurrentprocessid
+    pub fn current() -> ProcessId {
+        ProcessId(unsafe { GetCurrentProcessId() })
+    }
+
+    /// Wraps a raw process identifier, e.g. one returned by
+    /// [`std::process::id`].
+    pub fn from_raw(pid: DWORD) -> ProcessId {
+        ProcessId(pid)
+    }
+
+    /// Returns the raw process identifier.
+    pub fn as_raw(&self) -> DWORD {
+        self.0
+    }
+
+    /// Returns true if a running process currently has this identifier.
+    ///
+    /// Processes that cannot be opened for lack of access rights, such as
+    /// protected system processes, are reported as existing. A process
+    /// that has exited but is still referenced by an open handle is not.
+    /// The System Idle Process, with identifier 0, is never reported.
+    ///
+    /// Note that the answer may be out of date as soon as it is returned.
+    ///
+    /// This corresponds to calling [`OpenProcess`] with
+    /// `PROCESS_QUERY_LIMITED_INFORMATION` and [`GetExitCodeProcess`].
+    ///
+    /// [`OpenProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-openprocess
+    /// [`GetExitCodeProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getexitcodeprocess
+    pub fn exists(&self) -> bool {
+        let handle = unsafe {
+            OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, self.0)
+        };
+        if handle.is_null() {
+            return unsafe { GetLastError() } == ERROR_ACCESS_DENIED;
+        }
+        let mut code: DWORD = 0;
+        let ok = unsafe { GetExitCodeProcess(handle, &mut code) };
+        unsafe { CloseHandle(handle) };
+        // A process that exits with STILL_ACTIVE as its exit code is
+        // indistinguishable from a running one here.
+        ok == 0 || code == STILL_ACTIVE
+    }
+
+    /// Returns the Remote Desktop Services session the process runs in.
+    ///
+    /// This corresponds to calling [`ProcessIdToSessionId`].
+    ///
+    /// [`ProcessIdToSessionId`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-processidtosessionid
+    pub fn session_id(&self) -> Result<u32, Error> {
+        let mut session: DWORD = 0;
+        if unsafe { ProcessIdToSessionId(self.0, &mut session) } == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(session)
+    }
+}
+
+impl<M: HandleMetadata> ProcessHandle<M> {
+    /// Returns the identifier of the process the handle refers to.
+    ///
+    /// This is available regardless of the access rights of the handle. It
+    /// only needs `PROCESS_QUERY_LIMITED_INFORMATION`, which is implied by
+    /// `PROCESS_QUERY_INFORMATION`, and fails if neither was granted.
+    ///
+    /// This corresponds to calling [`GetProcessId`].
+    ///
+    /// [`GetProcessId`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocessid
+    pub fn pid(&self) -> Result<ProcessId, Error> {
+        match unsafe { GetProcessId(self.inner.as_ptr()) } {
+            0 => Err(Error(PhantomData)),
+            pid => Ok(ProcessId(pid)),
+        }
+    }
+}
+
+/// Identifies a process unambiguously, even after its identifier has been
+/// reused, by pairing the identifier with the creation time of the process.
+///
+/// An identity can be stored or sent to another process, and later turned
+/// back into a handle with [`open_process_identity`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProcessIdentity {
+    pid: ProcessId,
+    creation_time: SystemTime,
+}
+
+impl ProcessIdentity {
+    /// Returns the identifier of the process.
+    pub fn pid(&self) -> ProcessId {
+        self.pid
+    }
+
+    /// Returns the time the process was created.
+    pub fn creation_time(&self) -> SystemTime {
+        self.creation_time
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the identity of the process the handle refers to.
+    pub fn identity(&self) -> Result<ProcessIdentity, Error> {
+        Ok(ProcessIdentity {
+            pid: self.pid()?,
+            creation_time: self.times()?.creation(),
+        })
+    }
+}
+
+/// Opens the process with the given identity, like [`open_process`].
+///
+/// If no process with the identifier exists, or if the identifier now
+/// belongs to a different process than the one the identity was taken
+/// from, then an error with code `ERROR_INVALID_PARAMETER` is returned, as
+/// [`open_process`] does for identifiers that do not exist.
+pub fn open_process_identity<R: IntoAccessRights>(
+    desired_access: R::RuntimeArgumentType,
+    inherit_handle: bool,
+    identity: &ProcessIdentity,
+) -> Result<ProcessHandle<R::AccessRightsType>, Error> {
+    // An identifier is not reused while a handle to its process is open, so
+    // the process verified through this handle is the one opened below.
+    let verifier =
+        open_process::<QueryLimitedAccess>(PhantomData, false, identity.pid)?;
+    if verifier.identity()? != *identity {
+        unsafe { SetLastError(ERROR_INVALID_PARAMETER) };
+        return Err(Error(PhantomData));
+    }
+    open_process::<R>(desired_access, inherit_handle, identity.pid)
+}
+
+/// Returns the identifiers of the processes running in the system.
+///
+/// This is much cheaper than taking a snapshot with
+/// `snapshot::Snapshot::processes`, but only returns the identifiers.
+///
+/// This corresponds to calling [`EnumProcesses`] with a buffer that grows
+/// until all identifiers fit.
+///
+/// [`EnumProcesses`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-enumprocesses
+pub fn list_process_ids() -> Result<Vec<ProcessId>, Error> {
+    let mut pids: Vec<DWORD> = vec![0; 1024];
+    loop {
+        let size = (pids.len() * mem::size_of::<DWORD>()) as DWORD;
+        let mut returned: DWORD = 0;
+        let ok =
+            unsafe { EnumProcesses(pids.as_mut_ptr(), size, &mut returned) };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        // A full buffer may have been too small to hold all identifiers.
+        if returned < size {
+            pids.truncate(returned as usize / mem::size_of::<DWORD>());
+            return Ok(pids.into_iter().map(ProcessId).collect());
+        }
+        pids.resize(pids.len() * 2, 0);
+    }
+}
+
+impl Display for ProcessId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl From<DWORD> for ProcessId {
+    fn from(pid: DWORD) -> ProcessId {
+        ProcessId(pid)
+    }
+}
+
+impl From<ProcessId> for DWORD {
+    fn from(pid: ProcessId) -> DWORD {
+        pid.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_process_exists() {
+        let pid = ProcessId::current();
+        assert_eq!(pid.as_raw(), std::process::id());
+        assert!(pid.exists());
+        assert!(!ProcessId::from_raw(0).exists());
+    }
+
+    #[test]
+    fn current_process_is_listed() {
+        let pids = list_process_ids().unwrap();
+        assert!(pids.contains(&ProcessId::current()));
+    }
+
+    #[test]
+    fn handle_knows_its_pid() {
+        let process = crate::open_process::current_process();
+        assert_eq!(process.pid().unwrap(), ProcessId::current());
+    }
+
+    #[test]
+    fn reopen_by_identity() {
+        use crate::open_process::RuntimeAccessRights;
+        use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+        let identity =
+            crate::open_process::current_process().identity().unwrap();
+        assert_eq!(identity.pid(), ProcessId::current());
+        let handle = open_process_identity::<RuntimeAccessRights>(
+            PROCESS_QUERY_LIMITED_INFORMATION,
+            false,
+            &identity,
+        )
+        .unwrap();
+        assert_eq!(handle.identity().unwrap(), identity);
+
+        let stale = ProcessIdentity {
+            pid: identity.pid(),
+            creation_time: std::time::UNIX_EPOCH,
+        };
+        let err = open_process_identity::<RuntimeAccessRights>(
+            PROCESS_QUERY_LIMITED_INFORMATION,
+            false,
+            &stale,
+        )
+        .unwrap_err();
+        assert_eq!(err.code().as_dword(), ERROR_INVALID_PARAMETER);
+    }
+
+    #[test]
+    fn exited_process_does_not_exist() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/c", "exit"])
+            .spawn()
+            .unwrap();
+        let pid = ProcessId::from_raw(child.id());
+        child.wait().unwrap();
+        // The child handle is still open, so the identifier cannot have
+        // been reused yet.
+        assert!(!pid.exists());
+    }
+}
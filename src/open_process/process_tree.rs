@@ -0,0 +1,355 @@
+//! Walking and terminating trees of processes.
+//!
+//! A [`ProcessTree`] records which process created which, as of the moment
+//! it was taken. [`kill_tree`] and [`kill_tree_with_job`] terminate a
+//! process together with everything it has started:
+//!
+//! ```no_run
+//! use winapi_util::open_process::{process_tree::kill_tree, ProcessId};
+//!
+//! kill_tree(ProcessId::from_raw(1234), 1).unwrap();
+//! ```
+
+use core::{marker::PhantomData, ptr};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use winapi::{
+    shared::{
+        minwindef::{BOOL, DWORD},
+        winerror::ERROR_INVALID_PARAMETER,
+    },
+    um::{
+        errhandlingapi::{GetLastError, SetLastError},
+        handleapi::CloseHandle,
+        jobapi::IsProcessInJob,
+        jobapi2::{
+            AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject,
+        },
+        winnt::{
+            HANDLE, JOB_OBJECT_ALL_ACCESS, PROCESS_QUERY_LIMITED_INFORMATION,
+            PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+        },
+    },
+};
+
+use super::{
+    open_process, parent::process_parents, track_raw, untrack_raw, Error,
+    ProcessHandle, ProcessId, RuntimeAccessRights,
+};
+
+/// The parent/child relations between the processes running at the moment
+/// the tree was taken.
+///
+/// The relations are based on the parent identifiers that processes keep
+/// for their lifetime. Once a parent exits, its identifier may be reused,
+/// so the tree may list a process as the child of an unrelated one.
+/// [`kill_tree`] and [`kill_tree_with_job`] rule such processes out.
+#[derive(Clone, Debug)]
+pub struct ProcessTree {
+    parents: HashMap<ProcessId, ProcessId>,
+    children: HashMap<ProcessId, Vec<ProcessId>>,
+}
+
+impl ProcessTree {
+    /// Takes a snapshot of the processes running in the system.
+    ///
+    /// This corresponds to calling [`CreateToolhelp32Snapshot`] with
+    /// `TH32CS_SNAPPROCESS`.
+    ///
+    /// [`CreateToolhelp32Snapshot`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-createtoolhelp32snapshot
+    pub fn snapshot() -> Result<ProcessTree, Error> {
+        let mut tree =
+            ProcessTree { parents: HashMap::new(), children: HashMap::new() };
+        for (pid, parent) in process_parents()? {
+            // The System Idle Process is its own parent.
+            if pid == parent {
+                continue;
+            }
+            tree.parents.insert(pid, parent);
+            tree.children.entry(parent).or_default().push(pid);
+        }
+        Ok(tree)
+    }
+
+    /// Returns the identifier of the parent of a process, or `None` if the
+    /// process was not running or has no parent.
+    pub fn parent_of(&self, pid: ProcessId) -> Option<ProcessId> {
+        self.parents.get(&pid).copied()
+    }
+
+    /// Returns the processes created by a process.
+    pub fn children_of(
+        &self,
+        pid: ProcessId,
+    ) -> impl Iterator<Item = ProcessId> + '_ {
+        self.children.get(&pid).into_iter().flatten().copied()
+    }
+
+    /// Returns the processes created by a process, the processes created
+    /// by those, and so on. Every process comes after its parent.
+    pub fn descendants_of(&self, pid: ProcessId) -> Descendants<'_> {
+        Descendants {
+            tree: self,
+            queue: self.children_of(pid).collect(),
+            seen: HashSet::from([pid]),
+        }
+    }
+}
+
+/// An iterator over the descendants of a process, returned by
+/// [`ProcessTree::descendants_of`].
+#[derive(Debug)]
+pub struct Descendants<'a> {
+    tree: &'a ProcessTree,
+    queue: VecDeque<ProcessId>,
+    // Reused identifiers can make the relations cyclic.
+    seen: HashSet<ProcessId>,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = ProcessId;
+
+    fn next(&mut self) -> Option<ProcessId> {
+        loop {
+            let pid = self.queue.pop_front()?;
+            if self.seen.insert(pid) {
+                self.queue.extend(self.tree.children_of(pid));
+                return Some(pid);
+            }
+        }
+    }
+}
+
+/// Terminates a process and all of its descendants, which exit with the
+/// given exit code.
+///
+/// The process is terminated before its descendants, so that it cannot
+/// start new ones in the meantime, but descendants may still start
+/// processes of their own before they are terminated. Use
+/// [`kill_tree_with_job`] to close that gap.
+///
+/// Processes that exit on their own in the meantime are skipped. If a
+/// descendant cannot be opened or terminated, the others are still
+/// terminated and the first error is returned.
+///
+/// This corresponds to calling [`TerminateProcess`] on every process.
+///
+/// [`TerminateProcess`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-terminateprocess
+pub fn kill_tree(root: ProcessId, exit_code: u32) -> Result<(), Error> {
+    let mut first_error = 0;
+    let processes = open_tree(root, PROCESS_TERMINATE, &mut first_error)?;
+    for process in &processes {
+        if process.terminate(exit_code).is_err() {
+            record_error(process, &mut first_error);
+        }
+    }
+    finish(first_error)
+}
+
+/// Terminates a process and all of its descendants like [`kill_tree`], by
+/// placing them in a temporary job object and terminating the job.
+///
+/// Processes started by a process in the job join the job, so descendants
+/// that are started while the tree is being terminated are terminated as
+/// well. Processes that cannot be placed in the job, e.g. because they
+/// already belong to a job that forbids nesting, are terminated directly.
+///
+/// This corresponds to calling [`AssignProcessToJobObject`] on every
+/// process and then [`TerminateJobObject`].
+///
+/// [`AssignProcessToJobObject`]: https://learn.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-assignprocesstojobobject
+/// [`TerminateJobObject`]: https://learn.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-terminatejobobject
+pub fn kill_tree_with_job(
+    root: ProcessId,
+    exit_code: u32,
+) -> Result<(), Error> {
+    let job = unsafe { CreateJobObjectW(ptr::null_mut(), ptr::null()) };
+    if job.is_null() {
+        return Err(Error(PhantomData));
+    }
+    track_raw(job, "Job", JOB_OBJECT_ALL_ACCESS);
+    let access = PROCESS_TERMINATE | PROCESS_SET_QUOTA;
+    let mut first_error = 0;
+    let mut stray = vec![];
+    // Descendants started before their parent joined the job are found by
+    // the second pass.
+    for pass in 0..2 {
+        let processes = match open_tree(root, access, &mut first_error) {
+            Ok(processes) => processes,
+            // The root exited on its own after the first pass.
+            Err(_) if pass > 0 => break,
+            Err(_) => {
+                let code = unsafe { GetLastError() };
+                untrack_raw(job);
+                unsafe {
+                    CloseHandle(job);
+                    SetLastError(code);
+                }
+                return Err(Error(PhantomData));
+            }
+        };
+        for process in processes {
+            if !assign(job, &process) {
+                stray.push(process);
+            }
+        }
+    }
+    if unsafe { TerminateJobObject(job, exit_code) } == 0 && first_error == 0 {
+        first_error = unsafe { GetLastError() };
+    }
+    untrack_raw(job);
+    unsafe { CloseHandle(job) };
+    for process in &stray {
+        if process.terminate(exit_code).is_err() {
+            record_error(process, &mut first_error);
+        }
+    }
+    finish(first_error)
+}
+
+/// Opens a process and its descendants, in the order of
+/// [`ProcessTree::descendants_of`].
+///
+/// Processes that exited in the meantime, and processes whose parent
+/// identifier was reused, are left out. The first error that occurs while
+/// opening a descendant is stored in `first_error`, while errors opening
+/// the root are returned.
+fn open_tree(
+    root: ProcessId,
+    access: DWORD,
+    first_error: &mut DWORD,
+) -> Result<Vec<ProcessHandle<RuntimeAccessRights>>, Error> {
+    let access = access | PROCESS_QUERY_LIMITED_INFORMATION;
+    let tree = ProcessTree::snapshot()?;
+    let root_handle =
+        open_process::<RuntimeAccessRights>(access, false, root)?;
+    let mut created = HashMap::from([(root, root_handle.times()?.creation())]);
+    let mut processes = vec![root_handle];
+    for pid in tree.descendants_of(root) {
+        let parent = tree.parent_of(pid).and_then(|p| created.get(&p));
+        let Some(&parent_created) = parent else {
+            continue;
+        };
+        let process =
+            match open_process::<RuntimeAccessRights>(access, false, pid) {
+                Ok(process) => process,
+                Err(_) => {
+                    let code = unsafe { GetLastError() };
+                    // OpenProcess reports identifiers that are no longer in use
+                    // this way.
+                    if code != ERROR_INVALID_PARAMETER && *first_error == 0 {
+                        *first_error = code;
+                    }
+                    continue;
+                }
+            };
+        let Ok(times) = process.times() else {
+            continue;
+        };
+        // A process created before its parent is the child of an earlier
+        // process with the same identifier.
+        if times.creation() < parent_created {
+            continue;
+        }
+        created.insert(pid, times.creation());
+        processes.push(process);
+    }
+    Ok(processes)
+}
+
+/// Adds a process to a job, unless it is already in it.
+fn assign(job: HANDLE, process: &ProcessHandle<RuntimeAccessRights>) -> bool {
+    let process = process.inner.as_ptr();
+    let mut in_job: BOOL = 0;
+    let ok = unsafe { IsProcessInJob(process, job, &mut in_job) };
+    if ok != 0 && in_job != 0 {
+        return true;
+    }
+    unsafe { AssignProcessToJobObject(job, process) != 0 }
+}
+
+/// Stores the last error in `first_error` if it is the first one, unless
+/// the process has exited in the meantime.
+fn record_error(
+    process: &ProcessHandle<RuntimeAccessRights>,
+    first_error: &mut DWORD,
+) {
+    let code = unsafe { GetLastError() };
+    if *first_error == 0 && process.is_running().unwrap_or(true) {
+        *first_error = code;
+    }
+}
+
+fn finish(first_error: DWORD) -> Result<(), Error> {
+    if first_error == 0 {
+        return Ok(());
+    }
+    unsafe { SetLastError(first_error) };
+    Err(Error(PhantomData))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{ExitStatus, WaitOutcome};
+    use std::{
+        process::{Child, Command},
+        thread,
+        time::Duration,
+    };
+    use winapi::um::winnt::SYNCHRONIZE;
+
+    /// Spawns a shell that runs a long `ping`, and returns it with a handle
+    /// to the `ping` process.
+    fn spawn_with_grandchild() -> (Child, ProcessHandle<RuntimeAccessRights>) {
+        let mut child = Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 60 127.0.0.1 >nul"])
+            .spawn()
+            .unwrap();
+        let pid = ProcessId::from_raw(child.id());
+        for _ in 0..100 {
+            let tree = ProcessTree::snapshot().unwrap();
+            // The shell may also have started a console host.
+            for grandchild in tree.children_of(pid) {
+                let handle = open_process::<RuntimeAccessRights>(
+                    SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION,
+                    false,
+                    grandchild,
+                )
+                .unwrap();
+                let path = handle.image_file_name().unwrap();
+                let name = path.file_name().unwrap().to_ascii_lowercase();
+                if name == "ping.exe" {
+                    return (child, handle);
+                }
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        child.kill().unwrap();
+        child.wait().unwrap();
+        panic!("the shell did not start ping");
+    }
+
+    fn assert_killed(
+        mut child: Child,
+        grandchild: ProcessHandle<RuntimeAccessRights>,
+    ) {
+        assert_eq!(child.wait().unwrap().code(), Some(7));
+        assert_eq!(grandchild.wait(None).unwrap(), WaitOutcome::Signaled);
+        assert_eq!(grandchild.exit_code().unwrap(), ExitStatus::Exited(7));
+    }
+
+    #[test]
+    fn kill_tree_terminates_descendants() {
+        let (child, grandchild) = spawn_with_grandchild();
+        kill_tree(ProcessId::from_raw(child.id()), 7).unwrap();
+        assert_killed(child, grandchild);
+    }
+
+    #[test]
+    fn kill_tree_with_job_terminates_descendants() {
+        let (child, grandchild) = spawn_with_grandchild();
+        kill_tree_with_job(ProcessId::from_raw(child.id()), 7).unwrap();
+        assert_killed(child, grandchild);
+    }
+}
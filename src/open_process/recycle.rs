@@ -0,0 +1,205 @@
+//! Detection of recycled handle values in debug builds.
+//!
+//! Windows reuses handle values as soon as they are closed. If some code
+//! closes a handle that a `Handle` owns, the value may be handed out again
+//! for an unrelated object, and the `Handle` then silently operates on (and
+//! eventually closes) that object instead. Likewise, adopting a raw handle
+//! that a `Handle` already owns leads to a double close.
+//!
+//! To catch these bugs, debug builds keep a duplicate of every handle owned
+//! by a `Handle`, keyed by the handle value. When a handle is closed or a
+//! raw handle is adopted, [`CompareObjectHandles`] tells whether the value
+//! still refers to the recorded object. In release builds, all of this
+//! compiles to nothing.
+//!
+//! [`CompareObjectHandles`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-compareobjecthandles
+
+#[cfg(not(debug_assertions))]
+use core::{ffi::c_void, ptr::NonNull};
+
+#[cfg(not(debug_assertions))]
+pub(super) fn track(_handle: NonNull<c_void>) {}
+
+#[cfg(not(debug_assertions))]
+pub(super) fn untrack(_handle: NonNull<c_void>) {}
+
+#[cfg(not(debug_assertions))]
+pub(super) fn check_adopt(_handle: NonNull<c_void>) {}
+
+#[cfg(debug_assertions)]
+pub(super) use self::imp::{check_adopt, track, untrack};
+
+#[cfg(debug_assertions)]
+mod imp {
+    use core::{ffi::c_void, mem, ptr::NonNull};
+    use std::{
+        collections::{btree_map::Entry, BTreeMap},
+        sync::Mutex,
+        sync::OnceLock,
+    };
+
+    use winapi::{
+        shared::minwindef::{BOOL, FARPROC},
+        um::{
+            handleapi::{CloseHandle, DuplicateHandle},
+            libloaderapi::{GetModuleHandleW, GetProcAddress},
+            processthreadsapi::GetCurrentProcess,
+            winnt::{DUPLICATE_SAME_ACCESS, HANDLE},
+        },
+    };
+
+    type CompareObjectHandlesFn =
+        unsafe extern "system" fn(first: HANDLE, second: HANDLE) -> BOOL;
+
+    const COMPARE_NAME: &[u8] = b"CompareObjectHandles\0";
+
+    /// Maps the value of every tracked handle to a duplicate of it, stored
+    /// as an integer since handles are not `Send`.
+    static LIVE: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+    /// Returns `CompareObjectHandles`, which is only available on Windows 10
+    /// and newer. Tracking is disabled on older versions.
+    fn compare_fn() -> Option<CompareObjectHandlesFn> {
+        static COMPARE: OnceLock<Option<CompareObjectHandlesFn>> =
+            OnceLock::new();
+        *COMPARE.get_or_init(|| unsafe {
+            let name = super::super::to_wide("kernelbase.dll".as_ref());
+            let module = GetModuleHandleW(name.as_ptr());
+            if module.is_null() {
+                return None;
+            }
+            let f = GetProcAddress(module, COMPARE_NAME.as_ptr().cast());
+            if f.is_null() {
+                return None;
+            }
+            Some(mem::transmute::<FARPROC, CompareObjectHandlesFn>(f))
+        })
+    }
+
+    /// Pseudo-handles such as the one returned by `GetCurrentProcess` have
+    /// negative values. They are never closed and so cannot be recycled.
+    fn is_pseudo(handle: NonNull<c_void>) -> bool {
+        (handle.as_ptr() as isize) < 0
+    }
+
+    fn same_object(
+        compare: CompareObjectHandlesFn,
+        handle: NonNull<c_void>,
+        witness: usize,
+    ) -> bool {
+        unsafe { compare(handle.as_ptr(), witness as HANDLE) != 0 }
+    }
+
+    /// Starts tracking a handle that was just opened by a `Handle`.
+    pub(in super::super) fn track(handle: NonNull<c_void>) {
+        if compare_fn().is_none() || is_pseudo(handle) {
+            return;
+        }
+        let mut witness: HANDLE = core::ptr::null_mut();
+        let ok = unsafe {
+            let current = GetCurrentProcess();
+            DuplicateHandle(
+                current,
+                handle.as_ptr(),
+                current,
+                &mut witness,
+                0,
+                0,
+                DUPLICATE_SAME_ACCESS,
+            )
+        };
+        // Not every handle can be duplicated. Such handles are not tracked.
+        if ok == 0 {
+            return;
+        }
+        // A value that is still tracked was closed behind the back of its
+        // owner. The old witness is kept as evidence for that owner.
+        let reused = {
+            let mut live = LIVE.lock().unwrap_or_else(|e| e.into_inner());
+            match live.entry(handle.as_ptr() as usize) {
+                Entry::Occupied(_) => true,
+                Entry::Vacant(entry) => {
+                    entry.insert(witness as usize);
+                    false
+                }
+            }
+        };
+        if reused {
+            unsafe { CloseHandle(witness) };
+            panic!(
+                "handle value {:p} reused while still owned by another \
+                 handle, which must have been closed behind its back",
+                handle.as_ptr(),
+            );
+        }
+    }
+
+    /// Stops tracking a handle that a `Handle` is about to close, asserting
+    /// that the handle value still refers to the object it was opened for.
+    pub(in super::super) fn untrack(handle: NonNull<c_void>) {
+        let Some(compare) = compare_fn() else { return };
+        let witness = LIVE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(handle.as_ptr() as usize));
+        let Some(witness) = witness else { return };
+        let same = same_object(compare, handle, witness);
+        unsafe { CloseHandle(witness as HANDLE) };
+        assert!(
+            same,
+            "handle {:p} was closed behind the back of its owner and the \
+             value was reused for another object",
+            handle.as_ptr(),
+        );
+    }
+
+    /// Checks a raw handle that is about to be adopted by a `Handle`.
+    pub(in super::super) fn check_adopt(handle: NonNull<c_void>) {
+        let Some(compare) = compare_fn() else { return };
+        let witness = LIVE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(handle.as_ptr() as usize))
+            .copied();
+        if let Some(witness) = witness {
+            if same_object(compare, handle, witness) {
+                panic!(
+                    "handle {:p} is already owned by another handle and \
+                     would be closed twice",
+                    handle.as_ptr(),
+                );
+            }
+            panic!(
+                "handle {:p} was closed behind the back of its owner and \
+                 the value was reused for another object",
+                handle.as_ptr(),
+            );
+        }
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::super::{open_process, ProcessHandle, RuntimeAccessRights};
+    use winapi::um::winnt::PROCESS_QUERY_INFORMATION;
+
+    #[test]
+    #[should_panic(expected = "already owned by another handle")]
+    fn adopting_an_owned_handle_panics() {
+        let access = PROCESS_QUERY_INFORMATION;
+        let handle = open_process::<RuntimeAccessRights>(
+            access,
+            false,
+            std::process::id(),
+        )
+        .unwrap();
+        let adopted = unsafe {
+            ProcessHandle::<RuntimeAccessRights>::from_raw(
+                handle.inner.as_ptr(),
+                access,
+            )
+        };
+        // Not reached, but never close the handle twice.
+        core::mem::forget(adopted);
+    }
+}
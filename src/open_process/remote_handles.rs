@@ -0,0 +1,352 @@
+use core::{mem, ptr, slice, time::Duration};
+use std::{
+    collections::HashMap, ffi::OsString, os::windows::ffi::OsStringExt,
+    sync::mpsc, thread,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE, ULONG},
+        ntdef::{HANDLE, UNICODE_STRING},
+    },
+    um::{
+        handleapi::{CloseHandle, DuplicateHandle},
+        processthreadsapi::GetCurrentProcess,
+        winnt::DUPLICATE_SAME_ACCESS,
+    },
+};
+
+use super::{
+    ntdll, Error, HasProcessDupHandle, HasProcessQueryLimitedInformation,
+    ProcessHandle,
+};
+
+/// The size of the buffer that the system handle table is first queried
+/// with. It grows as needed.
+const INITIAL_TABLE_SIZE: usize = 1 << 20;
+
+/// How long querying the name of a file object may take before the object
+/// is assumed to be blocked by a pending synchronous operation.
+const FILE_NAME_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A handle that is open in another process.
+///
+/// See `ProcessHandle::remote_handles`.
+#[derive(Clone, Debug)]
+pub struct RemoteHandle {
+    value: usize,
+    granted_access: DWORD,
+    type_name: Option<String>,
+    name: Option<OsString>,
+}
+
+impl RemoteHandle {
+    /// Returns the value of the handle, as seen by the process that owns
+    /// it.
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    /// Returns the access rights granted to the handle.
+    pub fn granted_access(&self) -> DWORD {
+        self.granted_access
+    }
+
+    /// Returns the type of the object the handle refers to, such as `File`,
+    /// `Key` or `Event`, or `None` if it could not be determined.
+    pub fn type_name(&self) -> Option<&str> {
+        self.type_name.as_deref()
+    }
+
+    /// Returns the name of the object the handle refers to, or `None` if
+    /// the object has no name or its name was not queried.
+    ///
+    /// Names are paths in the object manager namespace, so files are named
+    /// like `\Device\HarddiskVolume3\Windows\System32\en-US\cmd.exe.mui`
+    /// and registry keys like `\REGISTRY\MACHINE\SOFTWARE`.
+    pub fn name(&self) -> Option<&OsString> {
+        self.name.as_ref()
+    }
+}
+
+impl<M> ProcessHandle<M>
+where
+    M: HasProcessDupHandle + HasProcessQueryLimitedInformation,
+{
+    /// Returns the handles that are open in the process.
+    ///
+    /// The handles are found in the handle table of the whole system, and
+    /// each one is then duplicated into the current process to query the
+    /// type and name of its object. Handles that cannot be duplicated are
+    /// still listed, but their names are unknown, as are their types unless
+    /// another handle to an object of the same type could be duplicated.
+    ///
+    /// Querying the name of a file object blocks while a synchronous
+    /// operation on it is in progress, e.g. a read from a pipe that never
+    /// receives data. To avoid hanging, the names of files are queried on a
+    /// worker thread, and are unknown if the query takes longer than 100
+    /// milliseconds. The worker is then left blocked until the operation
+    /// completes, and a new one takes over.
+    ///
+    /// This corresponds to calling `NtQuerySystemInformation` with
+    /// `SystemExtendedHandleInformation`, and [`DuplicateHandle`] and
+    /// [`NtQueryObject`] for every handle of the process.
+    ///
+    /// [`DuplicateHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-duplicatehandle
+    /// [`NtQueryObject`]: https://learn.microsoft.com/en-us/windows/win32/api/winternl/nf-winternl-ntqueryobject
+    pub fn remote_handles(&self) -> Result<Vec<RemoteHandle>, Error> {
+        let () = <M as HasProcessDupHandle>::ASSERT;
+        let () = <M as HasProcessQueryLimitedInformation>::ASSERT;
+        let pid = self.pid()?.as_raw() as usize;
+        let table = system_handle_table()?;
+        let entries = unsafe { table_entries(&table) };
+
+        let mut type_names = HashMap::new();
+        let mut file_names: Option<FileNameWorker> = None;
+        let mut handles = Vec::new();
+        let mut type_indices = Vec::new();
+        for entry in entries.iter().filter(|e| e.UniqueProcessId == pid) {
+            let mut handle = RemoteHandle {
+                value: entry.HandleValue,
+                granted_access: entry.GrantedAccess,
+                type_name: None,
+                name: None,
+            };
+            if let Some(local) = self.duplicate_remote(entry.HandleValue) {
+                let index = entry.ObjectTypeIndex;
+                let type_name = type_names
+                    .get(&index)
+                    .cloned()
+                    .or_else(|| object_type_name(local));
+                if let Some(type_name) = &type_name {
+                    type_names.insert(index, type_name.clone());
+                }
+                if type_name.as_deref() == Some("File") {
+                    let worker =
+                        file_names.get_or_insert_with(FileNameWorker::spawn);
+                    handle.name = worker.query(local);
+                } else {
+                    handle.name = object_name(local);
+                    unsafe { CloseHandle(local) };
+                }
+                handle.type_name = type_name;
+            }
+            handles.push(handle);
+            type_indices.push(entry.ObjectTypeIndex);
+        }
+        for (handle, index) in handles.iter_mut().zip(type_indices) {
+            if handle.type_name.is_none() {
+                handle.type_name = type_names.get(&index).cloned();
+            }
+        }
+        Ok(handles)
+    }
+
+    /// Duplicates a handle of the process into the current process.
+    fn duplicate_remote(&self, value: usize) -> Option<HANDLE> {
+        let mut local: HANDLE = ptr::null_mut();
+        let ok = unsafe {
+            DuplicateHandle(
+                self.inner.as_ptr(),
+                value as HANDLE,
+                GetCurrentProcess(),
+                &mut local,
+                0,
+                FALSE,
+                DUPLICATE_SAME_ACCESS,
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        Some(local)
+    }
+}
+
+/// A thread that queries the names of file objects, which may block.
+struct FileNameWorker {
+    /// Sends duplicated handles, as integers since handles are not `Send`.
+    requests: mpsc::Sender<usize>,
+    replies: mpsc::Receiver<Option<OsString>>,
+}
+
+impl FileNameWorker {
+    fn spawn() -> FileNameWorker {
+        let (requests, pending) = mpsc::channel::<usize>();
+        let (reply, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for handle in pending {
+                let name = object_name(handle as HANDLE);
+                unsafe { CloseHandle(handle as HANDLE) };
+                if reply.send(name).is_err() {
+                    break;
+                }
+            }
+        });
+        FileNameWorker { requests, replies }
+    }
+
+    /// Returns the name of the object of a handle, and closes the handle.
+    ///
+    /// If the query times out, the worker is abandoned together with the
+    /// handle, which it closes once the query returns, and replaced.
+    fn query(&mut self, handle: HANDLE) -> Option<OsString> {
+        if self.requests.send(handle as usize).is_err() {
+            unsafe { CloseHandle(handle) };
+            return None;
+        }
+        match self.replies.recv_timeout(FILE_NAME_TIMEOUT) {
+            Ok(name) => name,
+            Err(_) => {
+                *self = FileNameWorker::spawn();
+                None
+            }
+        }
+    }
+}
+
+/// Returns the handle table of the whole system, starting with a
+/// `SYSTEM_HANDLE_INFORMATION_EX` header. The buffer is made of `usize`s
+/// to align the entries.
+fn system_handle_table() -> Result<Vec<usize>, Error> {
+    let unit = mem::size_of::<usize>();
+    let mut table: Vec<usize> = vec![0; INITIAL_TABLE_SIZE / unit];
+    loop {
+        let mut needed: ULONG = 0;
+        let status = unsafe {
+            ntdll::NtQuerySystemInformation(
+                ntdll::SystemExtendedHandleInformation,
+                table.as_mut_ptr().cast(),
+                (table.len() * unit) as ULONG,
+                &mut needed,
+            )
+        };
+        if status == ntdll::STATUS_INFO_LENGTH_MISMATCH {
+            // Leave room for handles that are opened in the meantime.
+            let len = (needed as usize).max(table.len() * unit) * 3 / 2;
+            table.resize(len / unit, 0);
+            continue;
+        }
+        ntdll::check(status)?;
+        return Ok(table);
+    }
+}
+
+/// Returns the entries of a table returned by `system_handle_table`.
+///
+/// # Safety
+///
+/// The table must have been filled in by `NtQuerySystemInformation`.
+unsafe fn table_entries(
+    table: &[usize],
+) -> &[ntdll::SYSTEM_HANDLE_TABLE_ENTRY_INFO_EX] {
+    let header = table.as_ptr() as *const ntdll::SYSTEM_HANDLE_INFORMATION_EX;
+    let entries =
+        header.add(1) as *const ntdll::SYSTEM_HANDLE_TABLE_ENTRY_INFO_EX;
+    slice::from_raw_parts(entries, (*header).NumberOfHandles)
+}
+
+/// Queries information about an object that starts with a
+/// `UNICODE_STRING`, and returns that string, or `None` if it is empty or
+/// the query fails.
+fn query_object_string(handle: HANDLE, class: ULONG) -> Option<OsString> {
+    let unit = mem::size_of::<usize>();
+    let mut buf: Vec<usize> = vec![0; 1024 / unit];
+    loop {
+        let mut needed: ULONG = 0;
+        let status = unsafe {
+            ntdll::NtQueryObject(
+                handle,
+                class,
+                buf.as_mut_ptr().cast(),
+                (buf.len() * unit) as ULONG,
+                &mut needed,
+            )
+        };
+        if status < 0 && needed as usize > buf.len() * unit {
+            buf.resize((needed as usize + unit - 1) / unit, 0);
+            continue;
+        }
+        if status < 0 {
+            return None;
+        }
+        // The string points into the buffer, right after itself.
+        let string = unsafe { &*(buf.as_ptr() as *const UNICODE_STRING) };
+        let len = usize::from(string.Length) / mem::size_of::<u16>();
+        if len == 0 || string.Buffer.is_null() {
+            return None;
+        }
+        let wide = unsafe { slice::from_raw_parts(string.Buffer, len) };
+        return Some(OsString::from_wide(wide));
+    }
+}
+
+fn object_type_name(handle: HANDLE) -> Option<String> {
+    let name = query_object_string(handle, ntdll::ObjectTypeInformation)?;
+    Some(name.to_string_lossy().into_owned())
+}
+
+fn object_name(handle: HANDLE) -> Option<OsString> {
+    query_object_string(handle, ntdll::ObjectNameInformation)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::current_process;
+    use core::time::Duration;
+    use std::{
+        fs::File,
+        io::Read,
+        os::windows::io::AsRawHandle,
+        process::{Command, Stdio},
+    };
+
+    #[test]
+    fn lists_open_file() {
+        let file_name = format!("winapi-util-handles-{}", std::process::id());
+        let path = std::env::temp_dir().join(&file_name);
+        let file = File::create(&path).unwrap();
+        let value = file.as_raw_handle() as usize;
+        let handles = current_process().remote_handles();
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+
+        let handle = handles
+            .unwrap()
+            .into_iter()
+            .find(|handle| handle.value() == value)
+            .unwrap();
+        assert_eq!(handle.type_name(), Some("File"));
+        let name = handle.name().unwrap().to_string_lossy().into_owned();
+        assert!(name.ends_with(&file_name));
+    }
+
+    #[test]
+    fn does_not_hang_on_pipe_with_pending_read() {
+        let mut child = Command::new("cmd.exe")
+            .args(["/d", "/c", "ping -n 30 127.0.0.1 >nul"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        let value = stdout.as_raw_handle() as usize;
+        // The read blocks until the child exits, since it writes nothing.
+        let reader = std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            stdout.read_to_end(&mut buf).map(|_| buf)
+        });
+        // Give the reader time to start its read.
+        std::thread::sleep(Duration::from_millis(100));
+        let handles = current_process().remote_handles();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(reader.join().unwrap().unwrap().is_empty());
+
+        let handle = handles
+            .unwrap()
+            .into_iter()
+            .find(|handle| handle.value() == value)
+            .unwrap();
+        assert_eq!(handle.type_name(), Some("File"));
+    }
+}
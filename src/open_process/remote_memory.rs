@@ -0,0 +1,348 @@
+use core::{marker::PhantomData, mem, slice};
+use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+use winapi::{
+    shared::winerror::ERROR_PARTIAL_COPY,
+    um::{
+        errhandlingapi::GetLastError,
+        memoryapi::{ReadProcessMemory, WriteProcessMemory},
+        processthreadsapi::FlushInstructionCache,
+    },
+};
+
+use super::{
+    memory::{read, read_terminated},
+    Error, HasProcessVmOperation, HasProcessVmRead, HasProcessVmWrite,
+    ProcessHandle,
+};
+
+/// Types that can be copied to and from the memory of another process.
+///
+/// # Safety
+///
+/// Any bit pattern must be a valid value of the type, and the type must not
+/// contain padding, pointers to memory of the current process, or other
+/// data that is meaningless in another process. This holds for integers,
+/// floating-point numbers, arrays of such types, and `#[repr(C)]` structs
+/// made only of such types without padding between or after the fields.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_pod!(f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+impl<M: HasProcessVmRead> ProcessHandle<M> {
+    /// Reads a value of type `T` from the memory of the process.
+    ///
+    /// This fails unless the whole value can be read. Addresses are those
+    /// of the process, so they can be found e.g. with
+    /// `ProcessHandle::memory_regions` or `ProcessHandle::modules`.
+    ///
+    /// This corresponds to calling [`ReadProcessMemory`].
+    ///
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn read_memory<T: Pod>(&self, address: usize) -> Result<T, Error> {
+        let () = M::ASSERT;
+        unsafe { read(self.inner.as_ptr(), address) }
+    }
+
+    /// Reads bytes from the memory of the process into a buffer, and
+    /// returns how many bytes were read.
+    ///
+    /// If the range crosses into memory that cannot be read, the bytes
+    /// before it may still be read, in which case their number is returned.
+    /// Whether they are depends on the size of the read and the version of
+    /// Windows. If no byte is read, an error is returned, usually with code
+    /// `ERROR_PARTIAL_COPY` or `ERROR_NOACCESS`.
+    ///
+    /// This corresponds to calling [`ReadProcessMemory`].
+    ///
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn read_memory_slice(
+        &self,
+        address: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        let () = M::ASSERT;
+        let mut read = 0;
+        let ok = unsafe {
+            ReadProcessMemory(
+                self.inner.as_ptr(),
+                address as *const _,
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                &mut read,
+            )
+        };
+        if ok == 0
+            && (read == 0 || unsafe { GetLastError() } != ERROR_PARTIAL_COPY)
+        {
+            return Err(Error(PhantomData));
+        }
+        Ok(read)
+    }
+
+    /// Reads a NUL-terminated string of bytes, such as an ANSI or UTF-8
+    /// string, from the memory of the process, and returns it without the
+    /// terminator.
+    ///
+    /// At most `max_len` bytes are read, so the string is truncated if it is
+    /// longer. The string is read in chunks that do not cross page
+    /// boundaries, so that it may end right before memory that cannot be
+    /// read. If the string runs into such memory before it ends, an error
+    /// with code `ERROR_PARTIAL_COPY` is returned.
+    ///
+    /// This corresponds to calling [`ReadProcessMemory`] once per page.
+    ///
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn read_cstring(
+        &self,
+        address: usize,
+        max_len: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let () = M::ASSERT;
+        read_terminated(self.inner.as_ptr(), address, 1, max_len)
+    }
+
+    /// Reads a NUL-terminated UTF-16 string from the memory of the process,
+    /// and returns it without the terminator.
+    ///
+    /// At most `max_len` code units are read, so the string is truncated if
+    /// it is longer. Otherwise, this behaves like
+    /// `ProcessHandle::read_cstring`.
+    ///
+    /// This corresponds to calling [`ReadProcessMemory`] once per page.
+    ///
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn read_wide_string(
+        &self,
+        address: usize,
+        max_len: usize,
+    ) -> Result<OsString, Error> {
+        let () = M::ASSERT;
+        let unit = mem::size_of::<u16>();
+        let bytes =
+            read_terminated(self.inner.as_ptr(), address, unit, max_len)?;
+        let wide: Vec<u16> = bytes
+            .chunks_exact(unit)
+            .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+            .collect();
+        Ok(OsString::from_wide(&wide))
+    }
+}
+
+impl<M> ProcessHandle<M>
+where
+    M: HasProcessVmWrite + HasProcessVmOperation,
+{
+    /// Writes a value of type `T` to the memory of the process, and returns
+    /// how many bytes were written.
+    ///
+    /// This behaves like `ProcessHandle::write_memory_slice` with the bytes
+    /// of the value, so `code` flushes the instruction cache for them.
+    ///
+    /// This corresponds to calling [`WriteProcessMemory`], and
+    /// [`FlushInstructionCache`] if `code` is true.
+    ///
+    /// [`WriteProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-writeprocessmemory
+    /// [`FlushInstructionCache`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-flushinstructioncache
+    pub fn write_memory<T: Pod>(
+        &self,
+        address: usize,
+        value: &T,
+        code: bool,
+    ) -> Result<usize, Error> {
+        let bytes = unsafe {
+            slice::from_raw_parts(
+                (value as *const T).cast::<u8>(),
+                mem::size_of::<T>(),
+            )
+        };
+        self.write_memory_slice(address, bytes, code)
+    }
+
+    /// Writes bytes to the memory of the process, and returns how many
+    /// bytes were written.
+    ///
+    /// Pages that are read-only, such as those of code, are made writable
+    /// for the duration of the write. If the range crosses into memory that
+    /// cannot be written, the bytes before it may still be written, in
+    /// which case their number is returned. If no byte is written, an error
+    /// is returned.
+    ///
+    /// If `code` is true, the bytes are machine code, and the instruction
+    /// cache of the process is flushed for the written range so that the
+    /// new code is executed. An error is returned if the flush fails, even
+    /// though the bytes have been written.
+    ///
+    /// This corresponds to calling [`WriteProcessMemory`], and
+    /// [`FlushInstructionCache`] if `code` is true.
+    ///
+    /// [`WriteProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-writeprocessmemory
+    /// [`FlushInstructionCache`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-flushinstructioncache
+    pub fn write_memory_slice(
+        &self,
+        address: usize,
+        data: &[u8],
+        code: bool,
+    ) -> Result<usize, Error> {
+        let () = <M as HasProcessVmWrite>::ASSERT;
+        let () = <M as HasProcessVmOperation>::ASSERT;
+        let process = self.inner.as_ptr();
+        let mut written = 0;
+        let ok = unsafe {
+            WriteProcessMemory(
+                process,
+                address as *mut _,
+                data.as_ptr().cast(),
+                data.len(),
+                &mut written,
+            )
+        };
+        if ok == 0
+            && (written == 0
+                || unsafe { GetLastError() } != ERROR_PARTIAL_COPY)
+        {
+            return Err(Error(PhantomData));
+        }
+        if code {
+            let flushed = unsafe {
+                FlushInstructionCache(process, address as *const _, written)
+            };
+            if flushed == 0 {
+                return Err(Error(PhantomData));
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::current_process;
+    use core::ptr;
+    use winapi::um::{
+        memoryapi::{VirtualAlloc, VirtualFree},
+        winnt::{
+            MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE_READ,
+            PAGE_NOACCESS, PAGE_READWRITE,
+        },
+    };
+
+    #[test]
+    fn reads_values() {
+        let value: [u32; 3] = [1, 2, 0xdead_beef];
+        let process = current_process();
+        let address = value.as_ptr() as usize;
+        assert_eq!(process.read_memory::<[u32; 3]>(address).unwrap(), value);
+        assert_eq!(
+            process.read_memory::<u32>(address + 8).unwrap(),
+            0xdead_beef
+        );
+    }
+
+    #[test]
+    fn reports_partial_reads() {
+        // Commit the first page of two, so that reads stop at the second.
+        let base = unsafe {
+            VirtualAlloc(ptr::null_mut(), 2 * 4096, MEM_RESERVE, PAGE_NOACCESS)
+        };
+        assert!(!base.is_null());
+        unsafe { VirtualAlloc(base, 4096, MEM_COMMIT, PAGE_READWRITE) };
+        let process = current_process();
+        let mut buf = [0xffu8; 32];
+        let full = process.read_memory_slice(base as usize, &mut buf);
+        let partial =
+            process.read_memory_slice(base as usize + 4080, &mut buf);
+        let none = process.read_memory_slice(base as usize + 4096, &mut buf);
+        unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+
+        assert_eq!(full.unwrap(), buf.len());
+        if let Ok(read) = partial {
+            assert!(read <= 16);
+        }
+        assert!(none.is_err());
+    }
+
+    #[test]
+    fn reads_strings_up_to_page_end() {
+        // Put the strings at the end of a page that is followed by an
+        // inaccessible one.
+        let base = unsafe {
+            VirtualAlloc(ptr::null_mut(), 2 * 4096, MEM_RESERVE, PAGE_NOACCESS)
+        };
+        assert!(!base.is_null());
+        unsafe { VirtualAlloc(base, 4096, MEM_COMMIT, PAGE_READWRITE) };
+        let end = base as usize + 4096;
+        let wide: Vec<u16> = "héllo\0".encode_utf16().collect();
+        unsafe {
+            ptr::copy_nonoverlapping(
+                [b'a', b'b', b'c', 0].as_ptr(),
+                (end - 16) as _,
+                4,
+            );
+            ptr::copy_nonoverlapping(b"xyz".as_ptr(), (end - 3) as _, 3);
+            ptr::copy_nonoverlapping(wide.as_ptr(), (end - 12) as _, 6);
+        }
+        let process = current_process();
+        let c = process.read_cstring(end - 16, 100);
+        let truncated = process.read_cstring(end - 16, 2);
+        let unterminated = process.read_cstring(end - 3, 100);
+        let w = process.read_wide_string(end - 12, 100);
+        unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+
+        assert_eq!(c.unwrap(), b"abc");
+        assert_eq!(truncated.unwrap(), b"ab");
+        assert!(unterminated.is_err());
+        assert_eq!(w.unwrap(), "héllo");
+    }
+
+    #[test]
+    fn writes_values() {
+        let mut value = [0u32; 3];
+        let process = current_process();
+        let address = value.as_mut_ptr() as usize;
+        assert_eq!(
+            process
+                .write_memory(address + 4, &0xdead_beef_u32, false)
+                .unwrap(),
+            4
+        );
+        assert_eq!(
+            process.write_memory_slice(address, &[1, 2], false).unwrap(),
+            2
+        );
+        assert_eq!(
+            process.read_memory::<[u32; 3]>(address).unwrap(),
+            [0x0201, 0xdead_beef, 0]
+        );
+        assert_eq!(unsafe { ptr::read_volatile(&value) }[1], 0xdead_beef);
+    }
+
+    #[test]
+    fn writes_code() {
+        let base = unsafe {
+            VirtualAlloc(
+                ptr::null_mut(),
+                4096,
+                MEM_COMMIT | MEM_RESERVE,
+                PAGE_EXECUTE_READ,
+            )
+        };
+        assert!(!base.is_null());
+        let process = current_process();
+        let ret = [0xc3];
+        let result = process.write_memory_slice(base as usize, &ret, true);
+        let read = process.read_memory::<u8>(base as usize);
+        unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(read.unwrap(), 0xc3);
+    }
+}
@@ -0,0 +1,227 @@
+use core::{
+    ffi::c_void,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem,
+    ptr::{self, NonNull},
+    time::Duration,
+};
+
+use winapi::{
+    shared::{minwindef::DWORD, winerror::WAIT_TIMEOUT},
+    um::{
+        handleapi::CloseHandle,
+        minwinbase::{LPTHREAD_START_ROUTINE, STILL_ACTIVE},
+        processthreadsapi::{
+            CreateRemoteThread, GetExitCodeThread, ResumeThread,
+        },
+        synchapi::WaitForSingleObject,
+        winbase::{WAIT_ABANDONED, WAIT_OBJECT_0},
+        winnt::{HANDLE, THREAD_ALL_ACCESS},
+    },
+};
+
+use super::{track_raw, untrack_raw};
+use super::{
+    wait::timeout_to_millis, Error, ExitStatus, HasProcessCreateThread,
+    HasProcessQueryInformation, HasProcessVmOperation, HasProcessVmRead,
+    HasProcessVmWrite, ProcessHandle, WaitOutcome, Waitable,
+};
+
+/// A thread created in another process, which is closed when this is
+/// dropped.
+///
+/// Closing the handle does not affect the thread. See
+/// `ProcessHandle::create_remote_thread`.
+pub struct RemoteThreadHandle {
+    handle: NonNull<c_void>,
+    thread_id: u32,
+}
+
+impl RemoteThreadHandle {
+    /// Returns the identifier of the thread.
+    pub fn thread_id(&self) -> u32 {
+        self.thread_id
+    }
+
+    /// Returns the raw handle to the thread, which has all access rights.
+    pub fn as_raw(&self) -> HANDLE {
+        self.handle.as_ptr()
+    }
+
+    /// Blocks until the thread exits or the timeout elapses. A timeout of
+    /// `None` waits indefinitely.
+    ///
+    /// This corresponds to calling [`WaitForSingleObject`].
+    ///
+    /// [`WaitForSingleObject`]: https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject
+    pub fn wait(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<WaitOutcome, Error> {
+        let millis = timeout_to_millis(timeout);
+        match unsafe { WaitForSingleObject(self.as_raw(), millis) } {
+            WAIT_OBJECT_0 => Ok(WaitOutcome::Signaled),
+            WAIT_TIMEOUT => Ok(WaitOutcome::TimedOut),
+            WAIT_ABANDONED => Ok(WaitOutcome::Abandoned),
+            _ => Err(Error(PhantomData)),
+        }
+    }
+
+    /// Returns whether the thread is still running, and its exit code if it
+    /// is not, which is the value returned by its start routine.
+    ///
+    /// As with processes, a thread that exits with `STILL_ACTIVE` (259) is
+    /// reported as running.
+    ///
+    /// This corresponds to calling [`GetExitCodeThread`].
+    ///
+    /// [`GetExitCodeThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getexitcodethread
+    pub fn exit_code(&self) -> Result<ExitStatus, Error> {
+        let mut code: DWORD = 0;
+        if unsafe { GetExitCodeThread(self.as_raw(), &mut code) } == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(if code == STILL_ACTIVE {
+            ExitStatus::StillActive
+        } else {
+            ExitStatus::Exited(code)
+        })
+    }
+
+    /// Resumes the thread if it was created suspended, and returns its
+    /// suspend count before the call. The thread runs once the count drops
+    /// to 0.
+    ///
+    /// This corresponds to calling [`ResumeThread`].
+    ///
+    /// [`ResumeThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-resumethread
+    pub fn resume(&self) -> Result<u32, Error> {
+        match unsafe { ResumeThread(self.as_raw()) } {
+            DWORD::MAX => Err(Error(PhantomData)),
+            count => Ok(count),
+        }
+    }
+}
+
+impl Waitable for RemoteThreadHandle {
+    fn wait_handle(&self) -> HANDLE {
+        self.as_raw()
+    }
+}
+
+impl Drop for RemoteThreadHandle {
+    fn drop(&mut self) {
+        untrack_raw(self.as_raw());
+        unsafe { CloseHandle(self.as_raw()) };
+    }
+}
+
+impl Debug for RemoteThreadHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteThreadHandle")
+            .field("handle", &self.handle)
+            .field("thread_id", &self.thread_id)
+            .finish()
+    }
+}
+
+// The handle is owned and can be used from any thread.
+unsafe impl Send for RemoteThreadHandle {}
+unsafe impl Sync for RemoteThreadHandle {}
+
+impl<M> ProcessHandle<M>
+where
+    M: HasProcessCreateThread
+        + HasProcessQueryInformation
+        + HasProcessVmOperation
+        + HasProcessVmRead
+        + HasProcessVmWrite,
+{
+    /// Creates a thread in the process that starts executing at
+    /// `start_address` with `parameter` as its only argument.
+    ///
+    /// The start address must be that of a function in the process with
+    /// the signature of a [`ThreadProc`], such as `LoadLibraryW` or code
+    /// written with `ProcessHandle::write_memory_slice`. `flags` may be
+    /// `CREATE_SUSPENDED` to create the thread suspended until
+    /// [`RemoteThreadHandle::resume`] is called, and
+    /// `STACK_SIZE_PARAM_IS_A_RESERVATION`, which has no effect since the
+    /// default stack size is used.
+    ///
+    /// This corresponds to calling [`CreateRemoteThread`].
+    ///
+    /// # Safety
+    ///
+    /// Running arbitrary code in the process can corrupt it, so the caller
+    /// must make sure that the function at `start_address` can handle
+    /// `parameter`.
+    ///
+    /// [`ThreadProc`]: https://learn.microsoft.com/en-us/previous-versions/windows/desktop/legacy/ms686736(v=vs.85)
+    /// [`CreateRemoteThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-createremotethread
+    pub unsafe fn create_remote_thread(
+        &self,
+        start_address: usize,
+        parameter: usize,
+        flags: DWORD,
+    ) -> Result<RemoteThreadHandle, Error> {
+        let () = <M as HasProcessCreateThread>::ASSERT;
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmOperation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        let () = <M as HasProcessVmWrite>::ASSERT;
+        let start: LPTHREAD_START_ROUTINE = mem::transmute(start_address);
+        let mut thread_id: DWORD = 0;
+        let handle = CreateRemoteThread(
+            self.inner.as_ptr(),
+            ptr::null_mut(),
+            0,
+            start,
+            parameter as *mut _,
+            flags,
+            &mut thread_id,
+        );
+        match NonNull::new(handle) {
+            Some(handle) => {
+                track_raw(
+                    handle.as_ptr(),
+                    "RemoteThreadHandle",
+                    THREAD_ALL_ACCESS,
+                );
+                Ok(RemoteThreadHandle { handle, thread_id })
+            }
+            None => Err(Error(PhantomData)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+    use winapi::{shared::minwindef::LPVOID, um::winbase::CREATE_SUSPENDED};
+
+    unsafe extern "system" fn echo(parameter: LPVOID) -> DWORD {
+        parameter as usize as DWORD
+    }
+
+    #[test]
+    fn runs_suspended_thread() {
+        let process = current_process();
+        let thread = unsafe {
+            process.create_remote_thread(
+                echo as *const () as usize,
+                42,
+                CREATE_SUSPENDED,
+            )
+        }
+        .unwrap();
+        let short = Some(Duration::from_millis(10));
+        assert_eq!(thread.wait(short).unwrap(), WaitOutcome::TimedOut);
+        assert_eq!(thread.exit_code().unwrap(), ExitStatus::StillActive);
+        assert_eq!(thread.resume().unwrap(), 1);
+        assert_eq!(thread.wait(None).unwrap(), WaitOutcome::Signaled);
+        assert_eq!(thread.exit_code().unwrap(), ExitStatus::Exited(42));
+        assert_ne!(thread.thread_id(), 0);
+    }
+}
@@ -0,0 +1,261 @@
+use winapi::{
+    shared::minwindef::DWORD,
+    um::winnt::{
+        PROCESS_CREATE_THREAD, PROCESS_DUP_HANDLE, PROCESS_QUERY_INFORMATION,
+        PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_INFORMATION,
+        PROCESS_SET_LIMITED_INFORMATION, PROCESS_SET_QUOTA,
+        PROCESS_SUSPEND_RESUME, PROCESS_TERMINATE, PROCESS_VM_OPERATION,
+        PROCESS_VM_READ, PROCESS_VM_WRITE, SYNCHRONIZE, THREAD_GET_CONTEXT,
+        THREAD_QUERY_INFORMATION, THREAD_QUERY_LIMITED_INFORMATION,
+        THREAD_SET_CONTEXT, THREAD_SET_INFORMATION,
+        THREAD_SET_LIMITED_INFORMATION, THREAD_SUSPEND_RESUME,
+        THREAD_TERMINATE, TOKEN_ADJUST_DEFAULT, TOKEN_ADJUST_GROUPS,
+        TOKEN_ADJUST_PRIVILEGES, TOKEN_ADJUST_SESSIONID, TOKEN_ASSIGN_PRIMARY,
+        TOKEN_DUPLICATE, TOKEN_IMPERSONATE, TOKEN_QUERY, TOKEN_QUERY_SOURCE,
+    },
+};
+
+use super::{ComptimeAccessRights, HandleMetadata, RuntimeAccessRights};
+
+macro_rules! access_right_marker {
+    ($(#[$doc:meta])* $name:ident, $right:ident, $accepted:expr) => {
+        $(#[$doc])*
+        ///
+        /// This is implemented for [`RuntimeAccessRights`], in which case
+        /// the rights are only checked by Windows when the method is called,
+        /// and for every [`ComptimeAccessRights`]. For the latter, calling a
+        /// method that requires this trait on a handle whose rights lack
+        #[doc = concat!("`", stringify!($right), "`")]
+        /// fails to compile. Since the check happens during code generation,
+        /// the error is reported by `cargo build` but not by `cargo check`.
+        pub trait $name: HandleMetadata {
+            /// Evaluating this constant fails at compile time if the access
+            /// rights do not include the required one.
+            #[doc(hidden)]
+            const ASSERT: ();
+        }
+
+        impl $name for RuntimeAccessRights {
+            const ASSERT: () = ();
+        }
+
+        impl<const N: DWORD> $name for ComptimeAccessRights<N> {
+            const ASSERT: () = assert!(
+                N & ($accepted) != 0,
+                concat!(
+                    "the access rights of the handle do not include ",
+                    stringify!($right),
+                ),
+            );
+        }
+    };
+}
+
+access_right_marker!(
+    /// Access rights that include `PROCESS_TERMINATE`.
+    HasProcessTerminate,
+    PROCESS_TERMINATE,
+    PROCESS_TERMINATE
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_CREATE_THREAD`.
+    HasProcessCreateThread,
+    PROCESS_CREATE_THREAD,
+    PROCESS_CREATE_THREAD
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_VM_OPERATION`.
+    HasProcessVmOperation,
+    PROCESS_VM_OPERATION,
+    PROCESS_VM_OPERATION
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_VM_READ`.
+    ///
+    /// # Example
+    ///
+    /// A handle that can only query information about a process cannot be
+    /// used where reading its memory is required:
+    ///
+    /// ```compile_fail
+    /// use core::marker::PhantomData;
+    /// use winapi::um::winnt::PROCESS_QUERY_INFORMATION;
+    /// use winapi_util::open_process::{
+    ///     open_process, ComptimeAccessRights, HasProcessVmRead, ProcessHandle,
+    /// };
+    ///
+    /// fn needs_vm_read<M: HasProcessVmRead>(_: &ProcessHandle<M>) {
+    ///     let () = M::ASSERT;
+    /// }
+    ///
+    /// let handle = open_process::<
+    ///     ComptimeAccessRights<PROCESS_QUERY_INFORMATION>,
+    /// >(PhantomData, false, std::process::id())
+    /// .unwrap();
+    /// needs_vm_read(&handle);
+    /// ```
+    HasProcessVmRead,
+    PROCESS_VM_READ,
+    PROCESS_VM_READ
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_VM_WRITE`.
+    HasProcessVmWrite,
+    PROCESS_VM_WRITE,
+    PROCESS_VM_WRITE
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_DUP_HANDLE`.
+    HasProcessDupHandle,
+    PROCESS_DUP_HANDLE,
+    PROCESS_DUP_HANDLE
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_SET_INFORMATION`.
+    HasProcessSetInformation,
+    PROCESS_SET_INFORMATION,
+    PROCESS_SET_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_SET_LIMITED_INFORMATION`, which
+    /// `PROCESS_SET_INFORMATION` implies.
+    HasProcessSetLimitedInformation,
+    PROCESS_SET_LIMITED_INFORMATION,
+    PROCESS_SET_LIMITED_INFORMATION | PROCESS_SET_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_SET_QUOTA`.
+    HasProcessSetQuota,
+    PROCESS_SET_QUOTA,
+    PROCESS_SET_QUOTA
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_QUERY_INFORMATION`.
+    HasProcessQueryInformation,
+    PROCESS_QUERY_INFORMATION,
+    PROCESS_QUERY_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_QUERY_LIMITED_INFORMATION`,
+    /// which `PROCESS_QUERY_INFORMATION` implies.
+    HasProcessQueryLimitedInformation,
+    PROCESS_QUERY_LIMITED_INFORMATION,
+    PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_QUERY_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `PROCESS_SUSPEND_RESUME`.
+    HasProcessSuspendResume,
+    PROCESS_SUSPEND_RESUME,
+    PROCESS_SUSPEND_RESUME
+);
+access_right_marker!(
+    /// Access rights that include `SYNCHRONIZE`, which is required to wait
+    /// for a process to exit.
+    HasSynchronize,
+    SYNCHRONIZE,
+    SYNCHRONIZE
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_TERMINATE`.
+    HasThreadTerminate,
+    THREAD_TERMINATE,
+    THREAD_TERMINATE
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_SUSPEND_RESUME`.
+    HasThreadSuspendResume,
+    THREAD_SUSPEND_RESUME,
+    THREAD_SUSPEND_RESUME
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_GET_CONTEXT`.
+    HasThreadGetContext,
+    THREAD_GET_CONTEXT,
+    THREAD_GET_CONTEXT
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_SET_CONTEXT`.
+    HasThreadSetContext,
+    THREAD_SET_CONTEXT,
+    THREAD_SET_CONTEXT
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_QUERY_INFORMATION`.
+    HasThreadQueryInformation,
+    THREAD_QUERY_INFORMATION,
+    THREAD_QUERY_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_QUERY_LIMITED_INFORMATION`,
+    /// which `THREAD_QUERY_INFORMATION` implies.
+    HasThreadQueryLimitedInformation,
+    THREAD_QUERY_LIMITED_INFORMATION,
+    THREAD_QUERY_LIMITED_INFORMATION | THREAD_QUERY_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_SET_INFORMATION`.
+    HasThreadSetInformation,
+    THREAD_SET_INFORMATION,
+    THREAD_SET_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `THREAD_SET_LIMITED_INFORMATION`, which
+    /// `THREAD_SET_INFORMATION` implies.
+    HasThreadSetLimitedInformation,
+    THREAD_SET_LIMITED_INFORMATION,
+    THREAD_SET_LIMITED_INFORMATION | THREAD_SET_INFORMATION
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_ASSIGN_PRIMARY`.
+    HasTokenAssignPrimary,
+    TOKEN_ASSIGN_PRIMARY,
+    TOKEN_ASSIGN_PRIMARY
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_DUPLICATE`.
+    HasTokenDuplicate,
+    TOKEN_DUPLICATE,
+    TOKEN_DUPLICATE
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_IMPERSONATE`.
+    HasTokenImpersonate,
+    TOKEN_IMPERSONATE,
+    TOKEN_IMPERSONATE
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_QUERY`.
+    HasTokenQuery,
+    TOKEN_QUERY,
+    TOKEN_QUERY
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_QUERY_SOURCE`.
+    HasTokenQuerySource,
+    TOKEN_QUERY_SOURCE,
+    TOKEN_QUERY_SOURCE
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_ADJUST_PRIVILEGES`.
+    HasTokenAdjustPrivileges,
+    TOKEN_ADJUST_PRIVILEGES,
+    TOKEN_ADJUST_PRIVILEGES
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_ADJUST_GROUPS`.
+    HasTokenAdjustGroups,
+    TOKEN_ADJUST_GROUPS,
+    TOKEN_ADJUST_GROUPS
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_ADJUST_DEFAULT`.
+    HasTokenAdjustDefault,
+    TOKEN_ADJUST_DEFAULT,
+    TOKEN_ADJUST_DEFAULT
+);
+access_right_marker!(
+    /// Access rights that include `TOKEN_ADJUST_SESSIONID`.
+    HasTokenAdjustSessionId,
+    TOKEN_ADJUST_SESSIONID,
+    TOKEN_ADJUST_SESSIONID
+);
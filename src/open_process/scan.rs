@@ -0,0 +1,452 @@
+//! Searching the memory of processes for byte patterns.
+//!
+//! A [`Pattern`] is written as hexadecimal bytes separated by spaces, with
+//! `??` standing for any byte, the way signatures of code are usually
+//! given:
+//!
+//! ```no_run
+//! use core::marker::PhantomData;
+//! use winapi_util::open_process::{
+//!     open_process,
+//!     scan::{Pattern, RegionFilter},
+//!     ReadAccess,
+//! };
+//!
+//! let pattern: Pattern = "48 8B ?? ?? 05".parse().unwrap();
+//! let process =
+//!     open_process::<ReadAccess>(PhantomData, false, 1234).unwrap();
+//! for address in process.scan(&pattern, RegionFilter::executable()) {
+//!     println!("found at {:#x}", address);
+//! }
+//! ```
+
+use core::{
+    fmt::{self, Debug, Display, Formatter},
+    str::FromStr,
+};
+
+use winapi::{
+    shared::minwindef::DWORD,
+    um::winnt::{
+        PAGE_EXECUTE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE,
+        PAGE_EXECUTE_WRITECOPY, PAGE_READWRITE, PAGE_WRITECOPY,
+    },
+};
+
+use super::{
+    HasProcessQueryInformation, HasProcessVmRead, MemoryRegion, MemoryRegions,
+    MemoryType, ProcessHandle,
+};
+
+/// The number of positions that are searched per read from the process.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// A sequence of bytes to search for, some of which may match any byte.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Pattern {
+    bytes: Vec<Option<u8>>,
+    // The index of the first byte that is not a wildcard, if any, which is
+    // looked for before comparing the rest of the pattern.
+    anchor: Option<usize>,
+}
+
+impl Pattern {
+    /// Creates a pattern from bytes, where `None` matches any byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is empty.
+    pub fn new(bytes: Vec<Option<u8>>) -> Pattern {
+        assert!(!bytes.is_empty(), "empty pattern");
+        let anchor = bytes.iter().position(Option::is_some);
+        Pattern { bytes, anchor }
+    }
+
+    /// Creates a pattern that matches exactly the given bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is empty.
+    pub fn exact(bytes: &[u8]) -> Pattern {
+        Pattern::new(bytes.iter().copied().map(Some).collect())
+    }
+
+    /// Returns the bytes of the pattern, where `None` matches any byte.
+    pub fn bytes(&self) -> &[Option<u8>] {
+        &self.bytes
+    }
+
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the offset of the first match that starts within the first
+    /// `starts` bytes of `data`, searching from `from`.
+    fn find(&self, data: &[u8], from: usize, starts: usize) -> Option<usize> {
+        let Some(anchor) = self.anchor else {
+            return (from < starts).then_some(from);
+        };
+        let byte = self.bytes[anchor];
+        let candidates = data.get(from + anchor..starts + anchor)?;
+        candidates
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| Some(b) == byte)
+            .map(|(offset, _)| from + offset)
+            .find(|&start| self.matches(&data[start..]))
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        self.bytes
+            .iter()
+            .zip(data)
+            .all(|(&expected, &b)| expected.is_none() || expected == Some(b))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    /// Parses a pattern of hexadecimal bytes separated by whitespace, such
+    /// as `48 8B ?? ?? 05`. Both `?` and `??` match any byte.
+    fn from_str(s: &str) -> Result<Pattern, PatternError> {
+        let bytes = s
+            .split_whitespace()
+            .map(|token| match token {
+                "?" | "??" => Ok(None),
+                _ if token.len() == 2 => u8::from_str_radix(token, 16)
+                    .map(Some)
+                    .map_err(|_| PatternError::InvalidByte(token.to_owned())),
+                _ => Err(PatternError::InvalidByte(token.to_owned())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if bytes.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        Ok(Pattern::new(bytes))
+    }
+}
+
+/// An error returned when parsing a [`Pattern`] fails.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PatternError {
+    /// The pattern has no bytes.
+    Empty,
+    /// A byte of the pattern is neither two hexadecimal digits nor a
+    /// wildcard.
+    InvalidByte(String),
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Empty => f.write_str("the pattern is empty"),
+            PatternError::InvalidByte(token) => {
+                write!(f, "`{}` is not a byte or a wildcard", token)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Selects the memory regions that `ProcessHandle::scan` searches.
+///
+/// Only committed regions that can be read are ever searched.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RegionFilter {
+    protection: Option<DWORD>,
+    memory_type: Option<MemoryType>,
+}
+
+impl RegionFilter {
+    /// Selects all readable regions.
+    pub fn readable() -> RegionFilter {
+        RegionFilter { protection: None, memory_type: None }
+    }
+
+    /// Selects the readable regions that contain code.
+    pub fn executable() -> RegionFilter {
+        RegionFilter::readable().protection(
+            PAGE_EXECUTE
+                | PAGE_EXECUTE_READ
+                | PAGE_EXECUTE_READWRITE
+                | PAGE_EXECUTE_WRITECOPY,
+        )
+    }
+
+    /// Selects the readable regions that can be written to, where data
+    /// such as globals and heaps live.
+    pub fn writable() -> RegionFilter {
+        RegionFilter::readable().protection(
+            PAGE_READWRITE
+                | PAGE_WRITECOPY
+                | PAGE_EXECUTE_READWRITE
+                | PAGE_EXECUTE_WRITECOPY,
+        )
+    }
+
+    /// Only selects regions whose protection is one of those in `mask`, a
+    /// combination of the `PAGE_*` constants.
+    pub fn protection(self, mask: DWORD) -> RegionFilter {
+        RegionFilter { protection: Some(mask), ..self }
+    }
+
+    /// Only selects regions of the given type, e.g. those of images to
+    /// search code loaded from DLLs but not generated code.
+    pub fn memory_type(self, memory_type: MemoryType) -> RegionFilter {
+        RegionFilter { memory_type: Some(memory_type), ..self }
+    }
+
+    fn accepts(&self, region: &MemoryRegion) -> bool {
+        region.is_readable()
+            && !matches!(self.protection, Some(m) if region.protection() & m == 0)
+            && (self.memory_type.is_none()
+                || region.memory_type() == self.memory_type)
+    }
+}
+
+impl Default for RegionFilter {
+    fn default() -> RegionFilter {
+        RegionFilter::readable()
+    }
+}
+
+/// An iterator over the addresses at which a pattern occurs in the memory
+/// of a process, returned by `ProcessHandle::scan`.
+pub struct Matches<'a, M>
+where
+    M: HasProcessQueryInformation + HasProcessVmRead,
+{
+    process: &'a ProcessHandle<M>,
+    regions: MemoryRegions<'a, M>,
+    pattern: &'a Pattern,
+    filter: RegionFilter,
+    // The end of the region being searched, and the address of the next
+    // chunk of it to read.
+    region_end: usize,
+    next_chunk: usize,
+    // The chunk that was read last, the number of positions in it at which
+    // matches may start, and the next of those to search from.
+    buf: Vec<u8>,
+    buf_address: usize,
+    starts: usize,
+    from: usize,
+}
+
+impl<M> Matches<'_, M>
+where
+    M: HasProcessQueryInformation + HasProcessVmRead,
+{
+    /// Reads the next chunk, moving on to the next region if needed.
+    /// Returns false once all regions have been searched.
+    fn read_chunk(&mut self) -> bool {
+        let len = self.pattern.len();
+        loop {
+            if self.next_chunk >= self.region_end {
+                let region = loop {
+                    match self.regions.next() {
+                        Some(Ok(region)) if self.filter.accepts(&region) => {
+                            break region;
+                        }
+                        Some(Ok(_)) => continue,
+                        Some(Err(_)) | None => return false,
+                    }
+                };
+                self.next_chunk = region.base_address();
+                self.region_end = region.base_address() + region.size();
+            }
+            // Chunks overlap by one byte less than the pattern, so that
+            // matches that cross into the next chunk are found as well.
+            let start = self.next_chunk;
+            let wanted = (CHUNK_SIZE + len - 1).min(self.region_end - start);
+            if wanted < len {
+                self.next_chunk = self.region_end;
+                continue;
+            }
+            self.buf.resize(wanted, 0);
+            let read = self
+                .process
+                .read_memory_slice(start, &mut self.buf)
+                .unwrap_or(0);
+            if read < wanted {
+                // The region changed since it was queried, so skip the
+                // rest of it.
+                self.next_chunk = self.region_end;
+            }
+            if read < len {
+                continue;
+            }
+            self.buf.truncate(read);
+            self.buf_address = start;
+            self.starts = read - len + 1;
+            self.from = 0;
+            if read == wanted {
+                self.next_chunk = start + self.starts;
+            }
+            return true;
+        }
+    }
+}
+
+impl<M> Iterator for Matches<'_, M>
+where
+    M: HasProcessQueryInformation + HasProcessVmRead,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            let found = self.pattern.find(&self.buf, self.from, self.starts);
+            if let Some(offset) = found {
+                self.from = offset + 1;
+                return Some(self.buf_address + offset);
+            }
+            if !self.read_chunk() {
+                self.starts = 0;
+                return None;
+            }
+        }
+    }
+}
+
+impl<M> Debug for Matches<'_, M>
+where
+    M: HasProcessQueryInformation + HasProcessVmRead,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matches")
+            .field("pattern", self.pattern)
+            .field("filter", &self.filter)
+            .field("next_chunk", &self.next_chunk)
+            .finish()
+    }
+}
+
+impl<M> ProcessHandle<M>
+where
+    M: HasProcessQueryInformation + HasProcessVmRead,
+{
+    /// Returns the addresses at which a pattern occurs in the memory of the
+    /// process, in increasing order, searching the regions selected by
+    /// `filter`.
+    ///
+    /// The memory is read in chunks as the iterator advances, so it may
+    /// change while the search is in progress. Regions that cannot be read
+    /// are skipped, and matches that cross from one region into the next
+    /// are not found. Overlapping matches are all returned.
+    ///
+    /// This corresponds to calling [`VirtualQueryEx`] for every region and
+    /// [`ReadProcessMemory`] for every chunk of the selected ones.
+    ///
+    /// [`VirtualQueryEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualqueryex
+    /// [`ReadProcessMemory`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-readprocessmemory
+    pub fn scan<'a>(
+        &'a self,
+        pattern: &'a Pattern,
+        filter: RegionFilter,
+    ) -> Matches<'a, M> {
+        let () = <M as HasProcessQueryInformation>::ASSERT;
+        let () = <M as HasProcessVmRead>::ASSERT;
+        Matches {
+            process: self,
+            regions: self.memory_regions(),
+            pattern,
+            filter,
+            region_end: 0,
+            next_chunk: 0,
+            buf: Vec::new(),
+            buf_address: 0,
+            starts: 0,
+            from: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+    use core::ptr;
+    use winapi::um::{
+        memoryapi::{VirtualAlloc, VirtualFree},
+        winnt::{MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_READONLY},
+    };
+
+    #[test]
+    fn parses_patterns() {
+        let pattern: Pattern = "48 8b ?? ? 05".parse().unwrap();
+        assert_eq!(
+            pattern.bytes(),
+            [Some(0x48), Some(0x8b), None, None, Some(0x05)]
+        );
+        assert_eq!("".parse::<Pattern>(), Err(PatternError::Empty));
+        assert_eq!(
+            "48 8".parse::<Pattern>(),
+            Err(PatternError::InvalidByte("8".to_owned()))
+        );
+        assert_eq!(
+            "48 zz".parse::<Pattern>(),
+            Err(PatternError::InvalidByte("zz".to_owned()))
+        );
+    }
+
+    #[test]
+    fn finds_matches() {
+        let pattern: Pattern = "01 ?? 01".parse().unwrap();
+        let data = [0, 1, 1, 1, 0, 1, 2, 1];
+        let mut matches = Vec::new();
+        let mut from = 0;
+        while let Some(found) = pattern.find(&data, from, data.len() - 2) {
+            matches.push(found);
+            from = found + 1;
+        }
+        assert_eq!(matches, [1, 5]);
+    }
+
+    #[test]
+    fn scans_across_chunks() {
+        // A marker that straddles the first two chunks of a region.
+        let size = 2 * CHUNK_SIZE;
+        let base = unsafe {
+            VirtualAlloc(
+                ptr::null_mut(),
+                size,
+                MEM_COMMIT | MEM_RESERVE,
+                PAGE_READWRITE,
+            )
+        };
+        assert!(!base.is_null());
+        let marker = b"winapi-util scan marker";
+        let first = CHUNK_SIZE - 5;
+        let second = size - marker.len();
+        unsafe {
+            let base = base as *mut u8;
+            ptr::copy_nonoverlapping(
+                marker.as_ptr(),
+                base.add(first),
+                marker.len(),
+            );
+            ptr::copy_nonoverlapping(
+                marker.as_ptr(),
+                base.add(second),
+                marker.len(),
+            );
+        }
+        let process = current_process();
+        let pattern = Pattern::exact(marker);
+        let filter = RegionFilter::writable().memory_type(MemoryType::Private);
+        let found: Vec<usize> = process
+            .scan(&pattern, filter)
+            .filter(|&a| a >= base as usize && a < base as usize + size)
+            .collect();
+        let read_only: Vec<usize> = process
+            .scan(&pattern, RegionFilter::readable().protection(PAGE_READONLY))
+            .filter(|&a| a >= base as usize && a < base as usize + size)
+            .collect();
+        unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+
+        assert_eq!(found, [base as usize + first, base as usize + second]);
+        assert!(read_only.is_empty());
+    }
+}
@@ -0,0 +1,480 @@
+use core::{mem, ptr};
+use std::{
+    ffi::OsString,
+    fs::File,
+    os::windows::{ffi::OsStringExt, io::AsRawHandle},
+    path::{Path, PathBuf},
+};
+
+use winapi::{
+    shared::{
+        guiddef::GUID,
+        minwindef::{BOOL, BYTE, DWORD, LPVOID, MAX_PATH},
+        winerror::{
+            TRUST_E_NOSIGNATURE, TRUST_E_PROVIDER_UNKNOWN,
+            TRUST_E_SUBJECT_FORM_UNKNOWN,
+        },
+    },
+    um::{
+        handleapi::INVALID_HANDLE_VALUE,
+        softpub::WINTRUST_ACTION_GENERIC_VERIFY_V2,
+        wincrypt::{
+            CertCloseStore, CertFindCertificateInStore,
+            CertFreeCertificateContext, CertGetNameStringW, CryptMsgClose,
+            CryptMsgGetParam, CryptQueryObject, CERT_FIND_SUBJECT_CERT,
+            CERT_INFO, CERT_NAME_SIMPLE_DISPLAY_TYPE,
+            CERT_QUERY_CONTENT_FLAG_ALL,
+            CERT_QUERY_CONTENT_FLAG_PKCS7_SIGNED_EMBED,
+            CERT_QUERY_FORMAT_FLAG_BINARY, CERT_QUERY_OBJECT_FILE,
+            CMSG_SIGNER_INFO, CMSG_SIGNER_INFO_PARAM, HCERTSTORE, HCRYPTMSG,
+            PCCTL_CONTEXT, PKCS_7_ASN_ENCODING, X509_ASN_ENCODING,
+        },
+        winnt::{HANDLE, LPCWSTR, WCHAR},
+        wintrust::{
+            WinVerifyTrust, WINTRUST_DATA, WINTRUST_FILE_INFO,
+            WTD_CHOICE_CATALOG, WTD_CHOICE_FILE, WTD_REVOKE_NONE,
+            WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
+        },
+    },
+};
+
+use super::{
+    canonicalize_image_path, from_io_error, to_wide, Error,
+    HasProcessQueryLimitedInformation, ProcessHandle,
+};
+
+#[allow(clippy::upper_case_acronyms)]
+type HCATADMIN = HANDLE;
+#[allow(clippy::upper_case_acronyms)]
+type HCATINFO = HANDLE;
+
+/// Not declared by winapi.
+#[repr(C)]
+#[allow(non_snake_case)]
+struct CATALOG_INFO {
+    cbStruct: DWORD,
+    wszCatalogFile: [WCHAR; MAX_PATH],
+}
+
+/// Not declared by winapi, whose `WINTRUST_DATA` only has a comment in
+/// place of the `pCatalog` member of its union.
+#[repr(C)]
+#[allow(non_snake_case)]
+struct WINTRUST_CATALOG_INFO {
+    cbStruct: DWORD,
+    dwCatalogVersion: DWORD,
+    pcwszCatalogFilePath: LPCWSTR,
+    pcwszMemberTag: LPCWSTR,
+    pcwszMemberFilePath: LPCWSTR,
+    hMemberFile: HANDLE,
+    pbCalculatedFileHash: *mut BYTE,
+    cbCalculatedFileHash: DWORD,
+    pcCatalogContext: PCCTL_CONTEXT,
+    hCatAdmin: HCATADMIN,
+}
+
+// The catalog functions are not exposed by winapi.
+#[link(name = "wintrust")]
+extern "system" {
+    fn CryptCATAdminAcquireContext(
+        phCatAdmin: *mut HCATADMIN,
+        pgSubsystem: *const GUID,
+        dwFlags: DWORD,
+    ) -> BOOL;
+    fn CryptCATAdminReleaseContext(
+        hCatAdmin: HCATADMIN,
+        dwFlags: DWORD,
+    ) -> BOOL;
+    fn CryptCATAdminCalcHashFromFileHandle(
+        hFile: HANDLE,
+        pcbHash: *mut DWORD,
+        pbHash: *mut BYTE,
+        dwFlags: DWORD,
+    ) -> BOOL;
+    fn CryptCATAdminEnumCatalogFromHash(
+        hCatAdmin: HCATADMIN,
+        pbHash: *mut BYTE,
+        cbHash: DWORD,
+        dwFlags: DWORD,
+        phPrevCatInfo: *mut HCATINFO,
+    ) -> HCATINFO;
+    fn CryptCATCatalogInfoFromContext(
+        hCatInfo: HCATINFO,
+        psCatInfo: *mut CATALOG_INFO,
+        dwFlags: DWORD,
+    ) -> BOOL;
+    fn CryptCATAdminReleaseCatalogContext(
+        hCatAdmin: HCATADMIN,
+        hCatInfo: HCATINFO,
+        dwFlags: DWORD,
+    ) -> BOOL;
+}
+
+/// The result of verifying the Authenticode signature of a file.
+///
+/// See [`verify_file_signature`].
+#[derive(Clone, Debug)]
+pub struct Signature {
+    status: SignatureStatus,
+    signer: Option<OsString>,
+    catalog: Option<PathBuf>,
+}
+
+impl Signature {
+    /// Returns whether the signature is present and trusted.
+    pub fn status(&self) -> SignatureStatus {
+        self.status
+    }
+
+    /// Returns true if and only if the file carries a trusted signature.
+    pub fn is_valid(&self) -> bool {
+        self.status == SignatureStatus::Valid
+    }
+
+    /// Returns the simple display name of the signing certificate's subject,
+    /// e.g. `Microsoft Corporation`.
+    ///
+    /// For files signed through a security catalog, this is the signer of
+    /// the catalog. It is available even if the signature failed to
+    /// verify, and is `None` for unsigned files.
+    pub fn signer(&self) -> Option<&OsString> {
+        self.signer.as_ref()
+    }
+
+    /// Returns the path of the security catalog that the file is signed
+    /// through, or `None` if the signature is embedded in the file or the
+    /// file is not signed.
+    pub fn catalog(&self) -> Option<&Path> {
+        self.catalog.as_deref()
+    }
+}
+
+/// The outcome of a call to [`WinVerifyTrust`].
+///
+/// [`WinVerifyTrust`]: https://learn.microsoft.com/en-us/windows/win32/api/wintrust/nf-wintrust-winverifytrust
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignatureStatus {
+    /// The file is signed and the signature chains up to a trusted root.
+    Valid,
+    /// The file has no embedded signature, or its format cannot carry one,
+    /// and no security catalog of the system lists it.
+    NotSigned,
+    /// The file is signed but the signature is not trusted. The value is the
+    /// `HRESULT` returned by `WinVerifyTrust`, e.g. `TRUST_E_BAD_DIGEST` or
+    /// `CERT_E_EXPIRED`.
+    Invalid(i32),
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Verifies the Authenticode signature of the executable image of the
+    /// process.
+    ///
+    /// The handle must have been opened with at least
+    /// `PROCESS_QUERY_LIMITED_INFORMATION`.
+    ///
+    /// This resolves the image path and then calls [`verify_file_signature`]
+    /// on it. If the image path could not be determined or the image could
+    /// not be opened, then an error is returned.
+    pub fn verify_image_signature(&self) -> Result<Signature, Error> {
+        let image = canonicalize_image_path(self.image_file_name()?)?;
+        verify_file_signature(image)
+    }
+}
+
+/// Verifies the Authenticode signature of the file at the given path, e.g.
+/// an executable or a module loaded into a process.
+///
+/// If the file has no embedded signature, then it is looked up in the
+/// security catalogs of the system, through which most binaries shipped
+/// with Windows are signed. Revocation is not checked, since that may
+/// require network access.
+///
+/// If the file could not be opened, then an error is returned. A missing or
+/// untrusted signature is not an error, and is reported through
+/// [`Signature::status`].
+///
+/// This corresponds to calling [`WinVerifyTrust`] with
+/// `WINTRUST_ACTION_GENERIC_VERIFY_V2`, first for the file itself and
+/// then, if it is not signed, for the catalog that
+/// [`CryptCATAdminEnumCatalogFromHash`] finds for its hash.
+///
+/// [`WinVerifyTrust`]: https://learn.microsoft.com/en-us/windows/win32/api/wintrust/nf-wintrust-winverifytrust
+/// [`CryptCATAdminEnumCatalogFromHash`]: https://learn.microsoft.com/en-us/windows/win32/api/mscat/nf-mscat-cryptcatadminenumcatalogfromhash
+pub fn verify_file_signature<P: AsRef<Path>>(
+    path: P,
+) -> Result<Signature, Error> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(from_io_error)?;
+    let wide = to_wide(path.as_os_str());
+
+    let rc = unsafe {
+        let mut file_info: WINTRUST_FILE_INFO = mem::zeroed();
+        file_info.cbStruct = mem::size_of::<WINTRUST_FILE_INFO>() as DWORD;
+        file_info.pcwszFilePath = wide.as_ptr();
+        file_info.hFile = file.as_raw_handle();
+
+        let mut data: WINTRUST_DATA = mem::zeroed();
+        data.dwUnionChoice = WTD_CHOICE_FILE;
+        *data.u.pFile_mut() = &mut file_info;
+        verify_trust(&mut data)
+    };
+    let status = status_from(rc);
+    if status != SignatureStatus::NotSigned {
+        let signer =
+            signer_subject(&wide, CERT_QUERY_CONTENT_FLAG_PKCS7_SIGNED_EMBED);
+        return Ok(Signature { status, signer, catalog: None });
+    }
+
+    let Some((catalog, mut hash)) = find_catalog(&file) else {
+        return Ok(Signature { status, signer: None, catalog: None });
+    };
+    let tag = to_wide(OsString::from(hex(&hash)).as_os_str());
+    let rc = unsafe {
+        let mut catalog_info: WINTRUST_CATALOG_INFO = mem::zeroed();
+        catalog_info.cbStruct =
+            mem::size_of::<WINTRUST_CATALOG_INFO>() as DWORD;
+        catalog_info.pcwszCatalogFilePath = catalog.as_ptr();
+        catalog_info.pcwszMemberTag = tag.as_ptr();
+        catalog_info.pcwszMemberFilePath = wide.as_ptr();
+        catalog_info.hMemberFile = file.as_raw_handle();
+        catalog_info.pbCalculatedFileHash = hash.as_mut_ptr();
+        catalog_info.cbCalculatedFileHash = hash.len() as DWORD;
+
+        let mut data: WINTRUST_DATA = mem::zeroed();
+        data.dwUnionChoice = WTD_CHOICE_CATALOG;
+        // The union only declares the file member, but all of its members
+        // are pointers.
+        *data.u.pFile_mut() =
+            (&mut catalog_info as *mut WINTRUST_CATALOG_INFO).cast();
+        verify_trust(&mut data)
+    };
+    let signer = signer_subject(&catalog, CERT_QUERY_CONTENT_FLAG_ALL);
+    // Drop the NUL terminator.
+    let catalog = OsString::from_wide(&catalog[..catalog.len() - 1]);
+    Ok(Signature {
+        status: status_from(rc),
+        signer,
+        catalog: Some(catalog.into()),
+    })
+}
+
+/// Calls `WinVerifyTrust` with the given subject, which the caller has
+/// filled in, and releases the state data allocated by the call.
+///
+/// # Safety
+///
+/// The union of `data` must point to a valid subject of the kind that
+/// `dwUnionChoice` selects.
+unsafe fn verify_trust(data: &mut WINTRUST_DATA) -> i32 {
+    data.cbStruct = mem::size_of::<WINTRUST_DATA>() as DWORD;
+    data.dwUIChoice = WTD_UI_NONE;
+    data.fdwRevocationChecks = WTD_REVOKE_NONE;
+    data.dwStateAction = WTD_STATEACTION_VERIFY;
+
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+    let hwnd = INVALID_HANDLE_VALUE.cast();
+    let rc = WinVerifyTrust(hwnd, &mut action, data as *mut _ as LPVOID);
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    WinVerifyTrust(hwnd, &mut action, data as *mut _ as LPVOID);
+    rc
+}
+
+fn status_from(rc: i32) -> SignatureStatus {
+    match rc {
+        0 => SignatureStatus::Valid,
+        TRUST_E_NOSIGNATURE
+        | TRUST_E_SUBJECT_FORM_UNKNOWN
+        | TRUST_E_PROVIDER_UNKNOWN => SignatureStatus::NotSigned,
+        code => SignatureStatus::Invalid(code),
+    }
+}
+
+/// Looks up the hash of the given file in the security catalogs of the
+/// system, and returns the NUL terminated path of the first catalog that
+/// lists it along with the hash.
+///
+/// Any failure along the way is reported as `None`, in which case the file
+/// is treated as unsigned.
+fn find_catalog(file: &File) -> Option<(Vec<u16>, Vec<u8>)> {
+    let handle = file.as_raw_handle();
+    let mut size: DWORD = 0;
+    let ok = unsafe {
+        CryptCATAdminCalcHashFromFileHandle(
+            handle,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if ok == 0 && size == 0 {
+        return None;
+    }
+    let mut hash = vec![0u8; size as usize];
+    let ok = unsafe {
+        CryptCATAdminCalcHashFromFileHandle(
+            handle,
+            &mut size,
+            hash.as_mut_ptr(),
+            0,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    hash.truncate(size as usize);
+
+    let mut admin: HCATADMIN = ptr::null_mut();
+    if unsafe { CryptCATAdminAcquireContext(&mut admin, ptr::null(), 0) } == 0
+    {
+        return None;
+    }
+    let info = unsafe {
+        CryptCATAdminEnumCatalogFromHash(
+            admin,
+            hash.as_mut_ptr(),
+            hash.len() as DWORD,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    let mut catalog = None;
+    if !info.is_null() {
+        let mut catalog_info: CATALOG_INFO = unsafe { mem::zeroed() };
+        catalog_info.cbStruct = mem::size_of::<CATALOG_INFO>() as DWORD;
+        if unsafe {
+            CryptCATCatalogInfoFromContext(info, &mut catalog_info, 0)
+        } != 0
+        {
+            let name = &catalog_info.wszCatalogFile;
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            let mut path = name[..len].to_vec();
+            path.push(0);
+            catalog = Some(path);
+        }
+        unsafe { CryptCATAdminReleaseCatalogContext(admin, info, 0) };
+    }
+    unsafe { CryptCATAdminReleaseContext(admin, 0) };
+    catalog.map(|catalog| (catalog, hash))
+}
+
+/// Formats a hash as upper case hexadecimal, which is how catalogs tag
+/// their members.
+fn hex(hash: &[u8]) -> String {
+    hash.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Returns the subject of the certificate that produced the signature of
+/// the file at the given NUL terminated path, which is either embedded in
+/// the file or the file itself for a catalog, as selected by `content`.
+///
+/// Any failure along the way is reported as `None`, since the signer is
+/// purely informational.
+fn signer_subject(wide_path: &[u16], content: DWORD) -> Option<OsString> {
+    let mut store: HCERTSTORE = ptr::null_mut();
+    let mut msg: HCRYPTMSG = ptr::null_mut();
+    let ok = unsafe {
+        CryptQueryObject(
+            CERT_QUERY_OBJECT_FILE,
+            wide_path.as_ptr().cast(),
+            content,
+            CERT_QUERY_FORMAT_FLAG_BINARY,
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut store,
+            &mut msg,
+            ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    let subject = unsafe { signer_subject_from(store, msg) };
+    unsafe {
+        CryptMsgClose(msg);
+        CertCloseStore(store, 0);
+    }
+    subject
+}
+
+/// # Safety
+///
+/// `store` and `msg` must be valid handles returned by `CryptQueryObject`.
+unsafe fn signer_subject_from(
+    store: HCERTSTORE,
+    msg: HCRYPTMSG,
+) -> Option<OsString> {
+    let mut size: DWORD = 0;
+    let param = CMSG_SIGNER_INFO_PARAM;
+    if CryptMsgGetParam(msg, param, 0, ptr::null_mut(), &mut size) == 0 {
+        return None;
+    }
+    // Use a u64 buffer so that the CMSG_SIGNER_INFO at its start is
+    // suitably aligned.
+    let mut buf = vec![0u64; size as usize / 8 + 1];
+    if CryptMsgGetParam(msg, param, 0, buf.as_mut_ptr().cast(), &mut size) == 0
+    {
+        return None;
+    }
+    let signer = &*(buf.as_ptr() as *const CMSG_SIGNER_INFO);
+
+    let mut cert_info: CERT_INFO = mem::zeroed();
+    cert_info.Issuer = signer.Issuer;
+    cert_info.SerialNumber = signer.SerialNumber;
+    let cert = CertFindCertificateInStore(
+        store,
+        X509_ASN_ENCODING | PKCS_7_ASN_ENCODING,
+        0,
+        CERT_FIND_SUBJECT_CERT,
+        &cert_info as *const CERT_INFO as *const _,
+        ptr::null(),
+    );
+    if cert.is_null() {
+        return None;
+    }
+    let kind = CERT_NAME_SIMPLE_DISPLAY_TYPE;
+    let len =
+        CertGetNameStringW(cert, kind, 0, ptr::null_mut(), ptr::null_mut(), 0);
+    let mut name = vec![0u16; len as usize];
+    let len = CertGetNameStringW(
+        cert,
+        kind,
+        0,
+        ptr::null_mut(),
+        name.as_mut_ptr(),
+        len,
+    );
+    CertFreeCertificateContext(cert);
+    // The length includes the NUL terminator, so 1 means an empty name.
+    if len <= 1 {
+        return None;
+    }
+    Some(OsString::from_wide(&name[..len as usize - 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_is_not_signed() {
+        let exe = std::env::current_exe().unwrap();
+        let sig = verify_file_signature(exe).unwrap();
+        assert_eq!(sig.status(), SignatureStatus::NotSigned);
+        assert!(sig.signer().is_none());
+        assert!(sig.catalog().is_none());
+    }
+
+    #[test]
+    fn catalog_signed_system_binary_is_valid() {
+        // cmd.exe carries no embedded signature and is only signed through
+        // a catalog.
+        let sig =
+            verify_file_signature(r"C:\Windows\System32\cmd.exe").unwrap();
+        assert_eq!(sig.status(), SignatureStatus::Valid);
+        let catalog = sig.catalog().unwrap();
+        assert!(catalog.extension().unwrap().eq_ignore_ascii_case("cat"));
+        assert!(sig.signer().unwrap().to_string_lossy().contains("Microsoft"));
+    }
+}
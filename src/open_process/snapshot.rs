@@ -0,0 +1,699 @@
+//! Listing the processes and threads of the system, and the modules and
+//! heaps of a process, as of a point in time.
+//!
+//! A [`Snapshot`] is taken with `CreateToolhelp32Snapshot`. Listing the
+//! processes and threads does not require any access to them:
+//!
+//! ```no_run
+//! use winapi_util::open_process::snapshot::Snapshot;
+//!
+//! for process in Snapshot::processes().unwrap() {
+//!     println!(
+//!         "{} (parent {}): {:?} with {} threads",
+//!         process.pid(),
+//!         process.parent_id(),
+//!         process.exe_name(),
+//!         process.thread_count(),
+//!     );
+//! }
+//! ```
+//!
+//! [`find_process_by_name`] and the other `find_*` functions look for
+//! processes in a snapshot. A [`ProcessEntry`] can be passed to
+//! [`open_process`](super::open_process) to open the process it lists:
+//!
+//! ```no_run
+//! use std::marker::PhantomData;
+//! use winapi_util::open_process::{
+//!     open_process, snapshot::find_process_by_name, QueryLimitedAccess,
+//! };
+//!
+//! let explorer = find_process_by_name("explorer.exe".as_ref()).unwrap();
+//! if let Some(entry) = explorer {
+//!     let process =
+//!         open_process::<QueryLimitedAccess>(PhantomData, false, &entry)
+//!             .unwrap();
+//!     println!("{:?}", process.times().unwrap());
+//! }
+//! ```
+
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem,
+};
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStringExt,
+    path::{Path, PathBuf},
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, HMODULE},
+        winerror::ERROR_BAD_LENGTH,
+    },
+    um::{
+        errhandlingapi::GetLastError,
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        tlhelp32::{
+            CreateToolhelp32Snapshot, Heap32First, Heap32ListFirst,
+            Heap32ListNext, Heap32Next, Module32FirstW, Module32NextW,
+            Process32FirstW, Process32NextW, Thread32First, Thread32Next,
+            HEAPENTRY32, HEAPLIST32, HF32_DEFAULT, LF32_FREE, LF32_MOVEABLE,
+            MODULEENTRY32W, PROCESSENTRY32W, TH32CS_SNAPHEAPLIST,
+            TH32CS_SNAPMODULE, TH32CS_SNAPMODULE32, TH32CS_SNAPPROCESS,
+            TH32CS_SNAPTHREAD, THREADENTRY32,
+        },
+        winnt::HANDLE,
+    },
+};
+
+use super::{
+    eq_ignore_ascii_case, sealed::IntoProcessId, track_raw, untrack_raw,
+    Error, ProcessId,
+};
+
+/// How many times taking a snapshot of the modules of a process is retried
+/// while its loader data is being modified.
+const MAX_MODULE_SNAPSHOT_RETRIES: usize = 16;
+
+/// A snapshot of the processes or threads running in the system, or of
+/// the modules or heaps of a process, which is released when dropped.
+///
+/// Snapshots are taken by the functions that list their contents, such as
+/// [`Snapshot::processes`].
+pub struct Snapshot {
+    handle: HANDLE,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the given kind.
+    ///
+    /// This corresponds to calling [`CreateToolhelp32Snapshot`].
+    ///
+    /// [`CreateToolhelp32Snapshot`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-createtoolhelp32snapshot
+    fn new(flags: DWORD, pid: DWORD) -> Result<Snapshot, Error> {
+        let handle = unsafe { CreateToolhelp32Snapshot(flags, pid) };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(Error(PhantomData));
+        }
+        track_raw(handle, "Snapshot", 0);
+        Ok(Snapshot { handle })
+    }
+
+    /// Takes a snapshot of the processes running in the system, and
+    /// returns an iterator over them.
+    ///
+    /// This corresponds to calling [`CreateToolhelp32Snapshot`] with
+    /// `TH32CS_SNAPPROCESS`, and [`Process32FirstW`] and
+    /// [`Process32NextW`].
+    ///
+    /// [`CreateToolhelp32Snapshot`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-createtoolhelp32snapshot
+    /// [`Process32FirstW`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-process32firstw
+    /// [`Process32NextW`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-process32nextw
+    pub fn processes() -> Result<Processes, Error> {
+        let snapshot = Snapshot::new(TH32CS_SNAPPROCESS, 0)?;
+        Ok(Processes { snapshot, started: false })
+    }
+
+    /// Takes a snapshot of the modules loaded into a process, and returns
+    /// an iterator over them. The executable of the process comes first.
+    ///
+    /// For a 32-bit process running under WOW64, both its 32-bit modules
+    /// and the 64-bit modules that implement WOW64 are listed. A 32-bit
+    /// process cannot list the modules of a 64-bit process, in which case
+    /// an error with code `ERROR_PARTIAL_COPY` is returned. If the loader
+    /// data of the process keeps changing while the snapshot is taken, an
+    /// error with code `ERROR_BAD_LENGTH` is returned after a few attempts.
+    ///
+    /// This corresponds to calling [`CreateToolhelp32Snapshot`] with
+    /// `TH32CS_SNAPMODULE` and `TH32CS_SNAPMODULE32`, and
+    /// [`Module32FirstW`] and [`Module32NextW`].
+    ///
+    /// [`CreateToolhelp32Snapshot`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-createtoolhelp32snapshot
+    /// [`Module32FirstW`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-module32firstw
+    /// [`Module32NextW`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-module32nextw
+    pub fn modules(pid: ProcessId) -> Result<Modules, Error> {
+        let flags = TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32;
+        let mut retries = 0;
+        loop {
+            match Snapshot::new(flags, pid.as_raw()) {
+                Ok(snapshot) => {
+                    return Ok(Modules { snapshot, started: false })
+                }
+                // This means that the loader data was being modified, in
+                // which case the documentation asks to try again.
+                Err(_)
+                    if unsafe { GetLastError() } == ERROR_BAD_LENGTH
+                        && retries < MAX_MODULE_SNAPSHOT_RETRIES =>
+                {
+                    retries += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Takes a snapshot of the threads running in the system, and returns
+    /// an iterator over them. If `owner` is given, then only the threads
+    /// of that process are listed.
+    ///
+    /// The snapshot always covers the threads of all processes, so listing
+    /// the threads of a single process is not any faster.
+    ///
+    /// This corresponds to calling [`CreateToolhelp32Snapshot`] with
+    /// `TH32CS_SNAPTHREAD`, and [`Thread32First`] and [`Thread32Next`].
+    ///
+    /// [`CreateToolhelp32Snapshot`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-createtoolhelp32snapshot
+    /// [`Thread32First`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-thread32first
+    /// [`Thread32Next`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-thread32next
+    pub fn threads(owner: Option<ProcessId>) -> Result<Threads, Error> {
+        let snapshot = Snapshot::new(TH32CS_SNAPTHREAD, 0)?;
+        Ok(Threads { snapshot, owner, started: false })
+    }
+
+    /// Takes a snapshot of the heaps of a process, and returns an iterator
+    /// over them. The blocks of each heap are listed by [`Heap::blocks`].
+    ///
+    /// This corresponds to calling [`CreateToolhelp32Snapshot`] with
+    /// `TH32CS_SNAPHEAPLIST`, and [`Heap32ListFirst`] and
+    /// [`Heap32ListNext`].
+    ///
+    /// [`CreateToolhelp32Snapshot`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-createtoolhelp32snapshot
+    /// [`Heap32ListFirst`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-heap32listfirst
+    /// [`Heap32ListNext`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-heap32listnext
+    pub fn heaps(pid: ProcessId) -> Result<Heaps, Error> {
+        let snapshot = Snapshot::new(TH32CS_SNAPHEAPLIST, pid.as_raw())?;
+        Ok(Heaps { snapshot, started: false })
+    }
+}
+
+impl Debug for Snapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot").field("handle", &self.handle).finish()
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        untrack_raw(self.handle);
+        unsafe { CloseHandle(self.handle) };
+    }
+}
+
+/// A process listed by [`Snapshot::processes`].
+#[derive(Clone, Debug)]
+pub struct ProcessEntry {
+    pid: ProcessId,
+    parent_id: ProcessId,
+    thread_count: u32,
+    exe_name: OsString,
+}
+
+impl ProcessEntry {
+    /// Returns the identifier of the process.
+    pub fn pid(&self) -> ProcessId {
+        self.pid
+    }
+
+    /// Returns the identifier of the process that created the process.
+    ///
+    /// The parent may have exited since, and its identifier may even have
+    /// been reused by an unrelated process.
+    pub fn parent_id(&self) -> ProcessId {
+        self.parent_id
+    }
+
+    /// Returns the number of threads of the process.
+    pub fn thread_count(&self) -> u32 {
+        self.thread_count
+    }
+
+    /// Returns the file name of the executable of the process, such as
+    /// `notepad.exe`.
+    pub fn exe_name(&self) -> &OsStr {
+        &self.exe_name
+    }
+}
+
+impl IntoProcessId for &ProcessEntry {
+    fn into_process_id(self) -> DWORD {
+        self.pid.as_raw()
+    }
+}
+
+/// Returns the running processes for which `predicate` returns true.
+///
+/// Note that the processes may exit at any time, and their identifiers
+/// may then be reused, so a process opened by the identifier of an entry
+/// is not necessarily the one that matched.
+pub fn find_processes(
+    predicate: impl FnMut(&ProcessEntry) -> bool,
+) -> Result<Vec<ProcessEntry>, Error> {
+    Ok(Snapshot::processes()?.filter(predicate).collect())
+}
+
+/// Returns the first running process for which `predicate` returns true,
+/// or `None` if there is none.
+///
+/// See [`find_processes`] for caveats.
+pub fn find_process(
+    mut predicate: impl FnMut(&ProcessEntry) -> bool,
+) -> Result<Option<ProcessEntry>, Error> {
+    Ok(Snapshot::processes()?.find(|entry| predicate(entry)))
+}
+
+/// Returns the running processes whose executable has the given file name,
+/// such as `notepad.exe`, ignoring the case of ASCII letters.
+///
+/// See [`find_processes`] for caveats.
+pub fn find_processes_by_name(
+    exe_name: &OsStr,
+) -> Result<Vec<ProcessEntry>, Error> {
+    find_processes(|entry| eq_ignore_ascii_case(entry.exe_name(), exe_name))
+}
+
+/// Returns the first running process whose executable has the given file
+/// name, such as `notepad.exe`, ignoring the case of ASCII letters, or
+/// `None` if there is none.
+///
+/// See [`find_processes`] for caveats.
+pub fn find_process_by_name(
+    exe_name: &OsStr,
+) -> Result<Option<ProcessEntry>, Error> {
+    find_process(|entry| eq_ignore_ascii_case(entry.exe_name(), exe_name))
+}
+
+/// An iterator over the processes in a snapshot, returned by
+/// [`Snapshot::processes`].
+#[derive(Debug)]
+pub struct Processes {
+    snapshot: Snapshot,
+    started: bool,
+}
+
+impl Iterator for Processes {
+    type Item = ProcessEntry;
+
+    fn next(&mut self) -> Option<ProcessEntry> {
+        let mut entry: PROCESSENTRY32W = unsafe { mem::zeroed() };
+        entry.dwSize = mem::size_of::<PROCESSENTRY32W>() as DWORD;
+        let handle = self.snapshot.handle;
+        let ok = if self.started {
+            unsafe { Process32NextW(handle, &mut entry) }
+        } else {
+            self.started = true;
+            unsafe { Process32FirstW(handle, &mut entry) }
+        };
+        if ok == 0 {
+            return None;
+        }
+        Some(ProcessEntry {
+            pid: ProcessId::from_raw(entry.th32ProcessID),
+            parent_id: ProcessId::from_raw(entry.th32ParentProcessID),
+            thread_count: entry.cntThreads,
+            exe_name: from_wide_nul(&entry.szExeFile),
+        })
+    }
+}
+
+/// A module listed by [`Snapshot::modules`].
+#[derive(Clone, Debug)]
+pub struct ModuleEntry {
+    base_address: usize,
+    size: u32,
+    handle: usize,
+    name: OsString,
+    path: PathBuf,
+}
+
+impl ModuleEntry {
+    /// Returns the address the module is mapped at.
+    pub fn base_address(&self) -> usize {
+        self.base_address
+    }
+
+    /// Returns the size of the mapped module, in bytes.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the handle of the module, which is only valid in the
+    /// process that loaded it.
+    pub fn handle(&self) -> HMODULE {
+        self.handle as HMODULE
+    }
+
+    /// Returns the file name of the module.
+    pub fn name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// Returns the full path of the module.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// An iterator over the modules in a snapshot, returned by
+/// [`Snapshot::modules`].
+#[derive(Debug)]
+pub struct Modules {
+    snapshot: Snapshot,
+    started: bool,
+}
+
+impl Iterator for Modules {
+    type Item = ModuleEntry;
+
+    fn next(&mut self) -> Option<ModuleEntry> {
+        let mut entry: MODULEENTRY32W = unsafe { mem::zeroed() };
+        entry.dwSize = mem::size_of::<MODULEENTRY32W>() as DWORD;
+        let handle = self.snapshot.handle;
+        let ok = if self.started {
+            unsafe { Module32NextW(handle, &mut entry) }
+        } else {
+            self.started = true;
+            unsafe { Module32FirstW(handle, &mut entry) }
+        };
+        if ok == 0 {
+            return None;
+        }
+        Some(ModuleEntry {
+            base_address: entry.modBaseAddr as usize,
+            size: entry.modBaseSize,
+            handle: entry.hModule as usize,
+            name: from_wide_nul(&entry.szModule),
+            path: from_wide_nul(&entry.szExePath).into(),
+        })
+    }
+}
+
+/// A thread listed by [`Snapshot::threads`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ThreadEntry {
+    thread_id: u32,
+    owner_id: ProcessId,
+    base_priority: i32,
+}
+
+impl ThreadEntry {
+    /// Returns the identifier of the thread.
+    pub fn thread_id(&self) -> u32 {
+        self.thread_id
+    }
+
+    /// Returns the identifier of the process the thread belongs to.
+    pub fn owner_id(&self) -> ProcessId {
+        self.owner_id
+    }
+
+    /// Returns the base priority of the thread, from 0 (lowest) to 31
+    /// (highest), which is derived from the priority class of its process.
+    pub fn base_priority(&self) -> i32 {
+        self.base_priority
+    }
+}
+
+/// An iterator over the threads in a snapshot, returned by
+/// [`Snapshot::threads`].
+#[derive(Debug)]
+pub struct Threads {
+    snapshot: Snapshot,
+    owner: Option<ProcessId>,
+    started: bool,
+}
+
+impl Iterator for Threads {
+    type Item = ThreadEntry;
+
+    fn next(&mut self) -> Option<ThreadEntry> {
+        let mut entry: THREADENTRY32 = unsafe { mem::zeroed() };
+        entry.dwSize = mem::size_of::<THREADENTRY32>() as DWORD;
+        let handle = self.snapshot.handle;
+        loop {
+            let ok = if self.started {
+                unsafe { Thread32Next(handle, &mut entry) }
+            } else {
+                self.started = true;
+                unsafe { Thread32First(handle, &mut entry) }
+            };
+            if ok == 0 {
+                return None;
+            }
+            let owner_id = ProcessId::from_raw(entry.th32OwnerProcessID);
+            if self.owner.is_none() || self.owner == Some(owner_id) {
+                return Some(ThreadEntry {
+                    thread_id: entry.th32ThreadID,
+                    owner_id,
+                    base_priority: entry.tpBasePri,
+                });
+            }
+        }
+    }
+}
+
+/// A heap listed by [`Snapshot::heaps`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Heap {
+    pid: ProcessId,
+    heap_id: usize,
+    is_default: bool,
+}
+
+impl Heap {
+    /// Returns the identifier of the heap, which is only meaningful to
+    /// Toolhelp functions.
+    pub fn heap_id(&self) -> usize {
+        self.heap_id
+    }
+
+    /// Returns whether this is the default heap of the process, as returned
+    /// by `GetProcessHeap`.
+    pub fn is_default(&self) -> bool {
+        self.is_default
+    }
+
+    /// Returns an iterator over the blocks of the heap, which are read
+    /// from the process when the iterator advances.
+    ///
+    /// Every step walks the heap from its start, so walking a large heap
+    /// takes time quadratic in the number of blocks. The walk ends early if
+    /// the heap is modified concurrently or the process exits.
+    ///
+    /// This corresponds to calling [`Heap32First`] and [`Heap32Next`].
+    ///
+    /// [`Heap32First`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-heap32first
+    /// [`Heap32Next`]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-heap32next
+    pub fn blocks(&self) -> HeapBlocks {
+        HeapBlocks { heap: *self, entry: None }
+    }
+}
+
+/// An iterator over the heaps in a snapshot, returned by
+/// [`Snapshot::heaps`].
+#[derive(Debug)]
+pub struct Heaps {
+    snapshot: Snapshot,
+    started: bool,
+}
+
+impl Iterator for Heaps {
+    type Item = Heap;
+
+    fn next(&mut self) -> Option<Heap> {
+        let mut list: HEAPLIST32 = unsafe { mem::zeroed() };
+        list.dwSize = mem::size_of::<HEAPLIST32>();
+        let handle = self.snapshot.handle;
+        let ok = if self.started {
+            unsafe { Heap32ListNext(handle, &mut list) }
+        } else {
+            self.started = true;
+            unsafe { Heap32ListFirst(handle, &mut list) }
+        };
+        if ok == 0 {
+            return None;
+        }
+        Some(Heap {
+            pid: ProcessId::from_raw(list.th32ProcessID),
+            heap_id: list.th32HeapID,
+            is_default: list.dwFlags & HF32_DEFAULT != 0,
+        })
+    }
+}
+
+/// The state of a heap block.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HeapBlockKind {
+    /// The block is allocated at a fixed address.
+    Fixed,
+    /// The block is not in use.
+    Free,
+    /// The block was allocated with `LMEM_MOVEABLE` and may be moved.
+    Moveable,
+}
+
+/// A block of a heap, listed by [`Heap::blocks`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HeapBlock {
+    address: usize,
+    size: usize,
+    kind: HeapBlockKind,
+    lock_count: u32,
+}
+
+impl HeapBlock {
+    /// Returns the address of the block in the process.
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the size of the block, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the state of the block.
+    pub fn kind(&self) -> HeapBlockKind {
+        self.kind
+    }
+
+    /// Returns the lock count of a moveable block.
+    pub fn lock_count(&self) -> u32 {
+        self.lock_count
+    }
+}
+
+/// An iterator over the blocks of a heap, returned by [`Heap::blocks`].
+pub struct HeapBlocks {
+    heap: Heap,
+    entry: Option<HEAPENTRY32>,
+}
+
+impl Iterator for HeapBlocks {
+    type Item = HeapBlock;
+
+    fn next(&mut self) -> Option<HeapBlock> {
+        let entry = match &mut self.entry {
+            Some(entry) => {
+                if unsafe { Heap32Next(entry) } == 0 {
+                    return None;
+                }
+                entry
+            }
+            None => {
+                let mut entry: HEAPENTRY32 = unsafe { mem::zeroed() };
+                entry.dwSize = mem::size_of::<HEAPENTRY32>();
+                let heap = &self.heap;
+                let ok = unsafe {
+                    Heap32First(&mut entry, heap.pid.as_raw(), heap.heap_id)
+                };
+                if ok == 0 {
+                    return None;
+                }
+                self.entry.insert(entry)
+            }
+        };
+        let kind = if entry.dwFlags & LF32_FREE != 0 {
+            HeapBlockKind::Free
+        } else if entry.dwFlags & LF32_MOVEABLE != 0 {
+            HeapBlockKind::Moveable
+        } else {
+            HeapBlockKind::Fixed
+        };
+        Some(HeapBlock {
+            address: entry.dwAddress,
+            size: entry.dwBlockSize,
+            kind,
+            lock_count: entry.dwLockCount,
+        })
+    }
+}
+
+impl Debug for HeapBlocks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeapBlocks").field("heap", &self.heap).finish()
+    }
+}
+
+/// Copies a UTF-16 string from a buffer, up to the first NUL.
+fn from_wide_nul(buf: &[u16]) -> OsString {
+    let len = buf.iter().position(|&unit| unit == 0).unwrap_or(buf.len());
+    OsString::from_wide(&buf[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_process_is_listed() {
+        let current = ProcessId::current();
+        let process = Snapshot::processes()
+            .unwrap()
+            .find(|process| process.pid() == current)
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert!(process
+            .exe_name()
+            .eq_ignore_ascii_case(exe.file_name().unwrap()));
+        assert!(process.thread_count() >= 1);
+        assert_eq!(
+            process.parent_id(),
+            crate::open_process::current_process().parent_id().unwrap()
+        );
+    }
+
+    #[test]
+    fn find_current_process_by_name() {
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_ascii_uppercase();
+        let processes = find_processes_by_name(&name).unwrap();
+        assert!(processes.iter().any(|p| p.pid() == ProcessId::current()));
+        assert!(find_process_by_name(&name).unwrap().is_some());
+        assert!(find_process_by_name("no such process.exe".as_ref())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn current_process_modules_start_with_executable() {
+        let modules: Vec<_> =
+            Snapshot::modules(ProcessId::current()).unwrap().collect();
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(
+            modules[0].path().canonicalize().unwrap(),
+            exe.canonicalize().unwrap()
+        );
+        let ntdll = modules
+            .iter()
+            .find(|m| m.name().eq_ignore_ascii_case("ntdll.dll"))
+            .unwrap();
+        assert_eq!(ntdll.handle() as usize, ntdll.base_address());
+        assert!(ntdll.size() > 0);
+    }
+
+    #[test]
+    fn threads_are_filtered_by_owner() {
+        let current = ProcessId::current();
+        let threads: Vec<_> =
+            Snapshot::threads(Some(current)).unwrap().collect();
+        assert!(threads.iter().all(|thread| thread.owner_id() == current));
+        let thread_id =
+            unsafe { winapi::um::processthreadsapi::GetCurrentThreadId() };
+        assert!(threads.iter().any(|thread| thread.thread_id() == thread_id));
+    }
+
+    #[test]
+    fn current_process_has_default_heap() {
+        let heap = Snapshot::heaps(ProcessId::current())
+            .unwrap()
+            .find(|heap| heap.is_default())
+            .unwrap();
+        // Walking the whole heap would take too long.
+        let blocks: Vec<_> = heap.blocks().take(10).collect();
+        assert!(!blocks.is_empty());
+        assert!(blocks.iter().all(|block| block.address() != 0));
+    }
+}
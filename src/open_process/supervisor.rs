@@ -0,0 +1,550 @@
+//! Keeping child processes running according to restart policies.
+//!
+//! A [`Supervisor`] spawns the children described by [`ChildSpec`]s, waits
+//! for them to exit and restarts them as their [`RestartPolicy`] demands,
+//! with an exponential backoff between restarts. Everything that happens to
+//! the children is reported as an [`Event`] by [`Supervisor::next_event`],
+//! which drives the supervisor and is meant to be called in a loop:
+//!
+//! ```no_run
+//! use std::process::Command;
+//! use winapi_util::open_process::supervisor::{
+//!     ChildSpec, RestartPolicy, Supervisor,
+//! };
+//!
+//! let mut supervisor = Supervisor::new();
+//! let spec = ChildSpec::new(Command::new("worker.exe"))
+//!     .policy(RestartPolicy::OnFailure)
+//!     .max_restarts(5);
+//! supervisor.add(spec).unwrap();
+//! while let Some(event) = supervisor.next_event(None) {
+//!     println!("{:?}", event);
+//! }
+//! ```
+//!
+//! A [`CrashLoopDetector`] recognizes programs that keep crashing, whether
+//! they are supervised here or restarted by something else.
+
+use core::{marker::PhantomData, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::OsString,
+    io,
+    path::PathBuf,
+    process::{Child, Command},
+    time::Instant,
+};
+
+use winapi::{
+    shared::minwindef::DWORD,
+    um::winnt::{MAXIMUM_WAIT_OBJECTS, SYNCHRONIZE},
+};
+
+use super::{
+    open_process,
+    wait::{self, Waitable},
+    ComptimeAccessRights, Error, ExitSignal, ExitStatus,
+    HasProcessQueryLimitedInformation, ProcessHandle, ProcessId,
+    ProcessIdentity,
+};
+
+/// When a child is restarted after it exits.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RestartPolicy {
+    /// The child is never restarted.
+    Never,
+    /// The child is restarted if it exits with a non-zero exit code, or if
+    /// it could not be spawned.
+    OnFailure,
+    /// The child is always restarted.
+    Always,
+}
+
+/// The description of a child process to supervise.
+#[derive(Debug)]
+pub struct ChildSpec {
+    command: Command,
+    policy: RestartPolicy,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_restarts: Option<u32>,
+}
+
+impl ChildSpec {
+    /// Describes a child spawned from the given command, which is restarted
+    /// on failure without limit, waiting between 100 milliseconds and 30
+    /// seconds before each restart.
+    pub fn new(command: Command) -> ChildSpec {
+        ChildSpec {
+            command,
+            policy: RestartPolicy::OnFailure,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            max_restarts: None,
+        }
+    }
+
+    /// Sets when the child is restarted.
+    pub fn policy(mut self, policy: RestartPolicy) -> ChildSpec {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the delay before the first restart, which doubles with every
+    /// further restart up to `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> ChildSpec {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Sets how many times the child is restarted at most before the
+    /// supervisor gives up on it.
+    pub fn max_restarts(mut self, max: u32) -> ChildSpec {
+        self.max_restarts = Some(max);
+        self
+    }
+}
+
+/// Identifies a child within its [`Supervisor`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChildId(usize);
+
+/// Something that happened to a supervised child.
+#[derive(Debug)]
+pub enum Event {
+    /// The child was spawned.
+    Started {
+        /// The child.
+        id: ChildId,
+        /// The identifier of the new process.
+        pid: ProcessId,
+    },
+    /// The child could not be spawned.
+    SpawnFailed {
+        /// The child.
+        id: ChildId,
+        /// The reason spawning failed.
+        error: io::Error,
+    },
+    /// Waiting for the children to exit failed. The wait is retried on the
+    /// next call to [`Supervisor::next_event`].
+    WaitFailed {
+        /// The reason waiting failed.
+        error: io::Error,
+    },
+    /// The child exited.
+    Exited {
+        /// The child.
+        id: ChildId,
+        /// The identifier of the process that exited.
+        pid: ProcessId,
+        /// The exit code of the process.
+        code: DWORD,
+    },
+    /// The child will be restarted once the delay has elapsed.
+    Restarting {
+        /// The child.
+        id: ChildId,
+        /// The delay before the restart.
+        delay: Duration,
+    },
+    /// The child exceeded its maximum number of restarts and will not be
+    /// restarted again.
+    GaveUp {
+        /// The child.
+        id: ChildId,
+    },
+}
+
+/// Spawns child processes and restarts them according to their policies.
+///
+/// At most 64 children can be supervised at once, since that is the most
+/// [`wait_any`](super::wait_any) can wait on. Children that are still
+/// running when the supervisor is dropped are killed.
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    children: Vec<Managed>,
+    events: VecDeque<Event>,
+}
+
+#[derive(Debug)]
+struct Managed {
+    spec: ChildSpec,
+    // The running process along with the signal raised once it exits.
+    child: Option<(Child, ExitSignal)>,
+    restarts: u32,
+    backoff: Duration,
+    restart_at: Option<Instant>,
+}
+
+impl Supervisor {
+    /// Creates a supervisor without any children.
+    pub fn new() -> Supervisor {
+        Supervisor::default()
+    }
+
+    /// Spawns a new child and starts supervising it.
+    ///
+    /// Whether spawning succeeded is reported as an event. An error is only
+    /// returned if the supervisor already has 64 children.
+    pub fn add(&mut self, spec: ChildSpec) -> io::Result<ChildId> {
+        if self.children.len() >= MAXIMUM_WAIT_OBJECTS as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a supervisor cannot have more than 64 children",
+            ));
+        }
+        let id = ChildId(self.children.len());
+        let backoff = spec.initial_backoff;
+        self.children.push(Managed {
+            spec,
+            child: None,
+            restarts: 0,
+            backoff,
+            restart_at: None,
+        });
+        self.spawn(id);
+        Ok(id)
+    }
+
+    /// Kills the child if it is running, and stops restarting it.
+    pub fn stop(&mut self, id: ChildId) -> io::Result<()> {
+        let managed = &mut self.children[id.0];
+        managed.restart_at = None;
+        managed.spec.policy = RestartPolicy::Never;
+        if let Some((mut child, _)) = managed.child.take() {
+            child.kill()?;
+            child.wait()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the identifier of the running process of the child, if any.
+    pub fn pid(&self, id: ChildId) -> Option<ProcessId> {
+        let (child, _) = self.children[id.0].child.as_ref()?;
+        Some(ProcessId::from_raw(child.id()))
+    }
+
+    /// Waits for the next event, restarting children whose backoff delay
+    /// has elapsed along the way. A timeout of `None` waits indefinitely.
+    ///
+    /// Returns `None` if the timeout elapses, or if no child is running or
+    /// waiting to be restarted anymore.
+    pub fn next_event(&mut self, timeout: Option<Duration>) -> Option<Event> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+            let now = Instant::now();
+            self.restart_due(now);
+            if !self.events.is_empty() {
+                continue;
+            }
+            let next_restart =
+                self.children.iter().filter_map(|m| m.restart_at).min();
+            let running: Vec<usize> = (0..self.children.len())
+                .filter(|&i| self.children[i].child.is_some())
+                .collect();
+            if running.is_empty() && next_restart.is_none() {
+                return None;
+            }
+            if deadline.is_some_and(|deadline| deadline <= now) {
+                return None;
+            }
+            let wake = match (deadline, next_restart) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let wait = wake.map(|wake| wake.saturating_duration_since(now));
+            match self.wait_any(&running, wait) {
+                Ok(Some(index)) => self.reap(running[index]),
+                Ok(None) => {}
+                Err(error) => {
+                    return Some(Event::WaitFailed { error: error.into() })
+                }
+            }
+        }
+    }
+
+    /// Waits for one of the given running children to exit, returning its
+    /// position in `running`, or `None` on timeout.
+    fn wait_any(
+        &self,
+        running: &[usize],
+        timeout: Option<Duration>,
+    ) -> Result<Option<usize>, Error> {
+        if running.is_empty() {
+            std::thread::sleep(timeout.unwrap_or_default());
+            return Ok(None);
+        }
+        let signals: Vec<&dyn Waitable> = running
+            .iter()
+            .map(|&i| {
+                let (_, exited) = self.children[i].child.as_ref().unwrap();
+                exited as &dyn Waitable
+            })
+            .collect();
+        wait::wait_any(&signals, timeout)
+    }
+
+    fn spawn(&mut self, id: ChildId) {
+        let managed = &mut self.children[id.0];
+        match managed.spec.command.spawn() {
+            Ok(mut child) => match exit_signal(&child) {
+                Ok(exited) => {
+                    let pid = ProcessId::from_raw(child.id());
+                    managed.child = Some((child, exited));
+                    self.events.push_back(Event::Started { id, pid });
+                }
+                Err(error) => {
+                    let error = io::Error::from(error);
+                    // A child that cannot be waited on cannot be
+                    // supervised.
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    self.events.push_back(Event::SpawnFailed { id, error });
+                    self.schedule_restart(id, true);
+                }
+            },
+            Err(error) => {
+                self.events.push_back(Event::SpawnFailed { id, error });
+                self.schedule_restart(id, true);
+            }
+        }
+    }
+
+    fn reap(&mut self, index: usize) {
+        let id = ChildId(index);
+        let (mut child, _) = self.children[index].child.take().unwrap();
+        let pid = ProcessId::from_raw(child.id());
+        // The process has exited, so this does not block.
+        let code = match child.wait() {
+            Ok(status) => status.code().unwrap_or(1) as DWORD,
+            Err(_) => 1,
+        };
+        self.events.push_back(Event::Exited { id, pid, code });
+        self.schedule_restart(id, code != 0);
+    }
+
+    fn schedule_restart(&mut self, id: ChildId, failed: bool) {
+        let managed = &mut self.children[id.0];
+        let restart = match managed.spec.policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => failed,
+            RestartPolicy::Always => true,
+        };
+        if !restart {
+            return;
+        }
+        if managed.spec.max_restarts.is_some_and(|max| managed.restarts >= max)
+        {
+            self.events.push_back(Event::GaveUp { id });
+            return;
+        }
+        let delay = managed.backoff;
+        managed.restarts += 1;
+        managed.backoff = (delay * 2).min(managed.spec.max_backoff);
+        managed.restart_at = Some(Instant::now() + delay);
+        self.events.push_back(Event::Restarting { id, delay });
+    }
+
+    fn restart_due(&mut self, now: Instant) {
+        for index in 0..self.children.len() {
+            let managed = &mut self.children[index];
+            if managed.restart_at.is_some_and(|at| at <= now) {
+                managed.restart_at = None;
+                self.spawn(ChildId(index));
+            }
+        }
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        for managed in &mut self.children {
+            if let Some((mut child, _)) = managed.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+/// Returns a signal that is raised once the given child exits.
+fn exit_signal(child: &Child) -> Result<ExitSignal, Error> {
+    open_process::<ComptimeAccessRights<SYNCHRONIZE>>(
+        PhantomData,
+        false,
+        child,
+    )?
+    .exit_notifier()
+}
+
+/// A program that crashed more often than allowed, reported by
+/// [`CrashLoopDetector::record`].
+#[derive(Clone, Debug)]
+pub struct CrashLoop {
+    image: PathBuf,
+    crashes: usize,
+}
+
+impl CrashLoop {
+    /// Returns the path of the executable image of the crashing program, in
+    /// the native NT device form.
+    pub fn image(&self) -> &PathBuf {
+        &self.image
+    }
+
+    /// Returns how many times the program crashed within the window of the
+    /// detector.
+    pub fn crashes(&self) -> usize {
+        self.crashes
+    }
+}
+
+/// Detects programs that keep exiting abnormally, i.e. with a non-zero exit
+/// code, in quick succession.
+///
+/// Exits are tracked per executable image, and every process is identified
+/// by its identifier together with its creation time, so recording the
+/// same process twice only counts once, even if its identifier has been
+/// reused by another process in the meantime.
+#[derive(Debug)]
+pub struct CrashLoopDetector {
+    threshold: usize,
+    window: Duration,
+    // The time and identity of the recent crashes of every image.
+    crashes: HashMap<OsString, VecDeque<(Instant, ProcessIdentity)>>,
+}
+
+impl CrashLoopDetector {
+    /// Creates a detector that reports a crash loop once a program exits
+    /// abnormally more than `threshold` times within `window`.
+    pub fn new(threshold: usize, window: Duration) -> CrashLoopDetector {
+        CrashLoopDetector { threshold, window, crashes: HashMap::new() }
+    }
+
+    /// Records the exit of the given process, returning a [`CrashLoop`] if
+    /// its program has now crashed more often than allowed. Every further
+    /// crash within the window is reported again.
+    ///
+    /// Processes that are still running or exited with code zero are
+    /// ignored.
+    pub fn record<M: HasProcessQueryLimitedInformation>(
+        &mut self,
+        process: &ProcessHandle<M>,
+    ) -> Result<Option<CrashLoop>, Error> {
+        let status = process.exit_code()?;
+        if let ExitStatus::StillActive | ExitStatus::Exited(0) = status {
+            return Ok(None);
+        }
+        let image = process.image_file_name()?;
+        let identity = process.identity()?;
+
+        let now = Instant::now();
+        // Paths on Windows are case-insensitive.
+        let key = image.as_os_str().to_ascii_lowercase();
+        let crashes = self.crashes.entry(key).or_default();
+        while crashes
+            .front()
+            .is_some_and(|&(at, _)| now.duration_since(at) > self.window)
+        {
+            crashes.pop_front();
+        }
+        if crashes.iter().any(|&(_, seen)| seen == identity) {
+            return Ok(None);
+        }
+        crashes.push_back((now, identity));
+        if crashes.len() <= self.threshold {
+            return Ok(None);
+        }
+        Ok(Some(CrashLoop { image, crashes: crashes.len() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exiting(code: u32) -> Command {
+        let mut command = Command::new("cmd.exe");
+        command.args(["/d", "/c", &format!("exit {code}")]);
+        command
+    }
+
+    #[test]
+    fn failing_child_is_restarted_until_limit() {
+        let mut supervisor = Supervisor::new();
+        let spec = ChildSpec::new(exiting(1))
+            .backoff(Duration::from_millis(1), Duration::from_millis(2))
+            .max_restarts(2);
+        let id = supervisor.add(spec).unwrap();
+        let mut starts = 0;
+        let mut delays = Vec::new();
+        let mut gave_up = false;
+        while let Some(event) = supervisor.next_event(None) {
+            match event {
+                Event::Started { id: started, .. } => {
+                    assert_eq!(started, id);
+                    starts += 1;
+                }
+                Event::Exited { code, .. } => assert_eq!(code, 1),
+                Event::Restarting { delay, .. } => delays.push(delay),
+                Event::GaveUp { .. } => gave_up = true,
+                Event::SpawnFailed { error, .. } => panic!("{error}"),
+                Event::WaitFailed { error } => panic!("{error}"),
+            }
+        }
+        assert_eq!(starts, 3);
+        assert_eq!(
+            delays,
+            [Duration::from_millis(1), Duration::from_millis(2)]
+        );
+        assert!(gave_up);
+    }
+
+    #[test]
+    fn repeated_crashes_are_detected() {
+        use crate::open_process::{open_process, QueryLimitedAccess};
+        use core::marker::PhantomData;
+
+        let mut detector = CrashLoopDetector::new(2, Duration::from_secs(60));
+        let mut crash = || {
+            let mut child = exiting(1).spawn().unwrap();
+            let process =
+                open_process::<QueryLimitedAccess>(PhantomData, false, &child)
+                    .unwrap();
+            child.wait().unwrap();
+            let result = detector.record(&process).unwrap();
+            // Recording the same process twice only counts once.
+            assert!(detector.record(&process).unwrap().is_none());
+            result
+        };
+        assert!(crash().is_none());
+        assert!(crash().is_none());
+        let crash_loop = crash().unwrap();
+        assert_eq!(crash_loop.crashes(), 3);
+        assert!(crash_loop
+            .image()
+            .to_string_lossy()
+            .to_ascii_lowercase()
+            .ends_with("cmd.exe"));
+    }
+
+    #[test]
+    fn successful_child_is_not_restarted_on_failure_policy() {
+        let mut supervisor = Supervisor::new();
+        supervisor.add(ChildSpec::new(exiting(0))).unwrap();
+        assert!(matches!(
+            supervisor.next_event(None),
+            Some(Event::Started { .. })
+        ));
+        assert!(matches!(
+            supervisor.next_event(None),
+            Some(Event::Exited { code: 0, .. })
+        ));
+        assert!(supervisor.next_event(None).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+#[cfg(not(feature = "ntdll"))]
+use core::marker::PhantomData;
+
+#[cfg(not(feature = "ntdll"))]
+use winapi::{
+    shared::winerror::ERROR_INVALID_PARAMETER,
+    um::{errhandlingapi::SetLastError, winnt::THREAD_SUSPEND_RESUME},
+};
+
+#[cfg(feature = "ntdll")]
+use super::ntdll;
+#[cfg(not(feature = "ntdll"))]
+use super::{open_thread, snapshot::Snapshot, ProcessId, ThreadHandle};
+use super::{
+    Error, HasProcessQueryLimitedInformation, HasProcessSuspendResume,
+    ProcessHandle,
+};
+
+impl<M> ProcessHandle<M>
+where
+    M: HasProcessSuspendResume + HasProcessQueryLimitedInformation,
+{
+    /// Suspends every thread of the process.
+    ///
+    /// Threads keep a suspend count, so a process that is suspended twice
+    /// has to be resumed twice before it runs again.
+    ///
+    /// With the `ntdll` feature, this corresponds to calling
+    /// `NtSuspendProcess`. Otherwise, this corresponds to calling
+    /// [`SuspendThread`] on every thread listed by a Toolhelp snapshot,
+    /// which misses threads that are started while the process is being
+    /// suspended. If a thread cannot be opened or suspended, e.g. because
+    /// access to it is denied, the threads that were already suspended are
+    /// resumed and the error is returned.
+    ///
+    /// [`SuspendThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-suspendthread
+    pub fn suspend(&self) -> Result<(), Error> {
+        let () = <M as HasProcessSuspendResume>::ASSERT;
+        let () = <M as HasProcessQueryLimitedInformation>::ASSERT;
+        #[cfg(feature = "ntdll")]
+        {
+            ntdll::check(unsafe {
+                ntdll::NtSuspendProcess(self.inner.as_ptr())
+            })
+        }
+        #[cfg(not(feature = "ntdll"))]
+        {
+            let threads = open_threads(self.pid()?)?;
+            for (i, thread) in threads.iter().enumerate() {
+                if let Err(err) = thread.suspend() {
+                    let code = err.code().as_dword();
+                    for thread in &threads[..i] {
+                        let _ = thread.resume();
+                    }
+                    drop(threads);
+                    unsafe { SetLastError(code) };
+                    return Err(Error(PhantomData));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Resumes every thread of the process, undoing one call to
+    /// `ProcessHandle::suspend`.
+    ///
+    /// With the `ntdll` feature, this corresponds to calling
+    /// `NtResumeProcess`. Otherwise, this corresponds to calling
+    /// [`ResumeThread`] on every thread listed by a Toolhelp snapshot. If a
+    /// thread cannot be resumed, the remaining threads are still resumed
+    /// and the first error is returned.
+    ///
+    /// [`ResumeThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-resumethread
+    pub fn resume(&self) -> Result<(), Error> {
+        let () = <M as HasProcessSuspendResume>::ASSERT;
+        let () = <M as HasProcessQueryLimitedInformation>::ASSERT;
+        #[cfg(feature = "ntdll")]
+        {
+            ntdll::check(unsafe {
+                ntdll::NtResumeProcess(self.inner.as_ptr())
+            })
+        }
+        #[cfg(not(feature = "ntdll"))]
+        {
+            let threads = open_threads(self.pid()?)?;
+            let mut first_error = None;
+            for thread in &threads {
+                if let Err(err) = thread.resume() {
+                    first_error.get_or_insert(err.code().as_dword());
+                }
+            }
+            drop(threads);
+            match first_error {
+                Some(code) => {
+                    unsafe { SetLastError(code) };
+                    Err(Error(PhantomData))
+                }
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "ntdll"))]
+type SuspendableThread =
+    ThreadHandle<crate::access_rights!(THREAD_SUSPEND_RESUME)>;
+
+/// Opens every thread of a process listed by a Toolhelp snapshot.
+///
+/// Threads that exit before they are opened are skipped, which
+/// `OpenThread` reports as `ERROR_INVALID_PARAMETER`. Any other failure is
+/// returned, so that no thread is silently left out.
+#[cfg(not(feature = "ntdll"))]
+fn open_threads(pid: ProcessId) -> Result<Vec<SuspendableThread>, Error> {
+    let mut threads = vec![];
+    for entry in Snapshot::threads(Some(pid))? {
+        match open_thread::<crate::access_rights!(THREAD_SUSPEND_RESUME)>(
+            PhantomData,
+            false,
+            entry.thread_id(),
+        ) {
+            Ok(thread) => threads.push(thread),
+            Err(err) if err.code().as_dword() == ERROR_INVALID_PARAMETER => {}
+            Err(err) => {
+                let code = err.code().as_dword();
+                drop(threads);
+                unsafe { SetLastError(code) };
+                return Err(Error(PhantomData));
+            }
+        }
+    }
+    Ok(threads)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::{open_process, WaitOutcome};
+    use core::{marker::PhantomData, time::Duration};
+    use winapi::um::winnt::{
+        PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SUSPEND_RESUME, SYNCHRONIZE,
+    };
+
+    #[test]
+    fn suspended_process_does_not_exit() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/q", "/k"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<
+            crate::access_rights!(
+                PROCESS_SUSPEND_RESUME,
+                PROCESS_QUERY_LIMITED_INFORMATION,
+                SYNCHRONIZE
+            ),
+        >(PhantomData, false, &child)
+        .unwrap();
+        handle.suspend().unwrap();
+        // The shell would exit right away if it were running.
+        drop(child.stdin.take());
+        let outcome = handle.wait(Some(Duration::from_millis(200))).unwrap();
+        assert_eq!(outcome, WaitOutcome::TimedOut);
+        handle.resume().unwrap();
+        assert_eq!(handle.wait(None).unwrap(), WaitOutcome::Signaled);
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,378 @@
+use core::{mem, slice, time::Duration};
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStringExt,
+    time::SystemTime,
+};
+
+use winapi::shared::minwindef::{DWORD, ULONG};
+
+use super::{
+    ntdll,
+    times::{from_intervals, system_time_from_intervals},
+    Error, ProcessId,
+};
+
+/// The size of the buffer that the process list is first queried with. It
+/// grows as needed.
+const INITIAL_BUFFER_SIZE: usize = 1 << 18;
+
+/// A process listed by [`system_processes`].
+#[derive(Clone, Debug)]
+pub struct SystemProcess {
+    pid: ProcessId,
+    parent_id: ProcessId,
+    name: OsString,
+    session_id: u32,
+    base_priority: i32,
+    handle_count: u32,
+    creation_time: SystemTime,
+    kernel_time: Duration,
+    user_time: Duration,
+    working_set: usize,
+    peak_working_set: usize,
+    private_bytes: usize,
+    virtual_size: usize,
+    page_fault_count: u32,
+    threads: Vec<SystemThread>,
+}
+
+impl SystemProcess {
+    /// Returns the identifier of the process.
+    pub fn pid(&self) -> ProcessId {
+        self.pid
+    }
+
+    /// Returns the identifier of the process that created the process.
+    ///
+    /// The parent may have exited since, and its identifier may even have
+    /// been reused by an unrelated process.
+    pub fn parent_id(&self) -> ProcessId {
+        self.parent_id
+    }
+
+    /// Returns the file name of the executable of the process, which is
+    /// empty for the System Idle Process.
+    pub fn name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// Returns the Remote Desktop Services session the process runs in.
+    pub fn session_id(&self) -> u32 {
+        self.session_id
+    }
+
+    /// Returns the base priority of the process, from 0 (lowest) to 31
+    /// (highest).
+    pub fn base_priority(&self) -> i32 {
+        self.base_priority
+    }
+
+    /// Returns the number of handles open in the process.
+    pub fn handle_count(&self) -> u32 {
+        self.handle_count
+    }
+
+    /// Returns the time the process was created.
+    pub fn creation_time(&self) -> SystemTime {
+        self.creation_time
+    }
+
+    /// Returns the time the threads of the process have spent executing in
+    /// kernel mode, summed over all threads.
+    pub fn kernel_time(&self) -> Duration {
+        self.kernel_time
+    }
+
+    /// Returns the time the threads of the process have spent executing in
+    /// user mode, summed over all threads.
+    pub fn user_time(&self) -> Duration {
+        self.user_time
+    }
+
+    /// Returns the size of the working set of the process, in bytes.
+    pub fn working_set(&self) -> usize {
+        self.working_set
+    }
+
+    /// Returns the largest size the working set of the process has had, in
+    /// bytes.
+    pub fn peak_working_set(&self) -> usize {
+        self.peak_working_set
+    }
+
+    /// Returns the memory that the process has committed and that cannot
+    /// be shared with other processes, in bytes.
+    pub fn private_bytes(&self) -> usize {
+        self.private_bytes
+    }
+
+    /// Returns the size of the virtual address space that the process has
+    /// reserved, in bytes.
+    pub fn virtual_size(&self) -> usize {
+        self.virtual_size
+    }
+
+    /// Returns the number of page faults of the process.
+    pub fn page_fault_count(&self) -> u32 {
+        self.page_fault_count
+    }
+
+    /// Returns the threads of the process.
+    pub fn threads(&self) -> &[SystemThread] {
+        &self.threads
+    }
+}
+
+/// A thread of a [`SystemProcess`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SystemThread {
+    thread_id: u32,
+    state: ThreadState,
+    wait_reason: u32,
+    priority: i32,
+    base_priority: i32,
+    context_switches: u32,
+    kernel_time: Duration,
+    user_time: Duration,
+    start_address: usize,
+}
+
+impl SystemThread {
+    /// Returns the identifier of the thread.
+    pub fn thread_id(&self) -> u32 {
+        self.thread_id
+    }
+
+    /// Returns the scheduling state of the thread.
+    pub fn state(&self) -> ThreadState {
+        self.state
+    }
+
+    /// Returns why the thread is waiting, as a `KWAIT_REASON` value, if its
+    /// state is [`ThreadState::Waiting`]. For example, 5 (`Suspended`)
+    /// means that the thread is suspended.
+    pub fn wait_reason(&self) -> u32 {
+        self.wait_reason
+    }
+
+    /// Returns the current priority of the thread, from 0 (lowest) to 31
+    /// (highest), which may be boosted above its base priority.
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Returns the base priority of the thread.
+    pub fn base_priority(&self) -> i32 {
+        self.base_priority
+    }
+
+    /// Returns the number of times the thread has been switched to.
+    pub fn context_switches(&self) -> u32 {
+        self.context_switches
+    }
+
+    /// Returns the time the thread has spent executing in kernel mode.
+    pub fn kernel_time(&self) -> Duration {
+        self.kernel_time
+    }
+
+    /// Returns the time the thread has spent executing in user mode.
+    pub fn user_time(&self) -> Duration {
+        self.user_time
+    }
+
+    /// Returns the address the thread started executing at in the process.
+    pub fn start_address(&self) -> usize {
+        self.start_address
+    }
+}
+
+/// The scheduling state of a thread, as a `KTHREAD_STATE` value.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ThreadState {
+    /// The thread is being created.
+    Initialized,
+    /// The thread is waiting to be scheduled.
+    Ready,
+    /// The thread is running on a processor.
+    Running,
+    /// The thread has been selected to run next on a processor.
+    Standby,
+    /// The thread has exited.
+    Terminated,
+    /// The thread is waiting for an object, or is suspended. See
+    /// `SystemThread::wait_reason`.
+    Waiting,
+    /// The thread is ready, but its kernel stack is paged out.
+    Transition,
+    /// The thread has been selected to run on a processor, which has not
+    /// been picked yet.
+    DeferredReady,
+    /// Another state, given by its `KTHREAD_STATE` value.
+    Other(u32),
+}
+
+impl ThreadState {
+    fn from_raw(state: ULONG) -> ThreadState {
+        match state {
+            0 => ThreadState::Initialized,
+            1 => ThreadState::Ready,
+            2 => ThreadState::Running,
+            3 => ThreadState::Standby,
+            4 => ThreadState::Terminated,
+            5 => ThreadState::Waiting,
+            6 => ThreadState::Transition,
+            7 => ThreadState::DeferredReady,
+            state => ThreadState::Other(state),
+        }
+    }
+}
+
+/// Returns the processes running in the system, together with their
+/// threads, CPU times and memory usage.
+///
+/// All of this is read with a single system call, without opening any of
+/// the processes, which makes this much cheaper than querying every
+/// process separately. This is meant for monitoring tools that refresh
+/// the information periodically.
+///
+/// This corresponds to calling `NtQuerySystemInformation` with
+/// `SystemProcessInformation`.
+pub fn system_processes() -> Result<Vec<SystemProcess>, Error> {
+    let buf = query_process_information()?;
+    let base = buf.as_ptr() as *const u8;
+    let mut processes = Vec::new();
+    let mut offset = 0;
+    loop {
+        let entry = unsafe {
+            &*(base.add(offset) as *const ntdll::SYSTEM_PROCESS_INFORMATION)
+        };
+        processes.push(unsafe { process_from_entry(entry) });
+        if entry.NextEntryOffset == 0 {
+            return Ok(processes);
+        }
+        offset += entry.NextEntryOffset as usize;
+    }
+}
+
+/// Returns the process list, which is made of `u64`s to align the entries.
+fn query_process_information() -> Result<Vec<u64>, Error> {
+    let unit = mem::size_of::<u64>();
+    let mut buf: Vec<u64> = vec![0; INITIAL_BUFFER_SIZE / unit];
+    loop {
+        let mut needed: ULONG = 0;
+        let status = unsafe {
+            ntdll::NtQuerySystemInformation(
+                ntdll::SystemProcessInformation,
+                buf.as_mut_ptr().cast(),
+                (buf.len() * unit) as ULONG,
+                &mut needed,
+            )
+        };
+        if status == ntdll::STATUS_INFO_LENGTH_MISMATCH {
+            // Leave room for processes that start in the meantime.
+            let len = (needed as usize).max(buf.len() * unit) * 3 / 2;
+            buf.resize((len + unit - 1) / unit, 0);
+            continue;
+        }
+        ntdll::check(status)?;
+        return Ok(buf);
+    }
+}
+
+/// Converts an entry of the process list.
+///
+/// # Safety
+///
+/// The entry must be part of a list returned by `NtQuerySystemInformation`,
+/// so that its threads follow it and its name points into the list.
+unsafe fn process_from_entry(
+    entry: &ntdll::SYSTEM_PROCESS_INFORMATION,
+) -> SystemProcess {
+    let threads = (entry as *const ntdll::SYSTEM_PROCESS_INFORMATION).add(1)
+        as *const ntdll::SYSTEM_THREAD_INFORMATION;
+    let threads = slice::from_raw_parts(threads, entry.NumberOfThreads as _);
+    let name = &entry.ImageName;
+    let name = if name.Buffer.is_null() {
+        OsString::new()
+    } else {
+        let len = usize::from(name.Length) / mem::size_of::<u16>();
+        OsString::from_wide(slice::from_raw_parts(name.Buffer, len))
+    };
+    SystemProcess {
+        pid: ProcessId::from_raw(entry.UniqueProcessId as DWORD),
+        parent_id: ProcessId::from_raw(
+            entry.InheritedFromUniqueProcessId as DWORD,
+        ),
+        name,
+        session_id: entry.SessionId,
+        base_priority: entry.BasePriority,
+        handle_count: entry.HandleCount,
+        creation_time: system_time_from_intervals(entry.CreateTime as u64),
+        kernel_time: from_intervals(entry.KernelTime as u64),
+        user_time: from_intervals(entry.UserTime as u64),
+        working_set: entry.WorkingSetSize,
+        peak_working_set: entry.PeakWorkingSetSize,
+        private_bytes: entry.PrivatePageCount,
+        virtual_size: entry.VirtualSize,
+        page_fault_count: entry.PageFaultCount,
+        threads: threads.iter().map(thread_from_entry).collect(),
+    }
+}
+
+fn thread_from_entry(
+    entry: &ntdll::SYSTEM_THREAD_INFORMATION,
+) -> SystemThread {
+    SystemThread {
+        thread_id: entry.UniqueThread as DWORD,
+        state: ThreadState::from_raw(entry.ThreadState),
+        wait_reason: entry.WaitReason,
+        priority: entry.Priority,
+        base_priority: entry.BasePriority,
+        context_switches: entry.ContextSwitches,
+        kernel_time: from_intervals(entry.KernelTime as u64),
+        user_time: from_intervals(entry.UserTime as u64),
+        start_address: entry.StartAddress as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winapi::um::processthreadsapi::GetCurrentThreadId;
+
+    #[test]
+    fn layouts_match_the_system() {
+        let (process, thread) = if cfg!(target_pointer_width = "64") {
+            (0x100, 0x50)
+        } else {
+            (0xb8, 0x40)
+        };
+        assert_eq!(
+            mem::size_of::<ntdll::SYSTEM_PROCESS_INFORMATION>(),
+            process
+        );
+        assert_eq!(mem::size_of::<ntdll::SYSTEM_THREAD_INFORMATION>(), thread);
+    }
+
+    #[test]
+    fn current_process_is_listed() {
+        let processes = system_processes().unwrap();
+        let current = processes
+            .iter()
+            .find(|process| process.pid() == ProcessId::current())
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert!(current.name().eq_ignore_ascii_case(exe.file_name().unwrap()));
+        assert!(current.working_set() > 0);
+        assert!(current.creation_time() <= SystemTime::now());
+        let thread_id = unsafe { GetCurrentThreadId() };
+        let thread = current
+            .threads()
+            .iter()
+            .find(|thread| thread.thread_id() == thread_id)
+            .unwrap();
+        assert_eq!(thread.state(), ThreadState::Running);
+    }
+}
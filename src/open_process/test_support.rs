@@ -0,0 +1,115 @@
+//! Disposable child processes for testing process-management code against
+//! real process handles.
+//!
+//! Every helper spawns the system's `cmd.exe`, so nothing needs to be built
+//! or shipped alongside the test binary. The spawned process is killed when
+//! the returned [`TestProcess`] is dropped, so a failing test does not leave
+//! stray processes behind.
+
+use std::{
+    io,
+    os::windows::process::CommandExt,
+    process::{Child, Command, ExitStatus, Stdio},
+};
+
+use winapi::um::winbase::{CREATE_NO_WINDOW, CREATE_SUSPENDED};
+
+use super::{open_process, Error, IntoAccessRights, ProcessHandle};
+
+/// A child process spawned for a test, killed when dropped.
+#[derive(Debug)]
+pub struct TestProcess {
+    child: Child,
+}
+
+impl TestProcess {
+    /// Returns the process identifier of the child.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Opens a new handle to the child with the given access rights.
+    ///
+    /// This is a shorthand for calling [`open_process`] with the child's
+    /// process identifier.
+    pub fn open<R: IntoAccessRights>(
+        &self,
+        desired_access: R::RuntimeArgumentType,
+    ) -> Result<ProcessHandle<R::AccessRightsType>, Error> {
+        open_process::<R>(desired_access, false, self.id())
+    }
+
+    /// Returns the underlying child process.
+    pub fn child(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Waits for the child to exit and returns its exit status.
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        self.child.wait()
+    }
+}
+
+impl Drop for TestProcess {
+    fn drop(&mut self) {
+        // Killing fails if the child has already exited, which is fine.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Spawns a child process that runs until it is killed.
+///
+/// The child blocks reading its standard input, which is a pipe that is
+/// never written to, so it uses no CPU while it waits.
+pub fn spawn_hung() -> io::Result<TestProcess> {
+    spawn(cmd().args(["/d", "/q", "/k"]), 0)
+}
+
+/// Spawns a child process that immediately exits with the given code.
+pub fn spawn_exiting(code: u32) -> io::Result<TestProcess> {
+    spawn(cmd().args(["/d", "/c", "exit", &code.to_string()]), 0)
+}
+
+/// Spawns a child process whose main thread is suspended, so that it never
+/// runs any code of its own.
+///
+/// Only the process is handed out, not its main thread, so the child stays
+/// suspended until it is killed.
+pub fn spawn_suspended() -> io::Result<TestProcess> {
+    spawn(cmd().args(["/d", "/q", "/k"]), CREATE_SUSPENDED)
+}
+
+fn cmd() -> Command {
+    let mut command = Command::new("cmd.exe");
+    command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null());
+    command
+}
+
+fn spawn(command: &mut Command, flags: u32) -> io::Result<TestProcess> {
+    let child = command.creation_flags(CREATE_NO_WINDOW | flags).spawn()?;
+    Ok(TestProcess { child })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::RuntimeAccessRights;
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    #[test]
+    fn exiting_child_reports_code() {
+        let mut child = spawn_exiting(7).unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(7));
+    }
+
+    #[test]
+    fn hung_and_suspended_children_can_be_opened() {
+        let access = PROCESS_QUERY_LIMITED_INFORMATION;
+        for child in [spawn_hung().unwrap(), spawn_suspended().unwrap()] {
+            let handle = child.open::<RuntimeAccessRights>(access).unwrap();
+            assert!(format!("{handle:?}")
+                .contains(&format!("pid: {}", child.id())));
+        }
+    }
+}
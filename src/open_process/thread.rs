@@ -0,0 +1,238 @@
+use core::{
+    ffi::c_void,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ptr::NonNull,
+};
+
+use winapi::{
+    shared::minwindef::{BOOL, DWORD},
+    um::{
+        processthreadsapi::{
+            GetThreadId, OpenThread, ResumeThread, SuspendThread,
+        },
+        winnt::HANDLE,
+    },
+};
+
+use super::{
+    sealed::{Handle, HandleType, IntoAccessRights, ThreadHandleKind},
+    Error, HasThreadQueryLimitedInformation, HasThreadSuspendResume,
+};
+
+/// A non-null handle to a thread, obtained e.g. via [`open_thread`].
+///
+/// Its access rights are tracked in the type just like those of a
+/// [`ProcessHandle`](super::ProcessHandle), using the `THREAD_*` constants
+/// instead of the `PROCESS_*` ones, e.g.
+/// `ThreadHandle<access_rights!(THREAD_SUSPEND_RESUME)>`.
+///
+/// When the handle goes out of scope, the handle gets automatically closed
+/// by calling [`CloseHandle`].
+///
+/// [`CloseHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+pub type ThreadHandle<M> = Handle<ThreadHandleKind, M>;
+
+impl HandleType for ThreadHandleKind {
+    const NAME: &'static str = "ThreadHandle";
+    const ID_NAME: &'static str = "tid";
+    fn object_id(handle: NonNull<c_void>) -> Option<DWORD> {
+        // Thread identifiers share their space with process identifiers, so
+        // 0 is never valid either.
+        match unsafe { GetThreadId(handle.as_ptr()) } {
+            0 => None,
+            tid => Some(tid),
+        }
+    }
+}
+
+/// Rustic wrapper around [`OpenThread`] function.
+///
+/// Thread identifiers can be found e.g. with
+/// [`Snapshot::threads`](super::snapshot::Snapshot::threads). The returned
+/// handle gets automatically closed by calling [`CloseHandle`] when the
+/// handle goes out of scope.
+///
+/// [`OpenThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-openthread
+/// [`CloseHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+pub fn open_thread<R: IntoAccessRights>(
+    desired_access: R::RuntimeArgumentType,
+    inherit_handle: bool,
+    thread_id: u32,
+) -> Result<ThreadHandle<R::AccessRightsType>, Error> {
+    let dw_desired_access: DWORD = R::rt_arg_to_dword(desired_access);
+    let inherit_handle: BOOL = if inherit_handle { 1 } else { 0 };
+    let metadata = R::rt_arg_to_metadata(desired_access);
+
+    let handle: HANDLE =
+        unsafe { OpenThread(dw_desired_access, inherit_handle, thread_id) };
+    let inner = NonNull::new(handle).ok_or(Error(PhantomData))?;
+    Ok(Handle::from_opened(inner, metadata))
+}
+
+impl<M: HasThreadQueryLimitedInformation> ThreadHandle<M> {
+    /// Returns the identifier of the thread.
+    ///
+    /// This corresponds to calling [`GetThreadId`].
+    ///
+    /// [`GetThreadId`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadid
+    pub fn tid(&self) -> Result<u32, Error> {
+        let () = M::ASSERT;
+        match unsafe { GetThreadId(self.inner.as_ptr()) } {
+            0 => Err(Error(PhantomData)),
+            tid => Ok(tid),
+        }
+    }
+}
+
+impl<M: HasThreadSuspendResume> ThreadHandle<M> {
+    /// Suspends the thread, and returns its suspend count before the call.
+    ///
+    /// Threads keep a suspend count, so a thread that is suspended twice
+    /// has to be resumed twice before it runs again. The thread may not
+    /// have stopped yet when this returns; it stops before it runs any
+    /// more code in user mode.
+    ///
+    /// This corresponds to calling [`SuspendThread`].
+    ///
+    /// [`SuspendThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-suspendthread
+    pub fn suspend(&self) -> Result<u32, Error> {
+        let () = M::ASSERT;
+        match unsafe { SuspendThread(self.inner.as_ptr()) } {
+            DWORD::MAX => Err(Error(PhantomData)),
+            count => Ok(count),
+        }
+    }
+
+    /// Decrements the suspend count of the thread, and returns the count
+    /// before the call. The thread runs again once the count drops to 0,
+    /// so a previous count of 1 means that it was resumed.
+    ///
+    /// This corresponds to calling [`ResumeThread`].
+    ///
+    /// [`ResumeThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-resumethread
+    pub fn resume(&self) -> Result<u32, Error> {
+        let () = M::ASSERT;
+        match unsafe { ResumeThread(self.inner.as_ptr()) } {
+            DWORD::MAX => Err(Error(PhantomData)),
+            count => Ok(count),
+        }
+    }
+
+    /// Suspends the thread until the returned guard is dropped, which
+    /// resumes it.
+    ///
+    /// This corresponds to calling [`SuspendThread`], and [`ResumeThread`]
+    /// on drop.
+    ///
+    /// [`SuspendThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-suspendthread
+    /// [`ResumeThread`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-resumethread
+    pub fn suspend_guard(&self) -> Result<SuspendGuard<'_, M>, Error> {
+        let previous_count = self.suspend()?;
+        Ok(SuspendGuard { thread: self, previous_count })
+    }
+}
+
+/// A suspension of a thread, which is undone when this is dropped.
+///
+/// See `ThreadHandle::suspend_guard`.
+pub struct SuspendGuard<'a, M: HasThreadSuspendResume> {
+    thread: &'a ThreadHandle<M>,
+    previous_count: u32,
+}
+
+impl<M: HasThreadSuspendResume> SuspendGuard<'_, M> {
+    /// Returns the suspend count of the thread before it was suspended by
+    /// this guard. If it is not 0, the thread stays suspended after the
+    /// guard is dropped.
+    pub fn previous_count(&self) -> u32 {
+        self.previous_count
+    }
+}
+
+impl<M: HasThreadSuspendResume> Drop for SuspendGuard<'_, M> {
+    fn drop(&mut self) {
+        let _ = self.thread.resume();
+    }
+}
+
+impl<M: HasThreadSuspendResume> Debug for SuspendGuard<'_, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SuspendGuard")
+            .field("thread", &self.thread)
+            .field("previous_count", &self.previous_count)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{ComptimeAccessRights, RuntimeAccessRights};
+    use winapi::um::{
+        processthreadsapi::GetCurrentThreadId,
+        winnt::{
+            THREAD_QUERY_INFORMATION, THREAD_QUERY_LIMITED_INFORMATION,
+            THREAD_SUSPEND_RESUME,
+        },
+    };
+
+    #[test]
+    fn open_current_thread() {
+        let tid = unsafe { GetCurrentThreadId() };
+        let handle = open_thread::<
+            ComptimeAccessRights<THREAD_QUERY_INFORMATION>,
+        >(PhantomData, false, tid)
+        .unwrap();
+        assert_eq!(handle.tid().unwrap(), tid);
+        assert!(format!("{handle:?}").starts_with("ThreadHandle"));
+        assert!(format!("{handle:?}").contains(&format!("tid: {tid}")));
+
+        let handle = open_thread::<RuntimeAccessRights>(
+            THREAD_QUERY_LIMITED_INFORMATION,
+            false,
+            tid,
+        )
+        .unwrap();
+        assert_eq!(handle.access_rights(), THREAD_QUERY_LIMITED_INFORMATION);
+        handle.try_close().unwrap();
+    }
+
+    #[test]
+    fn suspend_counts_nest() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            sender.send(unsafe { GetCurrentThreadId() }).unwrap();
+            let _ = stopped.recv();
+        });
+        let tid = receiver.recv().unwrap();
+        let handle =
+            open_thread::<crate::access_rights!(THREAD_SUSPEND_RESUME)>(
+                PhantomData,
+                false,
+                tid,
+            )
+            .unwrap();
+
+        assert_eq!(handle.suspend().unwrap(), 0);
+        {
+            let guard = handle.suspend_guard().unwrap();
+            assert_eq!(guard.previous_count(), 1);
+        }
+        assert_eq!(handle.resume().unwrap(), 1);
+        assert_eq!(handle.resume().unwrap(), 0);
+        stop.send(()).unwrap();
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn open_missing_thread_fails() {
+        let result = open_thread::<RuntimeAccessRights>(
+            THREAD_QUERY_LIMITED_INFORMATION,
+            false,
+            0,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use core::{mem, ptr};
+
+use winapi::{
+    shared::minwindef::{DWORD, ULONG},
+    um::minwinbase::STILL_ACTIVE,
+};
+
+use super::{
+    ntdll, Error, ExitStatus, HasThreadQueryInformation,
+    HasThreadQueryLimitedInformation, ProcessId, ThreadHandle,
+};
+
+/// Basic information about a thread, returned by
+/// `ThreadHandle::basic_information`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ThreadBasicInformation {
+    exit_status: ExitStatus,
+    teb_address: usize,
+    process_id: ProcessId,
+    thread_id: u32,
+    affinity_mask: usize,
+    priority: i32,
+    base_priority: i32,
+}
+
+impl ThreadBasicInformation {
+    /// Returns whether the thread is still running, and its exit code if it
+    /// is not.
+    pub fn exit_status(&self) -> ExitStatus {
+        self.exit_status
+    }
+
+    /// Returns the address of the thread environment block of the thread
+    /// in its process, which holds e.g. the bounds of its stack and its
+    /// thread-local storage.
+    ///
+    /// For threads of WOW64 processes, this is the address of the 64-bit
+    /// thread environment block.
+    pub fn teb_address(&self) -> usize {
+        self.teb_address
+    }
+
+    /// Returns the identifier of the process that the thread belongs to.
+    pub fn process_id(&self) -> ProcessId {
+        self.process_id
+    }
+
+    /// Returns the identifier of the thread.
+    pub fn thread_id(&self) -> u32 {
+        self.thread_id
+    }
+
+    /// Returns the affinity mask of the thread.
+    pub fn affinity_mask(&self) -> usize {
+        self.affinity_mask
+    }
+
+    /// Returns the current scheduling priority of the thread, from 0 to 31,
+    /// which may be boosted above its base priority.
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Returns the base scheduling priority of the thread, from 0 to 31,
+    /// which follows from its priority class and relative priority.
+    pub fn base_priority(&self) -> i32 {
+        self.base_priority
+    }
+}
+
+impl<M: HasThreadQueryLimitedInformation> ThreadHandle<M> {
+    /// Returns basic information about the thread, such as the process it
+    /// belongs to and the address of its thread environment block.
+    ///
+    /// This relies on the undocumented `ThreadBasicInformation`
+    /// information class of `NtQueryInformationThread`.
+    pub fn basic_information(&self) -> Result<ThreadBasicInformation, Error> {
+        let () = M::ASSERT;
+        let mut info: ntdll::THREAD_BASIC_INFORMATION =
+            unsafe { mem::zeroed() };
+        let status = unsafe {
+            ntdll::NtQueryInformationThread(
+                self.inner.as_ptr(),
+                ntdll::ThreadBasicInformation,
+                (&mut info as *mut ntdll::THREAD_BASIC_INFORMATION).cast(),
+                mem::size_of::<ntdll::THREAD_BASIC_INFORMATION>() as ULONG,
+                ptr::null_mut(),
+            )
+        };
+        ntdll::check(status)?;
+        // Running threads report `STATUS_PENDING`, which is `STILL_ACTIVE`.
+        let exit_status = match info.ExitStatus as DWORD {
+            STILL_ACTIVE => ExitStatus::StillActive,
+            code => ExitStatus::Exited(code),
+        };
+        Ok(ThreadBasicInformation {
+            exit_status,
+            teb_address: info.TebBaseAddress as usize,
+            process_id: ProcessId::from_raw(info.UniqueProcess as DWORD),
+            thread_id: info.UniqueThread as DWORD,
+            affinity_mask: info.AffinityMask,
+            priority: info.Priority,
+            base_priority: info.BasePriority,
+        })
+    }
+}
+
+impl<M: HasThreadQueryInformation> ThreadHandle<M> {
+    /// Returns the address that the thread started executing at, i.e. the
+    /// start routine that was passed to `CreateThread`, which can be used
+    /// to find the module that created the thread.
+    ///
+    /// This relies on the undocumented `ThreadQuerySetWin32StartAddress`
+    /// information class of `NtQueryInformationThread`.
+    pub fn start_address(&self) -> Result<usize, Error> {
+        let () = M::ASSERT;
+        let mut address: usize = 0;
+        let status = unsafe {
+            ntdll::NtQueryInformationThread(
+                self.inner.as_ptr(),
+                ntdll::ThreadQuerySetWin32StartAddress,
+                (&mut address as *mut usize).cast(),
+                mem::size_of::<usize>() as ULONG,
+                ptr::null_mut(),
+            )
+        };
+        ntdll::check(status)?;
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_process::{open_thread, ExitStatus};
+    use core::marker::PhantomData;
+    use winapi::um::{
+        processthreadsapi::GetCurrentThreadId, winnt::THREAD_QUERY_INFORMATION,
+    };
+
+    #[test]
+    fn queries_spawned_thread() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            sender.send(unsafe { GetCurrentThreadId() }).unwrap();
+            let _ = stopped.recv();
+        });
+        let tid = receiver.recv().unwrap();
+        let handle = open_thread::<
+            crate::access_rights!(THREAD_QUERY_INFORMATION),
+        >(PhantomData, false, tid)
+        .unwrap();
+        let running = handle.basic_information();
+        let start_address = handle.start_address();
+        stop.send(()).unwrap();
+        worker.join().unwrap();
+
+        let running = running.unwrap();
+        assert_eq!(running.exit_status(), ExitStatus::StillActive);
+        assert_eq!(running.process_id().as_raw(), std::process::id());
+        assert_eq!(running.thread_id(), tid);
+        assert_ne!(running.teb_address(), 0);
+        assert_ne!(start_address.unwrap(), 0);
+
+        let exited = handle.basic_information().unwrap();
+        assert_eq!(exited.exit_status(), ExitStatus::Exited(0));
+    }
+}
@@ -0,0 +1,145 @@
+use core::{marker::PhantomData, mem, time::Duration};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use winapi::{
+    shared::minwindef::FILETIME, um::processthreadsapi::GetProcessTimes,
+};
+
+use super::{Error, HasProcessQueryLimitedInformation, ProcessHandle};
+
+/// The number of 100-nanosecond intervals between the `FILETIME` epoch,
+/// January 1, 1601, and the Unix epoch.
+const UNIX_EPOCH_INTERVALS: u64 = 116_444_736_000_000_000;
+
+/// The timing information of a process.
+///
+/// See `ProcessHandle::times`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProcessTimes {
+    creation: SystemTime,
+    exit: Option<SystemTime>,
+    kernel: Duration,
+    user: Duration,
+}
+
+impl ProcessTimes {
+    /// Returns the time the process was created.
+    pub fn creation(&self) -> SystemTime {
+        self.creation
+    }
+
+    /// Returns the time the process exited, or `None` if it is still
+    /// running.
+    pub fn exit(&self) -> Option<SystemTime> {
+        self.exit
+    }
+
+    /// Returns the time the threads of the process have spent executing in
+    /// kernel mode, summed over all threads.
+    pub fn kernel(&self) -> Duration {
+        self.kernel
+    }
+
+    /// Returns the time the threads of the process have spent executing in
+    /// user mode, summed over all threads.
+    pub fn user(&self) -> Duration {
+        self.user
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Returns the creation and exit times of the process, and how much CPU
+    /// time it has consumed.
+    ///
+    /// This corresponds to calling [`GetProcessTimes`].
+    ///
+    /// [`GetProcessTimes`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocesstimes
+    pub fn times(&self) -> Result<ProcessTimes, Error> {
+        let () = M::ASSERT;
+        let mut creation: FILETIME = unsafe { mem::zeroed() };
+        let (mut exit, mut kernel, mut user) = (creation, creation, creation);
+        let ok = unsafe {
+            GetProcessTimes(
+                self.inner.as_ptr(),
+                &mut creation,
+                &mut exit,
+                &mut kernel,
+                &mut user,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        // The exit time is left at zero while the process is running.
+        let exit = match intervals(&exit) {
+            0 => None,
+            _ => Some(to_system_time(&exit)),
+        };
+        Ok(ProcessTimes {
+            creation: to_system_time(&creation),
+            exit,
+            kernel: to_duration(&kernel),
+            user: to_duration(&user),
+        })
+    }
+}
+
+/// Returns the number of 100-nanosecond intervals in a `FILETIME`.
+fn intervals(time: &FILETIME) -> u64 {
+    u64::from(time.dwHighDateTime) << 32 | u64::from(time.dwLowDateTime)
+}
+
+/// Converts a `FILETIME` holding a duration.
+pub(super) fn to_duration(time: &FILETIME) -> Duration {
+    from_intervals(intervals(time))
+}
+
+/// Converts a `FILETIME` holding a point in time.
+fn to_system_time(time: &FILETIME) -> SystemTime {
+    system_time_from_intervals(intervals(time))
+}
+
+/// Converts a point in time given in 100-nanosecond intervals since 1601.
+pub(super) fn system_time_from_intervals(intervals: u64) -> SystemTime {
+    if intervals >= UNIX_EPOCH_INTERVALS {
+        UNIX_EPOCH + from_intervals(intervals - UNIX_EPOCH_INTERVALS)
+    } else {
+        UNIX_EPOCH - from_intervals(UNIX_EPOCH_INTERVALS - intervals)
+    }
+}
+
+/// Converts a duration given in 100-nanosecond intervals.
+pub(super) fn from_intervals(intervals: u64) -> Duration {
+    let nanos = (intervals % 10_000_000) as u32 * 100;
+    Duration::new(intervals / 10_000_000, nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+
+    #[test]
+    fn filetime_conversions() {
+        let time = |intervals: u64| FILETIME {
+            dwLowDateTime: intervals as u32,
+            dwHighDateTime: (intervals >> 32) as u32,
+        };
+        assert_eq!(to_system_time(&time(UNIX_EPOCH_INTERVALS)), UNIX_EPOCH);
+        assert_eq!(
+            to_system_time(&time(UNIX_EPOCH_INTERVALS + 15)),
+            UNIX_EPOCH + Duration::from_nanos(1_500)
+        );
+        assert_eq!(
+            to_duration(&time(25_000_001)),
+            Duration::new(2, 500_000_100)
+        );
+    }
+
+    #[test]
+    fn current_process_times() {
+        let times = current_process().times().unwrap();
+        assert!(times.creation() <= SystemTime::now());
+        assert_eq!(times.exit(), None);
+    }
+}
@@ -0,0 +1,98 @@
+use core::{
+    marker::PhantomData,
+    ptr::{self, NonNull},
+};
+
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{processthreadsapi::OpenProcessToken, winnt::HANDLE},
+};
+
+use super::{
+    sealed::{Handle, HandleType, IntoAccessRights, TokenHandleKind},
+    Error, HasProcessQueryLimitedInformation, ProcessHandle,
+};
+
+/// A non-null handle to an access token, obtained e.g. via
+/// `ProcessHandle::open_token`.
+///
+/// Its access rights are tracked in the type just like those of a
+/// [`ProcessHandle`](super::ProcessHandle), using the `TOKEN_*` constants
+/// instead of the `PROCESS_*` ones, e.g.
+/// `TokenHandle<access_rights!(TOKEN_QUERY)>`.
+///
+/// When the handle goes out of scope, the handle gets automatically closed
+/// by calling [`CloseHandle`].
+///
+/// [`CloseHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+pub type TokenHandle<M> = Handle<TokenHandleKind, M>;
+
+impl HandleType for TokenHandleKind {
+    const NAME: &'static str = "TokenHandle";
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Opens the primary access token of the process, which holds the
+    /// security context that its threads run under unless they impersonate
+    /// another user.
+    ///
+    /// The returned handle gets automatically closed by calling
+    /// [`CloseHandle`] when the handle goes out of scope.
+    ///
+    /// This corresponds to calling [`OpenProcessToken`].
+    ///
+    /// [`OpenProcessToken`]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-openprocesstoken
+    /// [`CloseHandle`]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+    pub fn open_token<R: IntoAccessRights>(
+        &self,
+        desired_access: R::RuntimeArgumentType,
+    ) -> Result<TokenHandle<R::AccessRightsType>, Error> {
+        let () = M::ASSERT;
+        let dw_desired_access: DWORD = R::rt_arg_to_dword(desired_access);
+        let metadata = R::rt_arg_to_metadata(desired_access);
+
+        let mut handle: HANDLE = ptr::null_mut();
+        let ok = unsafe {
+            OpenProcessToken(
+                self.inner.as_ptr(),
+                dw_desired_access,
+                &mut handle,
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        let inner = NonNull::new(handle).ok_or(Error(PhantomData))?;
+        Ok(Handle::from_opened(inner, metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::{
+        current_process, ComptimeAccessRights, RuntimeAccessRights,
+    };
+    use winapi::um::winnt::{TOKEN_ADJUST_PRIVILEGES, TOKEN_QUERY};
+
+    #[test]
+    fn opens_token_of_current_process() {
+        let process = current_process();
+        let token = process
+            .open_token::<ComptimeAccessRights<TOKEN_QUERY>>(PhantomData)
+            .unwrap();
+        assert_eq!(token.access_rights(), TOKEN_QUERY);
+        assert!(format!("{token:?}").starts_with("TokenHandle"));
+
+        let token = process
+            .open_token::<RuntimeAccessRights>(
+                TOKEN_QUERY | TOKEN_ADJUST_PRIVILEGES,
+            )
+            .unwrap();
+        assert_eq!(
+            token.access_rights(),
+            TOKEN_QUERY | TOKEN_ADJUST_PRIVILEGES
+        );
+        token.try_close().unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+use core::marker::PhantomData;
+use std::{
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStringExt,
+    path::Path,
+    ptr,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{LPVOID, UINT},
+        winerror::ERROR_RESOURCE_NAME_NOT_FOUND,
+    },
+    um::{
+        errhandlingapi::SetLastError,
+        winver::{
+            GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
+        },
+    },
+};
+
+use super::{
+    canonicalize_image_path, to_wide, Error,
+    HasProcessQueryLimitedInformation, ProcessHandle,
+};
+
+/// The names of the string fields defined for a `StringFileInfo` block.
+const STANDARD_FIELDS: &[&str] = &[
+    "Comments",
+    "CompanyName",
+    "FileDescription",
+    "FileVersion",
+    "InternalName",
+    "LegalCopyright",
+    "LegalTrademarks",
+    "OriginalFilename",
+    "PrivateBuild",
+    "ProductName",
+    "ProductVersion",
+    "SpecialBuild",
+];
+
+/// Translations to try when a file does not list any, in the order the
+/// Windows shell tries them: US English with the Unicode and Windows
+/// Latin-1 code pages.
+const FALLBACK_TRANSLATIONS: &[(u16, u16)] =
+    &[(0x0409, 0x04B0), (0x0409, 0x04E4)];
+
+/// The version resource of an executable or DLL, such as its product name
+/// and file version.
+///
+/// See [`version_info`].
+#[derive(Clone, Debug)]
+pub struct VersionInfo {
+    language: u16,
+    code_page: u16,
+    fields: BTreeMap<String, OsString>,
+}
+
+impl VersionInfo {
+    /// Returns the value of the given string field, e.g. `"CompanyName"`.
+    pub fn get(&self, name: &str) -> Option<&OsStr> {
+        self.fields.get(name).map(|v| v.as_os_str())
+    }
+
+    /// Returns all standard string fields present in the resource, keyed by
+    /// name.
+    pub fn fields(&self) -> &BTreeMap<String, OsString> {
+        &self.fields
+    }
+
+    /// Returns the `FileVersion` field, if present.
+    pub fn file_version(&self) -> Option<&OsStr> {
+        self.get("FileVersion")
+    }
+
+    /// Returns the `ProductName` field, if present.
+    pub fn product_name(&self) -> Option<&OsStr> {
+        self.get("ProductName")
+    }
+
+    /// Returns the `FileDescription` field, if present. This is the name
+    /// that Task Manager displays for a process.
+    pub fn file_description(&self) -> Option<&OsStr> {
+        self.get("FileDescription")
+    }
+
+    /// Returns the language identifier of the string table the fields were
+    /// read from.
+    pub fn language(&self) -> u16 {
+        self.language
+    }
+
+    /// Returns the code page of the string table the fields were read from.
+    pub fn code_page(&self) -> u16 {
+        self.code_page
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation> ProcessHandle<M> {
+    /// Reads the version resource of the executable image of the process.
+    ///
+    /// The handle must have been opened with at least
+    /// `PROCESS_QUERY_LIMITED_INFORMATION`.
+    ///
+    /// This resolves the image path and then calls [`version_info`] on it.
+    pub fn image_version_info(&self) -> Result<VersionInfo, Error> {
+        let image = canonicalize_image_path(self.image_file_name()?)?;
+        version_info(image)
+    }
+}
+
+/// Reads the version resource of the file at the given path.
+///
+/// The string fields are read from the first translation listed in the
+/// resource that has any of them. If the file has no version resource, then
+/// an error is returned, with code `ERROR_RESOURCE_NAME_NOT_FOUND` if the
+/// resource has no string fields.
+///
+/// This corresponds to calling [`GetFileVersionInfoW`] and
+/// [`VerQueryValueW`].
+///
+/// [`GetFileVersionInfoW`]: https://learn.microsoft.com/en-us/windows/win32/api/winver/nf-winver-getfileversioninfow
+/// [`VerQueryValueW`]: https://learn.microsoft.com/en-us/windows/win32/api/winver/nf-winver-verqueryvaluew
+pub fn version_info<P: AsRef<Path>>(path: P) -> Result<VersionInfo, Error> {
+    let path = to_wide(path.as_ref().as_os_str());
+    let size =
+        unsafe { GetFileVersionInfoSizeW(path.as_ptr(), ptr::null_mut()) };
+    if size == 0 {
+        return Err(Error(PhantomData));
+    }
+    // Use a u32 buffer since VerQueryValueW hands out pointers into it that
+    // we read DWORDs and u16s through.
+    let mut block = vec![0u32; size as usize / 4 + 1];
+    let ok = unsafe {
+        GetFileVersionInfoW(path.as_ptr(), 0, size, block.as_mut_ptr().cast())
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    let block: &[u32] = &block;
+
+    let mut translations = translations(block);
+    translations.extend_from_slice(FALLBACK_TRANSLATIONS);
+    for (language, code_page) in translations {
+        let mut fields = BTreeMap::new();
+        for &name in STANDARD_FIELDS {
+            let sub_block = format!(
+                "\\StringFileInfo\\{:04x}{:04x}\\{}",
+                language, code_page, name
+            );
+            if let Some(value) = query_string(block, &sub_block) {
+                fields.insert(name.to_string(), value);
+            }
+        }
+        if !fields.is_empty() {
+            return Ok(VersionInfo { language, code_page, fields });
+        }
+    }
+    // The version resource has no string table.
+    unsafe { SetLastError(ERROR_RESOURCE_NAME_NOT_FOUND) };
+    Err(Error(PhantomData))
+}
+
+/// Returns the (language, code page) pairs listed in the
+/// `\VarFileInfo\Translation` value of the given version block.
+fn translations(block: &[u32]) -> Vec<(u16, u16)> {
+    let Some((ptr, len)) = query(block, "\\VarFileInfo\\Translation") else {
+        return vec![];
+    };
+    // Each entry is a language identifier followed by a code page.
+    let count = len as usize / 4;
+    let words =
+        unsafe { core::slice::from_raw_parts(ptr as *const u16, count * 2) };
+    words.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// Queries a string value in the given version block.
+fn query_string(block: &[u32], sub_block: &str) -> Option<OsString> {
+    let (ptr, len) = query(block, sub_block)?;
+    // For string values, the length is in characters and includes the NUL
+    // terminator.
+    let chars = unsafe {
+        core::slice::from_raw_parts(ptr as *const u16, len as usize)
+    };
+    let chars = match chars.iter().position(|&c| c == 0) {
+        Some(end) => &chars[..end],
+        None => chars,
+    };
+    if chars.is_empty() {
+        return None;
+    }
+    Some(OsString::from_wide(chars))
+}
+
+/// Returns a pointer into the given version block along with the length of
+/// the value at that location.
+fn query(block: &[u32], sub_block: &str) -> Option<(LPVOID, UINT)> {
+    let sub_block = to_wide(OsStr::new(sub_block));
+    let mut value: LPVOID = ptr::null_mut();
+    let mut len: UINT = 0;
+    let ok = unsafe {
+        VerQueryValueW(
+            block.as_ptr().cast(),
+            sub_block.as_ptr(),
+            &mut value,
+            &mut len,
+        )
+    };
+    if ok == 0 || value.is_null() || len == 0 {
+        return None;
+    }
+    Some((value, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel32_has_version_info() {
+        let info = version_info(r"C:\Windows\System32\kernel32.dll").unwrap();
+        assert!(info.file_version().is_some());
+        assert!(info.product_name().is_some());
+    }
+}
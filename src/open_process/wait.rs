@@ -0,0 +1,234 @@
+use core::{marker::PhantomData, time::Duration};
+
+use winapi::{
+    shared::{
+        minwindef::{BOOL, DWORD, FALSE, TRUE},
+        winerror::WAIT_TIMEOUT,
+    },
+    um::{
+        synchapi::{WaitForMultipleObjects, WaitForSingleObject},
+        winbase::{INFINITE, WAIT_ABANDONED, WAIT_ABANDONED_0, WAIT_OBJECT_0},
+        winnt::HANDLE,
+    },
+};
+
+use super::{
+    Error, ExitSignal, HasSynchronize, ProcessHandle, ProcessHandleRef,
+};
+
+/// The outcome of waiting on a handle.
+///
+/// See `ProcessHandle::wait`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum WaitOutcome {
+    /// The object was signaled, i.e. the process has exited.
+    Signaled,
+    /// The timeout elapsed before the object was signaled.
+    TimedOut,
+    /// The object is a mutex whose owning thread exited without releasing
+    /// it. This never happens for processes.
+    Abandoned,
+}
+
+impl<M: HasSynchronize> ProcessHandle<M> {
+    /// Blocks until the process exits or the timeout elapses. A timeout of
+    /// `None` waits indefinitely.
+    ///
+    /// Timeouts are rounded up to whole milliseconds.
+    ///
+    /// This corresponds to calling [`WaitForSingleObject`].
+    ///
+    /// [`WaitForSingleObject`]: https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitforsingleobject
+    pub fn wait(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<WaitOutcome, Error> {
+        let () = M::ASSERT;
+        let millis = timeout_to_millis(timeout);
+        match unsafe { WaitForSingleObject(self.inner.as_ptr(), millis) } {
+            WAIT_OBJECT_0 => Ok(WaitOutcome::Signaled),
+            WAIT_TIMEOUT => Ok(WaitOutcome::TimedOut),
+            WAIT_ABANDONED => Ok(WaitOutcome::Abandoned),
+            _ => Err(Error(PhantomData)),
+        }
+    }
+}
+
+/// An object that can be waited on together with others, via [`wait_any`]
+/// or [`wait_all`].
+pub trait Waitable {
+    /// Returns the raw handle to wait on, which must stay open for as long
+    /// as `self` is borrowed.
+    fn wait_handle(&self) -> HANDLE;
+}
+
+impl<M: HasSynchronize> Waitable for ProcessHandle<M> {
+    fn wait_handle(&self) -> HANDLE {
+        let () = M::ASSERT;
+        self.inner.as_ptr()
+    }
+}
+
+impl<'a, M: HasSynchronize> Waitable for ProcessHandleRef<'a, M> {
+    fn wait_handle(&self) -> HANDLE {
+        (**self).wait_handle()
+    }
+}
+
+impl Waitable for ExitSignal {
+    fn wait_handle(&self) -> HANDLE {
+        self.event()
+    }
+}
+
+/// Blocks until any of the given objects is signaled or the timeout
+/// elapses, returning the index of a signaled object, or `None` on
+/// timeout. A timeout of `None` waits indefinitely.
+///
+/// If several objects are signaled, the lowest index is returned. An
+/// abandoned mutex counts as signaled. At most 64 objects can be waited on.
+///
+/// This corresponds to calling [`WaitForMultipleObjects`].
+///
+/// [`WaitForMultipleObjects`]: https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitformultipleobjects
+pub fn wait_any(
+    objects: &[&dyn Waitable],
+    timeout: Option<Duration>,
+) -> Result<Option<usize>, Error> {
+    let handles: Vec<HANDLE> =
+        objects.iter().map(|o| o.wait_handle()).collect();
+    let result = wait_multiple(&handles, FALSE, timeout);
+    let count = handles.len() as DWORD;
+    match result {
+        WAIT_TIMEOUT => Ok(None),
+        r if (WAIT_OBJECT_0..WAIT_OBJECT_0 + count).contains(&r) => {
+            Ok(Some((r - WAIT_OBJECT_0) as usize))
+        }
+        r if (WAIT_ABANDONED_0..WAIT_ABANDONED_0 + count).contains(&r) => {
+            Ok(Some((r - WAIT_ABANDONED_0) as usize))
+        }
+        _ => Err(Error(PhantomData)),
+    }
+}
+
+/// Blocks until all of the given objects are signaled or the timeout
+/// elapses. A timeout of `None` waits indefinitely.
+///
+/// At most 64 objects can be waited on.
+///
+/// This corresponds to calling [`WaitForMultipleObjects`] with
+/// `bWaitAll` set.
+///
+/// [`WaitForMultipleObjects`]: https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-waitformultipleobjects
+pub fn wait_all(
+    objects: &[&dyn Waitable],
+    timeout: Option<Duration>,
+) -> Result<WaitOutcome, Error> {
+    let handles: Vec<HANDLE> =
+        objects.iter().map(|o| o.wait_handle()).collect();
+    let result = wait_multiple(&handles, TRUE, timeout);
+    let count = handles.len() as DWORD;
+    match result {
+        WAIT_TIMEOUT => Ok(WaitOutcome::TimedOut),
+        r if (WAIT_OBJECT_0..WAIT_OBJECT_0 + count).contains(&r) => {
+            Ok(WaitOutcome::Signaled)
+        }
+        r if (WAIT_ABANDONED_0..WAIT_ABANDONED_0 + count).contains(&r) => {
+            Ok(WaitOutcome::Abandoned)
+        }
+        _ => Err(Error(PhantomData)),
+    }
+}
+
+fn wait_multiple(
+    handles: &[HANDLE],
+    wait_all: BOOL,
+    timeout: Option<Duration>,
+) -> DWORD {
+    unsafe {
+        WaitForMultipleObjects(
+            handles.len() as DWORD,
+            handles.as_ptr(),
+            wait_all,
+            timeout_to_millis(timeout),
+        )
+    }
+}
+
+/// Converts a timeout to milliseconds, rounding up so that short waits do
+/// not turn into polls, and mapping `None` to `INFINITE`.
+pub(super) fn timeout_to_millis(timeout: Option<Duration>) -> DWORD {
+    let Some(timeout) = timeout else { return INFINITE };
+    let partial = timeout.subsec_nanos() % 1_000_000 != 0;
+    let millis = timeout.as_millis() + u128::from(partial);
+    // INFINITE is u32::MAX, so longer timeouts are capped just below it.
+    millis.min(u128::from(INFINITE - 1)) as DWORD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::open_process;
+    use winapi::um::winnt::SYNCHRONIZE;
+
+    #[test]
+    fn timeouts_round_up() {
+        assert_eq!(timeout_to_millis(None), INFINITE);
+        assert_eq!(timeout_to_millis(Some(Duration::ZERO)), 0);
+        assert_eq!(timeout_to_millis(Some(Duration::from_nanos(1))), 1);
+        assert_eq!(timeout_to_millis(Some(Duration::MAX)), INFINITE - 1);
+    }
+
+    #[test]
+    fn wait_for_child() {
+        let mut child = std::process::Command::new("cmd.exe")
+            .args(["/d", "/q", "/k"])
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let handle = open_process::<crate::access_rights!(SYNCHRONIZE)>(
+            PhantomData,
+            false,
+            &child,
+        )
+        .unwrap();
+        let outcome = handle.wait(Some(Duration::from_millis(10))).unwrap();
+        assert_eq!(outcome, WaitOutcome::TimedOut);
+        // Closing the standard input makes the shell exit.
+        drop(child.stdin.take());
+        assert_eq!(handle.wait(None).unwrap(), WaitOutcome::Signaled);
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn wait_any_reports_exited_child() {
+        let spawn = || {
+            std::process::Command::new("cmd.exe")
+                .args(["/d", "/q", "/k"])
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .unwrap()
+        };
+        let open = |child: &std::process::Child| {
+            open_process::<crate::access_rights!(SYNCHRONIZE)>(
+                PhantomData,
+                false,
+                child,
+            )
+            .unwrap()
+        };
+        let (mut first, mut second) = (spawn(), spawn());
+        let handles = [open(&first), open(&second)];
+        let objects: Vec<&dyn Waitable> =
+            handles.iter().map(|h| h as &dyn Waitable).collect();
+        let short = Some(Duration::from_millis(10));
+        assert_eq!(wait_any(&objects, short).unwrap(), None);
+        drop(second.stdin.take());
+        assert_eq!(wait_any(&objects, None).unwrap(), Some(1));
+        assert_eq!(wait_all(&objects, short).unwrap(), WaitOutcome::TimedOut);
+        drop(first.stdin.take());
+        assert_eq!(wait_all(&objects, None).unwrap(), WaitOutcome::Signaled);
+        first.wait().unwrap();
+        second.wait().unwrap();
+    }
+}
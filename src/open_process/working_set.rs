@@ -0,0 +1,163 @@
+use core::marker::PhantomData;
+
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{
+        memoryapi::SetProcessWorkingSetSizeEx,
+        psapi::EmptyWorkingSet,
+        winnt::{
+            QUOTA_LIMITS_HARDWS_MAX_DISABLE, QUOTA_LIMITS_HARDWS_MAX_ENABLE,
+            QUOTA_LIMITS_HARDWS_MIN_DISABLE, QUOTA_LIMITS_HARDWS_MIN_ENABLE,
+        },
+    },
+};
+
+use super::{
+    Error, HasProcessQueryLimitedInformation, HasProcessSetQuota,
+    ProcessHandle,
+};
+
+/// Options controlling whether the limits passed to
+/// `ProcessHandle::set_working_set_limits` are enforced.
+///
+/// By default, the limits are soft: the process may go below the minimum
+/// when memory is scarce, and above the maximum when it is plentiful.
+/// Options that are left unset keep their current setting.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WorkingSetFlags {
+    hard_min: Option<bool>,
+    hard_max: Option<bool>,
+}
+
+impl WorkingSetFlags {
+    /// Creates a new set of options, leaving the current settings
+    /// unchanged.
+    pub fn new() -> WorkingSetFlags {
+        WorkingSetFlags::default()
+    }
+
+    /// When enabled, the working set never shrinks below the minimum.
+    ///
+    /// This corresponds to `QUOTA_LIMITS_HARDWS_MIN_ENABLE`, or to
+    /// `QUOTA_LIMITS_HARDWS_MIN_DISABLE` when disabled.
+    pub fn hard_min(mut self, yes: bool) -> WorkingSetFlags {
+        self.hard_min = Some(yes);
+        self
+    }
+
+    /// When enabled, the working set never grows beyond the maximum.
+    ///
+    /// This corresponds to `QUOTA_LIMITS_HARDWS_MAX_ENABLE`, or to
+    /// `QUOTA_LIMITS_HARDWS_MAX_DISABLE` when disabled.
+    pub fn hard_max(mut self, yes: bool) -> WorkingSetFlags {
+        self.hard_max = Some(yes);
+        self
+    }
+
+    fn to_dword(self) -> DWORD {
+        let min = match self.hard_min {
+            Some(true) => QUOTA_LIMITS_HARDWS_MIN_ENABLE,
+            Some(false) => QUOTA_LIMITS_HARDWS_MIN_DISABLE,
+            None => 0,
+        };
+        let max = match self.hard_max {
+            Some(true) => QUOTA_LIMITS_HARDWS_MAX_ENABLE,
+            Some(false) => QUOTA_LIMITS_HARDWS_MAX_DISABLE,
+            None => 0,
+        };
+        min | max
+    }
+}
+
+impl<M: HasProcessQueryLimitedInformation + HasProcessSetQuota>
+    ProcessHandle<M>
+{
+    /// Removes as many pages as possible from the working set of the
+    /// process, leaving them to be paged back in when they are accessed
+    /// again.
+    ///
+    /// This corresponds to calling [`EmptyWorkingSet`].
+    ///
+    /// [`EmptyWorkingSet`]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-emptyworkingset
+    pub fn empty_working_set(&self) -> Result<(), Error> {
+        let () = <M as HasProcessQueryLimitedInformation>::ASSERT;
+        let () = <M as HasProcessSetQuota>::ASSERT;
+        if unsafe { EmptyWorkingSet(self.inner.as_ptr()) } == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+impl<M: HasProcessSetQuota> ProcessHandle<M> {
+    /// Sets the minimum and maximum working set sizes of the process, in
+    /// bytes.
+    ///
+    /// Passing `usize::MAX` for both sizes removes as many pages as
+    /// possible from the working set instead, like
+    /// `ProcessHandle::empty_working_set`. Raising the minimum above the
+    /// default requires `SeIncreaseBasePriorityPrivilege`.
+    ///
+    /// This corresponds to calling [`SetProcessWorkingSetSizeEx`].
+    ///
+    /// [`SetProcessWorkingSetSizeEx`]: https://learn.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-setprocessworkingsetsizeex
+    pub fn set_working_set_limits(
+        &self,
+        min: usize,
+        max: usize,
+        flags: WorkingSetFlags,
+    ) -> Result<(), Error> {
+        let () = M::ASSERT;
+        let ok = unsafe {
+            SetProcessWorkingSetSizeEx(
+                self.inner.as_ptr(),
+                min,
+                max,
+                flags.to_dword(),
+            )
+        };
+        if ok == 0 {
+            return Err(Error(PhantomData));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_process::current_process;
+    use winapi::um::memoryapi::GetProcessWorkingSetSizeEx;
+
+    #[test]
+    fn flags_map_to_quota_limits() {
+        assert_eq!(WorkingSetFlags::new().to_dword(), 0);
+        let flags = WorkingSetFlags::new().hard_min(false).hard_max(true);
+        assert_eq!(
+            flags.to_dword(),
+            QUOTA_LIMITS_HARDWS_MIN_DISABLE | QUOTA_LIMITS_HARDWS_MAX_ENABLE
+        );
+    }
+
+    #[test]
+    fn trim_and_restore_limits() {
+        let process = current_process();
+        let (mut min, mut max, mut flags) = (0, 0, 0);
+        let ok = unsafe {
+            GetProcessWorkingSetSizeEx(
+                process.inner.as_ptr(),
+                &mut min,
+                &mut max,
+                &mut flags,
+            )
+        };
+        assert_ne!(ok, 0);
+        process.empty_working_set().unwrap();
+        process
+            .set_working_set_limits(min, max * 2, WorkingSetFlags::new())
+            .unwrap();
+        process
+            .set_working_set_limits(min, max, WorkingSetFlags::new())
+            .unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+//! Listing the processes of all Remote Desktop Services sessions.
+//!
+//! Every interactive logon, whether local or remote, runs in its own
+//! session. Services, which run in session 0, can use [`processes`] to
+//! find the instance of a program that belongs to a particular user:
+//!
+//! ```no_run
+//! use winapi_util::open_process::wts;
+//!
+//! for process in wts::processes().unwrap() {
+//!     println!(
+//!         "{} {:?} in session {}: {:?}",
+//!         process.pid(),
+//!         process.name(),
+//!         process.session_id(),
+//!         process.user_name(),
+//!     );
+//! }
+//! ```
+
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ptr, slice,
+};
+use std::{
+    ffi::{OsStr, OsString},
+    os::windows::ffi::OsStringExt,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{BOOL, DWORD},
+        ntdef::{HANDLE, LPWSTR, PVOID},
+        sddl::ConvertSidToStringSidW,
+        winerror::ERROR_INSUFFICIENT_BUFFER,
+    },
+    um::{
+        errhandlingapi::GetLastError,
+        securitybaseapi::GetLengthSid,
+        winbase::{LocalFree, LookupAccountSidW},
+        winnt::{PSID, SID_NAME_USE},
+    },
+};
+
+use super::{Error, ProcessId};
+
+/// `WTS_PROCESS_INFOW`, which winapi does not declare.
+#[repr(C)]
+#[allow(non_snake_case)]
+struct WTS_PROCESS_INFOW {
+    SessionId: DWORD,
+    ProcessId: DWORD,
+    pProcessName: LPWSTR,
+    pUserSid: PSID,
+}
+
+// winapi does not declare the process enumeration functions.
+#[link(name = "wtsapi32")]
+extern "system" {
+    fn WTSEnumerateProcessesW(
+        hServer: HANDLE,
+        Reserved: DWORD,
+        Version: DWORD,
+        ppProcessInfo: *mut *mut WTS_PROCESS_INFOW,
+        pCount: *mut DWORD,
+    ) -> BOOL;
+    fn WTSFreeMemory(pMemory: PVOID);
+}
+
+/// `WTS_CURRENT_SERVER_HANDLE`, i.e. the local machine.
+const WTS_CURRENT_SERVER_HANDLE: HANDLE = ptr::null_mut();
+
+/// A process listed by [`processes`].
+#[derive(Clone, Debug)]
+pub struct WtsProcess {
+    pid: ProcessId,
+    session_id: u32,
+    name: OsString,
+    // The binary SID of the user, which is only available to callers that
+    // are allowed to query the process.
+    sid: Option<Vec<u8>>,
+}
+
+impl WtsProcess {
+    /// Returns the identifier of the process.
+    pub fn pid(&self) -> ProcessId {
+        self.pid
+    }
+
+    /// Returns the session the process runs in.
+    pub fn session_id(&self) -> u32 {
+        self.session_id
+    }
+
+    /// Returns the file name of the executable of the process.
+    pub fn name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// Returns the SID of the user the process runs as, in its string form
+    /// (e.g. `S-1-5-18`), or `None` if it is not known.
+    pub fn user_sid(&self) -> Option<String> {
+        let sid = self.sid.as_ref()?;
+        let mut string: LPWSTR = ptr::null_mut();
+        let ok = unsafe {
+            ConvertSidToStringSidW(sid.as_ptr() as PSID, &mut string)
+        };
+        if ok == 0 {
+            return None;
+        }
+        let result = unsafe { from_wide_ptr(string) };
+        unsafe { LocalFree(string.cast()) };
+        // SID strings only consist of digits, letters and dashes.
+        Some(result.to_string_lossy().into_owned())
+    }
+
+    /// Returns the name of the user the process runs as, in the form
+    /// `DOMAIN\user`, or `None` if the SID is not known.
+    ///
+    /// Resolving the name may require contacting a domain controller.
+    ///
+    /// This corresponds to calling [`LookupAccountSidW`].
+    ///
+    /// [`LookupAccountSidW`]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-lookupaccountsidw
+    pub fn user_name(&self) -> Result<Option<OsString>, Error> {
+        let Some(sid) = self.sid.as_ref() else {
+            return Ok(None);
+        };
+        let (mut name, mut domain) = (vec![0u16; 64], vec![0u16; 64]);
+        loop {
+            let mut name_len = name.len() as DWORD;
+            let mut domain_len = domain.len() as DWORD;
+            let mut kind: SID_NAME_USE = 0;
+            let ok = unsafe {
+                LookupAccountSidW(
+                    ptr::null(),
+                    sid.as_ptr() as PSID,
+                    name.as_mut_ptr(),
+                    &mut name_len,
+                    domain.as_mut_ptr(),
+                    &mut domain_len,
+                    &mut kind,
+                )
+            };
+            if ok != 0 {
+                let mut user =
+                    OsString::from_wide(&domain[..domain_len as usize]);
+                user.push("\\");
+                user.push(OsString::from_wide(&name[..name_len as usize]));
+                return Ok(Some(user));
+            }
+            if unsafe { GetLastError() } != ERROR_INSUFFICIENT_BUFFER {
+                return Err(Error(PhantomData));
+            }
+            // On failure, the lengths are the required sizes.
+            name.resize(name_len as usize, 0);
+            domain.resize(domain_len as usize, 0);
+        }
+    }
+}
+
+/// An iterator over the processes of all sessions, returned by
+/// [`processes`].
+pub struct WtsProcesses {
+    info: *mut WTS_PROCESS_INFOW,
+    count: usize,
+    next: usize,
+}
+
+/// Lists the processes running on the local machine, across all sessions.
+///
+/// This corresponds to calling [`WTSEnumerateProcessesW`].
+///
+/// [`WTSEnumerateProcessesW`]: https://learn.microsoft.com/en-us/windows/win32/api/wtsapi32/nf-wtsapi32-wtsenumerateprocessesw
+pub fn processes() -> Result<WtsProcesses, Error> {
+    let mut info = ptr::null_mut();
+    let mut count: DWORD = 0;
+    let ok = unsafe {
+        WTSEnumerateProcessesW(
+            WTS_CURRENT_SERVER_HANDLE,
+            0,
+            1,
+            &mut info,
+            &mut count,
+        )
+    };
+    if ok == 0 {
+        return Err(Error(PhantomData));
+    }
+    Ok(WtsProcesses { info, count: count as usize, next: 0 })
+}
+
+impl Iterator for WtsProcesses {
+    type Item = WtsProcess;
+
+    fn next(&mut self) -> Option<WtsProcess> {
+        if self.next == self.count {
+            return None;
+        }
+        let info = unsafe { &*self.info.add(self.next) };
+        self.next += 1;
+        let name = if info.pProcessName.is_null() {
+            OsString::new()
+        } else {
+            unsafe { from_wide_ptr(info.pProcessName) }
+        };
+        let sid = (!info.pUserSid.is_null()).then(|| {
+            let len = unsafe { GetLengthSid(info.pUserSid) } as usize;
+            unsafe { slice::from_raw_parts(info.pUserSid as *const u8, len) }
+                .to_vec()
+        });
+        Some(WtsProcess {
+            pid: ProcessId::from_raw(info.ProcessId),
+            session_id: info.SessionId,
+            name,
+            sid,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl Debug for WtsProcesses {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WtsProcesses")
+            .field("remaining", &(self.count - self.next))
+            .finish()
+    }
+}
+
+impl Drop for WtsProcesses {
+    fn drop(&mut self) {
+        unsafe { WTSFreeMemory(self.info.cast()) };
+    }
+}
+
+/// Copies a NUL-terminated UTF-16 string.
+unsafe fn from_wide_ptr(ptr: *const u16) -> OsString {
+    let mut len = 0;
+    while *ptr.add(len) != 0 {
+        len += 1;
+    }
+    OsString::from_wide(slice::from_raw_parts(ptr, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_process_is_listed() {
+        let current = ProcessId::current();
+        let process = processes()
+            .unwrap()
+            .find(|process| process.pid() == current)
+            .unwrap();
+        assert_eq!(process.session_id(), current.session_id().unwrap());
+        assert!(!process.name().is_empty());
+        assert!(process.user_sid().unwrap().starts_with("S-1-"));
+        assert!(process.user_name().unwrap().is_some());
+    }
+}
@@ -0,0 +1,27 @@
+//! The items are meant to be glob imported:
+//!
+//! ```no_run
+//! use core::marker::PhantomData;
+//! use winapi_util::prelude::*;
+//!
+//! let process = open_process::<QueryLimitedAccess>(
+//!     PhantomData,
+//!     false,
+//!     ProcessId::current(),
+//! )
+//! .unwrap();
+//! println!("{}", process.pid().unwrap());
+//! ```
+
+pub use crate::access_rights;
+pub use crate::open_process::{
+    current_process, open_process, AllAccess, ComptimeAccessRights, Error,
+    ErrorCode, ExitStatus, HasProcessCreateThread, HasProcessDupHandle,
+    HasProcessQueryInformation, HasProcessQueryLimitedInformation,
+    HasProcessSetInformation, HasProcessSetLimitedInformation,
+    HasProcessSetQuota, HasProcessSuspendResume, HasProcessTerminate,
+    HasProcessVmOperation, HasProcessVmRead, HasProcessVmWrite,
+    HasSynchronize, OpenProcess, ProcessHandle, ProcessHandleRef, ProcessId,
+    QueryAccess, QueryLimitedAccess, ReadAccess, RuntimeAccessRights,
+    WaitOutcome, Waitable,
+};